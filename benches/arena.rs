@@ -0,0 +1,41 @@
+// Arena-allocated stack vs. the Box-based one from second.rs: fill up, then drain.
+//
+//     cargo bench --features arena --bench arena
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use too_many_linked_lists::{arena, second};
+
+fn push_pop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_pop");
+    for n in [100, 10_000] {
+        group.bench_with_input(BenchmarkId::new("box", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut list = second::List::new();
+                for i in 0..n {
+                    list.push_front(black_box(i));
+                }
+                while let Some(elem) = list.pop_front() {
+                    black_box(elem);
+                }
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("arena", n), &n, |b, &n| {
+            b.iter(|| {
+                // the arena's drop (one free per chunk) is part of the cost
+                let arena = arena::Arena::new();
+                let mut list = arena::List::new(&arena);
+                for i in 0..n {
+                    list.push_front(black_box(i));
+                }
+                while let Some(elem) = list.pop_front() {
+                    black_box(elem);
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, push_pop);
+criterion_main!(benches);
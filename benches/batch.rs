@@ -0,0 +1,36 @@
+// Pushing a batch into the unsafe queue: one push per element, against
+// push_slice chaining the whole batch up and linking it on in one go.
+//
+//     cargo bench --bench batch
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use too_many_linked_lists::fifth;
+
+fn batch_push(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_push");
+    for n in [16, 256, 4096] {
+        let batch: Vec<u64> = (0..n).collect();
+
+        group.bench_with_input(BenchmarkId::new("push", n), &batch, |b, batch| {
+            b.iter(|| {
+                let mut queue = fifth::List::with_block_size(64);
+                for &elem in batch {
+                    queue.push(elem);
+                }
+                black_box(queue)
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("push_slice", n), &batch, |b, batch| {
+            b.iter(|| {
+                let mut queue = fifth::List::with_block_size(64);
+                queue.push_slice(black_box(batch));
+                black_box(queue)
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, batch_push);
+criterion_main!(benches);
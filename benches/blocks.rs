@@ -0,0 +1,38 @@
+// The unsafe queue at different block sizes: bursts of pushes, then drain. A block
+// size of 1 is one allocator call per node, same as a Box; bigger blocks should
+// pull ahead as the bursts grow.
+//
+//     cargo bench --bench blocks
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use too_many_linked_lists::fifth;
+
+fn bursts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bursts");
+    for n in [64, 1024, 16384] {
+        for block_size in [1, 16, 256] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("k={block_size}"), n),
+                &n,
+                |b, &n| {
+                    b.iter(|| {
+                        let mut queue = fifth::List::with_block_size(block_size);
+                        // a few bursts, so later ones start mid-block
+                        for _ in 0..4 {
+                            for i in 0..n {
+                                queue.push(black_box(i));
+                            }
+                            while let Some(elem) = queue.pop() {
+                                black_box(elem);
+                            }
+                        }
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bursts);
+criterion_main!(benches);
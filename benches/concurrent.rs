@@ -0,0 +1,187 @@
+// Throughput of every queue in the crate that can be shared between threads,
+// against std's channel: 1, 2 or 4 producers each sending 10,000 values to one
+// consumer. One consumer, because the Vyukov queue and std's channel only allow
+// one. Once criterion's done, the means are read back out of its reports and
+// printed as a table of values moved per second.
+//
+//     cargo bench --bench concurrent --features async,parking_lot
+//
+// - two_lock: Michael and Scott's two-lock queue (two_lock.rs)
+// - vyukov: the intrusive MPSC queue (mpsc.rs), nodes allocated up front
+// - mutex_stack: second.rs's stack behind one Mutex (shared.rs). The crate has
+//   no lock-free Treiber stack, so this stands in for the stack
+// - async_channel: the bounded channel (async_queue.rs), driven with block_on,
+//   so a full or empty channel parks the thread like a blocking queue would
+// - std_mpsc: std::sync::mpsc::channel
+
+use std::fs;
+use std::hint;
+use std::path::PathBuf;
+use std::sync::mpsc as std_mpsc;
+use std::thread;
+
+use criterion::{black_box, criterion_group, BenchmarkId, Criterion, Throughput};
+use futures::executor::block_on;
+use too_many_linked_lists::async_queue;
+use too_many_linked_lists::mpsc::{self, Pop};
+use too_many_linked_lists::shared::SharedList;
+use too_many_linked_lists::two_lock::SharedDeque;
+
+const PER_PRODUCER: usize = 10_000;
+const PRODUCERS: [usize; 3] = [1, 2, 4];
+const BACKENDS: [&str; 5] = [
+    "two_lock",
+    "vyukov",
+    "mutex_stack",
+    "async_channel",
+    "std_mpsc",
+];
+
+// Pops until `total` values have come out, spinning while there's nothing yet.
+fn drain(total: usize, mut pop: impl FnMut() -> Option<usize>) {
+    let mut popped = 0;
+    while popped < total {
+        match pop() {
+            Some(value) => {
+                black_box(value);
+                popped += 1;
+            }
+            None => hint::spin_loop(),
+        }
+    }
+}
+
+fn two_lock(producers: usize) {
+    let deque = SharedDeque::new();
+    thread::scope(|scope| {
+        for _ in 0..producers {
+            scope.spawn(|| (0..PER_PRODUCER).for_each(|i| deque.push_back(i)));
+        }
+        drain(producers * PER_PRODUCER, || deque.pop_front());
+    });
+}
+
+fn vyukov(nodes: &[mpsc::Node<usize>], producers: usize) {
+    let mut queue = mpsc::Queue::new();
+    let (producer, mut consumer) = queue.split();
+    thread::scope(|scope| {
+        for chunk in nodes.chunks(PER_PRODUCER).take(producers) {
+            scope.spawn(move || {
+                for node in chunk {
+                    assert!(producer.push(node).is_ok());
+                }
+            });
+        }
+        drain(producers * PER_PRODUCER, || match consumer.pop() {
+            Pop::Data(&value) => Some(value),
+            Pop::Empty | Pop::Inconsistent => None,
+        });
+    });
+}
+
+fn mutex_stack(producers: usize) {
+    let stack = SharedList::new();
+    thread::scope(|scope| {
+        for _ in 0..producers {
+            scope.spawn(|| (0..PER_PRODUCER).for_each(|i| stack.push(i).unwrap()));
+        }
+        drain(producers * PER_PRODUCER, || stack.pop().unwrap());
+    });
+}
+
+fn async_channel(producers: usize) {
+    let (tx, mut rx) = async_queue::channel(1024);
+    thread::scope(|scope| {
+        for _ in 0..producers {
+            let tx = tx.clone();
+            scope.spawn(move || {
+                for i in 0..PER_PRODUCER {
+                    block_on(tx.send(i)).unwrap();
+                }
+            });
+        }
+        drop(tx);
+        // every sender gone means every value's in
+        while let Some(value) = block_on(rx.recv()) {
+            black_box(value);
+        }
+    });
+}
+
+fn std_channel(producers: usize) {
+    let (tx, rx) = std_mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..producers {
+            let tx = tx.clone();
+            scope.spawn(move || (0..PER_PRODUCER).for_each(|i| tx.send(i).unwrap()));
+        }
+        drop(tx);
+        for value in rx {
+            black_box(value);
+        }
+    });
+}
+
+fn throughput(c: &mut Criterion) {
+    let nodes: Vec<_> = (0..PRODUCERS[PRODUCERS.len() - 1] * PER_PRODUCER)
+        .map(mpsc::Node::new)
+        .collect();
+
+    let mut group = c.benchmark_group("throughput");
+    for producers in PRODUCERS {
+        group.throughput(Throughput::Elements((producers * PER_PRODUCER) as u64));
+        let id = |backend| BenchmarkId::new(backend, producers);
+        group.bench_function(id("two_lock"), |b| b.iter(|| two_lock(producers)));
+        group.bench_function(id("vyukov"), |b| b.iter(|| vyukov(&nodes, producers)));
+        group.bench_function(id("mutex_stack"), |b| {
+            b.iter(|| mutex_stack(producers));
+        });
+        group.bench_function(id("async_channel"), |b| {
+            b.iter(|| async_channel(producers));
+        });
+        group.bench_function(id("std_mpsc"), |b| b.iter(|| std_channel(producers)));
+    }
+    group.finish();
+}
+
+// Reads the mean time for each benchmark back out of criterion's reports and
+// prints millions of values per second. Benchmarks filtered out of this run
+// (or never run) show as "-".
+fn summary() {
+    let reports = std::env::var_os("CARGO_TARGET_DIR")
+        .map_or_else(|| PathBuf::from("target"), PathBuf::from)
+        .join("criterion/throughput");
+
+    print!("\n{:<14}", "M values/s");
+    for producers in PRODUCERS {
+        print!("{:>10}", format!("{producers}p:1c"));
+    }
+    println!();
+    for backend in BACKENDS {
+        print!("{backend:<14}");
+        for producers in PRODUCERS {
+            let estimates = reports.join(format!("{backend}/{producers}/new/estimates.json"));
+            let mean_ns = fs::read_to_string(estimates)
+                .ok()
+                .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+                .and_then(|json| json["mean"]["point_estimate"].as_f64());
+            match mean_ns {
+                Some(ns) => {
+                    let values = (producers * PER_PRODUCER) as f64;
+                    print!("{:>10.1}", values / ns * 1e3);
+                }
+                None => print!("{:>10}", "-"),
+            }
+        }
+        println!();
+    }
+}
+
+criterion_group!(benches, throughput);
+
+// criterion_main!, plus the table at the end
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+    summary();
+}
@@ -0,0 +1,47 @@
+// Writers only: threads inserting and removing their own keys in a 64-element
+// Harris list, with nobody taking snapshots. Every insert and remove pays for
+// snapshot_iter's write counters whether or not a snapshot is ever taken, so
+// this is the number to watch when changing how they're kept.
+//
+//     cargo bench --bench harris --features epoch
+
+use std::thread;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use too_many_linked_lists::harris::List;
+
+const KEYS: usize = 64;
+const PAIRS_PER_THREAD: usize = 1_000;
+
+// Each thread inserts and removes keys nobody else uses, around the even keys
+// the list starts with.
+fn writers(list: &List<usize>, threads: usize) {
+    thread::scope(|scope| {
+        for thread in 0..threads {
+            scope.spawn(move || {
+                for i in 0..PAIRS_PER_THREAD {
+                    let key = 2 * ((i * threads + thread) % KEYS) + 1;
+                    black_box(list.insert(key));
+                    black_box(list.remove(&key));
+                }
+            });
+        }
+    });
+}
+
+fn write_cost(c: &mut Criterion) {
+    let mut group = c.benchmark_group("writes");
+    for threads in [1, 4] {
+        group.bench_function(format!("{threads}_threads"), |b| {
+            let list = List::new();
+            for key in 0..KEYS {
+                list.insert(2 * key);
+            }
+            b.iter(|| writers(&list, threads));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, write_cost);
+criterion_main!(benches);
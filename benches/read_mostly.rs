@@ -0,0 +1,84 @@
+// Four readers summing a 64-element routing table over and over while one
+// writer changes it now and then: the copy-on-write list, where readers never
+// touch a lock, against SharedList, where every read takes the mutex.
+//
+//     cargo bench --bench read_mostly --features epoch
+
+use std::thread;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use too_many_linked_lists::read_mostly::ReadMostlyList;
+use too_many_linked_lists::shared::SharedList;
+
+const ENTRIES: usize = 64;
+const READS_PER_READER: usize = 2_000;
+const WRITES: usize = 10;
+
+// Just the two operations both lists have.
+trait Table: Sync {
+    fn sum(&self) -> usize;
+    fn change(&self, value: usize);
+}
+
+impl Table for ReadMostlyList<usize> {
+    fn sum(&self) -> usize {
+        self.read().iter().sum()
+    }
+
+    fn change(&self, value: usize) {
+        self.update(|list| {
+            list.pop_front();
+            list.push_front(value);
+        });
+    }
+}
+
+impl Table for SharedList<usize> {
+    fn sum(&self) -> usize {
+        self.with(|list| list.iter().sum()).unwrap()
+    }
+
+    fn change(&self, value: usize) {
+        self.with(|list| {
+            list.pop_front();
+            list.push_front(value);
+        })
+        .unwrap();
+    }
+}
+
+fn read_heavy(table: &impl Table) {
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..READS_PER_READER {
+                    black_box(table.sum());
+                }
+            });
+        }
+        scope.spawn(|| {
+            for i in 0..WRITES {
+                table.change(black_box(i));
+                thread::yield_now();
+            }
+        });
+    });
+}
+
+fn contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_heavy");
+    group.bench_function("read_mostly", |b| {
+        let table = ReadMostlyList::new();
+        table.update(|list| (0..ENTRIES).for_each(|i| list.push_front(i)));
+        b.iter(|| read_heavy(&table));
+    });
+    group.bench_function("mutex", |b| {
+        let table = SharedList::new();
+        (0..ENTRIES).for_each(|i| table.push(i).unwrap());
+        b.iter(|| read_heavy(&table));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, contention);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+// fifth.rs's queue against the sentinel rebuild of it, which skips the null
+// checks on tail in push and pop. Both allocate one node per push here, so the
+// branches are the only difference that matters.
+//
+//     cargo bench --bench sentinel
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use too_many_linked_lists::{fifth, sentinel};
+
+fn fill_then_drain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fill_then_drain");
+    for n in [16, 1024] {
+        group.bench_with_input(BenchmarkId::new("fifth", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut queue = fifth::List::new();
+                for i in 0..n {
+                    queue.push(black_box(i));
+                }
+                while let Some(elem) = queue.pop() {
+                    black_box(elem);
+                }
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("sentinel", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut queue = sentinel::Queue::new();
+                for i in 0..n {
+                    queue.push(black_box(i));
+                }
+                while let Some(elem) = queue.pop() {
+                    black_box(elem);
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+// One in, one out: the queue keeps going empty, which is exactly when fifth.rs
+// has to touch its tail in pop.
+fn ping_pong(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ping_pong");
+    group.bench_function("fifth", |b| {
+        let mut queue = fifth::List::new();
+        b.iter(|| {
+            queue.push(black_box(1));
+            black_box(queue.pop())
+        });
+    });
+    group.bench_function("sentinel", |b| {
+        let mut queue = sentinel::Queue::new();
+        b.iter(|| {
+            queue.push(black_box(1));
+            black_box(queue.pop())
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, fill_then_drain, ping_pong);
+criterion_main!(benches);
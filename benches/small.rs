@@ -0,0 +1,41 @@
+// SmallList vs. the plain Box stack: fill up, then drain. Lists at or under the
+// inline capacity (8 here) never allocate, which is the whole point; past it, the
+// two should converge.
+//
+//     cargo bench --bench small
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use too_many_linked_lists::{second, small::SmallList};
+
+fn push_pop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_pop");
+    for n in [4, 8, 64] {
+        group.bench_with_input(BenchmarkId::new("box", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut list = second::List::new();
+                for i in 0..n {
+                    list.push_front(black_box(i));
+                }
+                while let Some(elem) = list.pop_front() {
+                    black_box(elem);
+                }
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("small", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut list: SmallList<_, 8> = SmallList::new();
+                for i in 0..n {
+                    list.push(black_box(i));
+                }
+                while let Some(elem) = list.pop() {
+                    black_box(elem);
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, push_pop);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+// Two producers and two consumers hammering one queue: the two-lock queue,
+// where the producers only fight each other for the tail lock and the consumers
+// for the head lock, against a LinkedList behind a single Mutex that all four
+// take turns on.
+//
+//     cargo bench --bench two_lock --features parking_lot
+
+use std::collections::LinkedList;
+use std::sync::Mutex;
+use std::thread;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use too_many_linked_lists::two_lock::SharedDeque;
+
+const PER_PRODUCER: usize = 10_000;
+
+// Just the two operations both queues have.
+trait Shared: Sync {
+    fn push(&self, value: usize);
+    fn pop(&self) -> Option<usize>;
+}
+
+impl Shared for SharedDeque<usize> {
+    fn push(&self, value: usize) {
+        self.push_back(value);
+    }
+
+    fn pop(&self) -> Option<usize> {
+        self.pop_front()
+    }
+}
+
+impl Shared for Mutex<LinkedList<usize>> {
+    fn push(&self, value: usize) {
+        self.lock().unwrap().push_back(value);
+    }
+
+    fn pop(&self) -> Option<usize> {
+        self.lock().unwrap().pop_front()
+    }
+}
+
+fn two_by_two(queue: &impl Shared) {
+    thread::scope(|scope| {
+        for _ in 0..2 {
+            scope.spawn(|| {
+                for i in 0..PER_PRODUCER {
+                    queue.push(black_box(i));
+                }
+            });
+            scope.spawn(|| {
+                let mut popped = 0;
+                while popped < PER_PRODUCER {
+                    match queue.pop() {
+                        Some(value) => {
+                            black_box(value);
+                            popped += 1;
+                        }
+                        None => thread::yield_now(),
+                    }
+                }
+            });
+        }
+    });
+}
+
+fn contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("two_by_two");
+    group.bench_function("two_lock", |b| {
+        b.iter(|| two_by_two(&SharedDeque::new()));
+    });
+    group.bench_function("one_lock", |b| {
+        b.iter(|| two_by_two(&Mutex::new(LinkedList::new())));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, contention);
+criterion_main!(benches);
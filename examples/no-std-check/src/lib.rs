@@ -0,0 +1,68 @@
+//! Proof that the lists build without std: this crate is `#![no_std]` and pulls in
+//! the library with default features off. Build it on its own, so no other
+//! workspace member switches `std` back on through feature unification:
+//!
+//! ```text
+//! cargo build -p no-std-check
+//! cargo build -p no-std-check --target thumbv7em-none-eabihf
+//! ```
+#![no_std]
+
+use too_many_linked_lists::{
+    embedded, fifth, fourth, inline, intrusive, mpsc, second, segmented, third,
+};
+
+/// Touches every core list, so none of them can quietly grow a std dependency.
+#[must_use]
+pub fn exercise() -> i32 {
+    let mut stack = second::List::new();
+    stack.push_front(1);
+
+    let persistent = third::List::new().prepend(2);
+
+    let mut deque = fourth::List::new();
+    deque.push_back(3);
+
+    let mut queue = fifth::List::new();
+    queue.push(4);
+
+    let mut inline: inline::List<i32, 4> = inline::List::new();
+    let _ = inline.try_push(5);
+    let pool: inline::StaticPool<i32, 4> = inline::StaticPool::new();
+    let mut pooled = pool.list();
+    let _ = pooled.try_push(10);
+
+    let mut segmented = segmented::Queue::new();
+    segmented.push(6);
+
+    let node = mpsc::Node::new(8);
+    let mut channel = mpsc::Queue::new();
+    let (producer, mut consumer) = channel.split();
+    let _ = producer.push(&node);
+    let mpsc = match consumer.pop() {
+        mpsc::Pop::Data(&value) => value,
+        mpsc::Pop::Empty | mpsc::Pop::Inconsistent => 0,
+    };
+
+    static SHARED: embedded::Queue<i32> = embedded::Queue::with_capacity(4);
+    let _ = SHARED.try_push(9);
+
+    // no heap at all for this one
+    static LIST: intrusive::List<i32> = intrusive::List::new();
+    static NODE: intrusive::Node<i32> = intrusive::Node::new(7);
+    let intrusive = critical_section::with(|cs| {
+        let _ = LIST.push_front(&NODE, cs);
+        LIST.pop_front(cs).map_or(0, |node| *node.value())
+    });
+
+    stack.pop_front().unwrap_or_default()
+        + persistent.head().copied().unwrap_or_default()
+        + deque.pop_front().unwrap_or_default()
+        + queue.pop().unwrap_or_default()
+        + inline.pop().unwrap_or_default()
+        + pooled.pop().unwrap_or_default()
+        + segmented.pop().unwrap_or_default()
+        + mpsc
+        + SHARED.pop().unwrap_or_default()
+        + intrusive
+}
@@ -0,0 +1,233 @@
+// Exposes the crate's lists to JavaScript for animating in a browser (see index.html).
+//
+// Every operation returns a JSON snapshot of the list's structure right after the op ran:
+//
+//     {
+//       "op": "push_back 5",
+//       "result": null,
+//       "nodes": [{ "id": "n10a0", "label": "2", "strong_count": 1 }, ...],
+//       "links": [{ "from": "n10a0", "to": "n10c0", "kind": "next" }, ...],
+//       "markers": { "head": "n10a0", "tail": "n10c0" },
+//       "groups": []
+//     }
+//
+// Everything but "op" and "result" is the list's own `to_json`, which walks the real nodes:
+// ids are addresses, and each link is drawn from the pointer that's actually there, so a
+// list whose links have gone wrong looks wrong.
+//
+// Build with `wasm-pack build --target web examples/wasm-viz`.
+
+use serde_json::{json, Value};
+use too_many_linked_lists::{fifth, fourth, second};
+use wasm_bindgen::prelude::*;
+
+// `structure` is the list's to_json, with the op and its result added on.
+fn snapshot(structure: String, op: &str, result: Option<i32>) -> String {
+    let mut snapshot: Value =
+        serde_json::from_str(&structure).expect("to_json always writes a JSON object");
+    snapshot["op"] = json!(op);
+    snapshot["result"] = json!(result);
+    snapshot.to_string()
+}
+
+/// The Box-based stack from second.rs.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Stack(second::List<i32>);
+
+#[wasm_bindgen]
+impl Stack {
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_front(&mut self, value: i32) -> String {
+        self.0.push_front(value);
+        snapshot(self.0.to_json(), &format!("push_front {value}"), None)
+    }
+
+    pub fn pop_front(&mut self) -> String {
+        let result = self.0.pop_front();
+        snapshot(self.0.to_json(), "pop_front", result)
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> String {
+        snapshot(self.0.to_json(), "snapshot", None)
+    }
+}
+
+/// The unsafe queue from fifth.rs.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Queue(fifth::List<i32>);
+
+#[wasm_bindgen]
+impl Queue {
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, value: i32) -> String {
+        self.0.push(value);
+        snapshot(self.0.to_json(), &format!("push {value}"), None)
+    }
+
+    pub fn pop(&mut self) -> String {
+        let result = self.0.pop();
+        snapshot(self.0.to_json(), "pop", result)
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> String {
+        snapshot(self.0.to_json(), "snapshot", None)
+    }
+}
+
+/// The Rc<RefCell> deque from fourth.rs.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Deque(fourth::List<i32>);
+
+#[wasm_bindgen]
+impl Deque {
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_front(&mut self, value: i32) -> String {
+        self.0.push_front(value);
+        snapshot(self.0.to_json(), &format!("push_front {value}"), None)
+    }
+
+    pub fn push_back(&mut self, value: i32) -> String {
+        self.0.push_back(value);
+        snapshot(self.0.to_json(), &format!("push_back {value}"), None)
+    }
+
+    pub fn pop_front(&mut self) -> String {
+        let result = self.0.pop_front();
+        snapshot(self.0.to_json(), "pop_front", result)
+    }
+
+    pub fn pop_back(&mut self) -> String {
+        let result = self.0.pop_back();
+        snapshot(self.0.to_json(), "pop_back", result)
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> String {
+        snapshot(self.0.to_json(), "snapshot", None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(snapshot: &str) -> Value {
+        serde_json::from_str(snapshot).unwrap()
+    }
+
+    fn labels(snap: &Value) -> Vec<&str> {
+        snap["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| node["label"].as_str().unwrap())
+            .collect()
+    }
+
+    // (from, to, kind) by label rather than address
+    fn links(snap: &Value) -> Vec<(&str, &str, &str)> {
+        let label = |id: &Value| {
+            snap["nodes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|node| node["id"] == *id)
+                .map(|node| node["label"].as_str().unwrap())
+                .unwrap()
+        };
+        snap["links"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|link| {
+                (
+                    label(&link["from"]),
+                    label(&link["to"]),
+                    link["kind"].as_str().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn marked<'a>(snap: &'a Value, marker: &str) -> Option<&'a str> {
+        let id = &snap["markers"][marker];
+        snap["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|node| node["id"] == *id)
+            .map(|node| node["label"].as_str().unwrap())
+    }
+
+    #[test]
+    fn empty() {
+        let snap = parse(&Deque::new().snapshot());
+        assert_eq!(snap["nodes"], json!([]));
+        assert_eq!(snap["links"], json!([]));
+        assert_eq!(snap["markers"], json!({}));
+    }
+
+    #[test]
+    fn stack() {
+        let mut stack = Stack::new();
+        stack.push_front(5);
+        let snap = parse(&stack.push_front(2));
+        assert_eq!(snap["op"], "push_front 2");
+        assert_eq!(labels(&snap), ["2", "5"]);
+        assert_eq!(links(&snap), [("2", "5", "next")]);
+        assert_eq!(marked(&snap, "head"), Some("2"));
+        assert_eq!(marked(&snap, "tail"), None);
+
+        let snap = parse(&stack.pop_front());
+        assert_eq!(snap["result"], 2);
+        assert_eq!(labels(&snap), ["5"]);
+    }
+
+    #[test]
+    fn queue() {
+        let mut queue = Queue::new();
+        queue.push(1);
+        let snap = parse(&queue.push(2));
+        assert_eq!(marked(&snap, "head"), Some("1"));
+        assert_eq!(marked(&snap, "tail"), Some("2"));
+
+        let snap = parse(&queue.pop());
+        assert_eq!(snap["result"], 1);
+        assert_eq!(marked(&snap, "head"), Some("2"));
+        assert_eq!(marked(&snap, "tail"), Some("2"));
+    }
+
+    #[test]
+    fn deque() {
+        let mut deque = Deque::new();
+        deque.push_back(2);
+        let snap = parse(&deque.push_front(1));
+        assert_eq!(labels(&snap), ["1", "2"]);
+        assert_eq!(links(&snap), [("1", "2", "next"), ("2", "1", "prev")]);
+        assert_eq!(marked(&snap, "tail"), Some("2"));
+
+        let snap = parse(&deque.pop_back());
+        assert_eq!(snap["result"], 2);
+        assert_eq!(links(&snap), []);
+    }
+}
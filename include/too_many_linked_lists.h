@@ -0,0 +1,73 @@
+/*
+ * C bindings for the unsafe queue (src/fifth.rs), exported from src/ffi.rs.
+ *
+ * Elements are type-erased `void *`s. The queue never dereferences or frees them;
+ * tmll_list_free only releases the queue's own nodes.
+ *
+ * Every name is prefixed tmll_ (or Tmll, for types), so as not to clash with the
+ * rest of a C program. Link against the crate built as a C library with its `ffi`
+ * feature, e.g.
+ *     cargo rustc --release --lib --features ffi --crate-type staticlib
+ */
+
+#ifndef TOO_MANY_LINKED_LISTS_H
+#define TOO_MANY_LINKED_LISTS_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque handle to a queue. Only ever use it through a pointer. */
+typedef struct TmllList TmllList;
+
+/* Allocates a new, empty queue. Free it with tmll_list_free. */
+TmllList *tmll_list_new(void);
+
+/* Pushes elem onto the back of the queue. */
+void tmll_list_push(TmllList *list, void *elem);
+
+/* Pops the front of the queue, or returns NULL if it is empty. */
+void *tmll_list_pop(TmllList *list);
+
+/* Frees the queue and its nodes (not the elements). NULL is a no-op. */
+void tmll_list_free(TmllList *list);
+
+/*
+ * Only with the crate's `repr-c` feature: the nodes themselves, for walking a queue
+ * from C. Fields are in this order, with C's usual padding.
+ */
+typedef struct TmllListNode {
+    struct TmllListNode *next; /* NULL after the tail */
+    void *block;               /* the block the node was carved from, NULL for one
+                                  from tmll_list_node_new; leave it be */
+    void *elem;
+} TmllListNode;
+
+/*
+ * Takes the queue apart: stores its first and last nodes (NULL if empty) and returns
+ * its length. The TmllList handle is freed, the nodes are not. You may relink them,
+ * and add nodes from tmll_list_node_new, but not drop any, before giving them back
+ * with tmll_list_from_raw_parts.
+ */
+size_t tmll_list_into_raw_parts(TmllList *list, TmllListNode **head, TmllListNode **tail);
+
+/*
+ * A node holding elem, for building a chain in C. Don't malloc nodes yourself: they're
+ * freed by the queue that adopts them, with Rust's allocator.
+ */
+TmllListNode *tmll_list_node_new(void *elem);
+
+/*
+ * Makes a queue of a chain of nodes from tmll_list_into_raw_parts and/or
+ * tmll_list_node_new. tail must be the last node and len the number of nodes. Free it
+ * with tmll_list_free.
+ */
+TmllList *tmll_list_from_raw_parts(TmllListNode *head, TmllListNode *tail, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TOO_MANY_LINKED_LISTS_H */
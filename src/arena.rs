@@ -0,0 +1,235 @@
+// A stack whose nodes live in an arena the caller hands us, instead of each getting its own Box.
+//
+// Allocating from an arena is just bumping an index into a big pre-allocated chunk, so pushes
+// are about as cheap as they get. The flip side: the arena owns every node ever allocated from
+// it and only frees them all at once, when the arena itself is dropped. Popping doesn't free
+// anything, so the popped element stays in the arena too — we hand back a reference to it.
+//
+// No Drop impl (and no drop-recursion worries): dropping a List is a no-op.
+//
+// Lists sharing an arena can also trade nodes. Moving a node from one list to another is a
+// couple of pointer swaps — the arena never hears about it. That's the scheduler run-queue
+// pattern: tasks hop between queues all day, and nothing gets allocated or freed.
+
+use typed_arena::Arena as TypedArena;
+
+/// The arena a [`List`] allocates from. Several lists can share one.
+pub type Arena<'arena, T> = TypedArena<Node<'arena, T>>;
+
+/// A stack allocating its nodes from an [`Arena`].
+///
+/// The list borrows the arena, so it can't outlive it:
+///
+/// ```compile_fail
+/// use too_many_linked_lists::arena::{Arena, List};
+///
+/// let list = {
+///     let arena = Arena::new();
+///     let mut list = List::new(&arena);
+///     list.push_front(1);
+///     list
+/// }; // arena dropped here while `list` still points into it
+/// ```
+///
+/// Popped elements live in the arena, so they can't outlive it either:
+///
+/// ```compile_fail
+/// use too_many_linked_lists::arena::{Arena, List};
+///
+/// let elem = {
+///     let arena = Arena::new();
+///     let mut list = List::new(&arena);
+///     list.push_front(1);
+///     list.pop_front()
+/// };
+/// ```
+///
+/// But they *can* outlive the list:
+///
+/// ```
+/// use too_many_linked_lists::arena::{Arena, List};
+///
+/// let arena = Arena::new();
+/// let elem = {
+///     let mut list = List::new(&arena);
+///     list.push_front(1);
+///     list.pop_front()
+/// };
+/// assert_eq!(elem, Some(&mut 1));
+/// ```
+pub struct List<'arena, T> {
+    arena: &'arena Arena<'arena, T>,
+    head: Link<'arena, T>,
+}
+
+// The arena hands out &'arena mut references, which are as good as a Box for our purposes —
+// except nobody frees them.
+type Link<'arena, T> = Option<&'arena mut Node<'arena, T>>;
+
+// References can't be null either, so this is exactly as small as the Box version.
+const _: () = assert!(size_of::<Link<'static, u64>>() == size_of::<usize>());
+const _: () = assert!(size_of::<Node<'static, u64>>() == size_of::<(u64, usize)>());
+
+pub struct Node<'arena, T> {
+    elem: T,
+    next: Link<'arena, T>,
+}
+
+impl<'arena, T> List<'arena, T> {
+    #[must_use]
+    pub const fn new(arena: &'arena Arena<'arena, T>) -> Self {
+        Self { arena, head: None }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let next = self.head.take();
+        self.head = Some(self.arena.alloc(Node { elem, next }));
+    }
+
+    /// Pops the front element. It stays put in the arena; you get a reference to it.
+    pub fn pop_front(&mut self) -> Option<&'arena mut T> {
+        self.head.take().map(|node| {
+            self.head = node.next.take();
+            &mut node.elem
+        })
+    }
+
+    /// Moves our front node onto the front of `to`, in O(1) and without allocating.
+    /// Returns false if there was nothing to move.
+    pub fn transplant_front(&mut self, to: &mut Self) -> bool {
+        match self.head.take() {
+            Some(node) => {
+                self.head = node.next.take();
+                node.next = to.head.take();
+                to.head = Some(node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.elem)
+    }
+
+    pub fn iter(&self) -> Iter<'_, 'arena, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+}
+
+// Same two-lifetime deal as silly2.rs: Node holds a &'arena mut, so it's invariant in 'arena.
+pub struct Iter<'a, 'arena, T> {
+    next: Option<&'a Node<'arena, T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, '_, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arena, List};
+
+    #[test]
+    fn basics() {
+        let arena = Arena::new();
+        let mut list = List::new(&arena);
+        assert_eq!(list.pop_front(), None);
+
+        list.push_front(1);
+        list.push_front(2);
+        list.push_front(3);
+        assert_eq!(list.peek(), Some(&3));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+
+        assert_eq!(list.pop_front(), Some(&mut 3));
+        *list.peek_mut().unwrap() = 20;
+        assert_eq!(list.pop_front(), Some(&mut 20));
+
+        list.push_front(4);
+        assert_eq!(list.pop_front(), Some(&mut 4));
+        assert_eq!(list.pop_front(), Some(&mut 1));
+        assert_eq!(list.pop_front(), None);
+    }
+
+    #[test]
+    fn shared_arena() {
+        let arena = Arena::new();
+        let mut odds = List::new(&arena);
+        let popped = {
+            let mut evens = List::new(&arena);
+            for i in 0..10 {
+                if i % 2 == 0 {
+                    evens.push_front(i);
+                } else {
+                    odds.push_front(i);
+                }
+            }
+            evens.pop_front()
+        };
+
+        // popped elements outlive their list, as long as the arena's around
+        assert_eq!(popped, Some(&mut 8));
+        assert_eq!(
+            odds.iter().copied().collect::<Vec<_>>(),
+            vec![9, 7, 5, 3, 1]
+        );
+        assert_eq!(arena.len(), 10);
+    }
+
+    #[test]
+    fn transplant() {
+        let arena = Arena::new();
+        let mut ready = List::new(&arena);
+        let mut waiting = List::new(&arena);
+        for task in ["a", "b", "c"] {
+            ready.push_front(task);
+        }
+
+        // shuffle tasks back and forth; the arena shouldn't grow a byte
+        for _ in 0..100 {
+            while ready.transplant_front(&mut waiting) {}
+            while waiting.transplant_front(&mut ready) {}
+        }
+        assert_eq!(arena.len(), 3);
+
+        assert!(ready.transplant_front(&mut waiting));
+        assert_eq!(ready.iter().copied().collect::<Vec<_>>(), vec!["b", "a"]);
+        assert_eq!(waiting.iter().copied().collect::<Vec<_>>(), vec!["c"]);
+        assert!(!List::new(&arena).transplant_front(&mut ready));
+    }
+
+    #[test]
+    fn drop_is_free() {
+        use std::rc::Rc;
+
+        // Nodes (and their elements) are only dropped along with the arena.
+        let canary = Rc::new(());
+        {
+            // (no drop(arena) — the list's borrow of it lasts as long as the arena itself)
+            let arena = Arena::new();
+            {
+                let mut list = List::new(&arena);
+                for _ in 0..1_000_000 {
+                    list.push_front(Rc::clone(&canary));
+                }
+            }
+            assert_eq!(Rc::strong_count(&canary), 1_000_001);
+        }
+        assert_eq!(Rc::strong_count(&canary), 1);
+    }
+}
@@ -0,0 +1,572 @@
+// fifth.rs's queue, shared between async tasks. Every handle is both ends: a
+// `Stream` of whatever's been pushed, and a `Sink` to push into. So a queue can
+// sit right in the middle of a `forward` pipeline:
+//
+//     let queue = Queue::bounded(16);
+//     let pump = source.map(Ok).forward(queue.clone());
+//     let drain = queue.for_each(handle);
+//
+// The bound is where the backpressure comes from: once the queue is full, the
+// Sink side stops being ready until something's popped. Closing the Sink (which
+// `forward` does when its source runs dry) closes the queue for everyone, and
+// the Stream ends once it's handed out what was left.
+//
+// `channel` is the same queue with the two ends kept apart, for when "who can
+// push" and "who pops" should be different tasks: any number of Senders, one
+// Receiver. Dropping the last Sender closes it, so the Receiver sees the end of
+// the stream; dropping the Receiver closes it too, and every Sender, including
+// ones blocked waiting for room, gets its element back.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::future::poll_fn;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::fifth::{List, WhenFull};
+
+pub struct Queue<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    // What start_send couldn't push, because another handle filled the queue
+    // between our poll_ready and now. It goes in ahead of anything else we send.
+    unsent: Option<T>,
+}
+
+struct Shared<T> {
+    list: Owned<T>,
+    closed: bool,
+    // only counted for channels, whose last Sender closes the queue
+    senders: usize,
+    // tasks waiting for an element, for room, and for the queue to close
+    pop_wakers: Vec<Waker>,
+    push_wakers: Vec<Waker>,
+    close_wakers: Vec<Waker>,
+}
+
+// Same as embedded.rs: the list never hands its nodes out, so it can move
+// between threads along with its Ts.
+struct Owned<T>(List<T>);
+
+unsafe impl<T: Send> Send for Owned<T> {}
+
+// `unsent` is only ever moved in and out, never pinned.
+impl<T> Unpin for Queue<T> {}
+
+/// The queue's been closed, so nothing more can go in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+impl fmt::Display for Closed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the queue is closed")
+    }
+}
+
+impl std::error::Error for Closed {}
+
+impl<T> Queue<T> {
+    /// An unbounded queue: the Sink side is always ready.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_list(List::new())
+    }
+
+    /// A queue for at most `capacity` elements at a time.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero: nothing could ever be sent.
+    #[must_use]
+    pub fn bounded(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+        Self::from_list(List::with_capacity(capacity, WhenFull::Panic))
+    }
+
+    fn from_list(list: List<T>) -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(Shared {
+                list: Owned(list),
+                closed: false,
+                senders: 0,
+                pop_wakers: Vec::new(),
+                push_wakers: Vec::new(),
+                close_wakers: Vec::new(),
+            })),
+            unsent: None,
+        }
+    }
+
+    /// Pushes without waiting. The element comes back if the queue is full or
+    /// closed.
+    pub fn try_push(&self, elem: T) -> Result<(), T> {
+        lock(&self.shared).try_push(elem)
+    }
+
+    /// Pops without waiting.
+    pub fn try_pop(&self) -> Option<T> {
+        lock(&self.shared).pop()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        lock(&self.shared).list.0.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stops any more pushes, through every handle. What's already queued can
+    /// still be popped; after that the Stream ends.
+    pub fn close(&self) {
+        lock(&self.shared).close();
+    }
+
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        lock(&self.shared).closed
+    }
+
+    // Pushes the held-back element, if there is one.
+    fn poll_unsent(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        let Some(elem) = self.unsent.take() else {
+            return Poll::Ready(Ok(()));
+        };
+        let mut shared = lock(&self.shared);
+        if shared.closed {
+            return Poll::Ready(Err(Closed));
+        }
+        match shared.try_push(elem) {
+            Ok(()) => Poll::Ready(Ok(())),
+            Err(elem) => {
+                self.unsent = Some(elem);
+                register(&mut shared.push_wakers, cx.waker());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Shared<T> {
+    fn has_room(&self) -> bool {
+        let list = &self.list.0;
+        list.capacity().is_none_or(|capacity| list.len() < capacity)
+    }
+
+    fn try_push(&mut self, elem: T) -> Result<(), T> {
+        if self.closed {
+            return Err(elem);
+        }
+        self.list.0.try_push(elem)?;
+        wake_all(&mut self.pop_wakers);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let elem = self.list.0.pop()?;
+        wake_all(&mut self.push_wakers);
+        Some(elem)
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        wake_all(&mut self.pop_wakers);
+        wake_all(&mut self.push_wakers);
+        wake_all(&mut self.close_wakers);
+    }
+
+    fn poll_pop(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Some(elem) = self.pop() {
+            return Poll::Ready(Some(elem));
+        }
+        if self.closed {
+            return Poll::Ready(None);
+        }
+        register(&mut self.pop_wakers, cx.waker());
+        Poll::Pending
+    }
+}
+
+// A panic while holding the lock can't leave the list half-relinked (fifth.rs's
+// operations don't call out to user code midway), so a poisoned lock is fine.
+fn lock<T>(shared: &Mutex<Shared<T>>) -> MutexGuard<'_, Shared<T>> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn register(wakers: &mut Vec<Waker>, waker: &Waker) {
+    if !wakers.iter().any(|queued| queued.will_wake(waker)) {
+        wakers.push(waker.clone());
+    }
+}
+
+fn wake_all(wakers: &mut Vec<Waker>) {
+    wakers.drain(..).for_each(Waker::wake);
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Another handle on the same queue. An element the original is holding back
+// stays with the original.
+impl<T> Clone for Queue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+            unsent: None,
+        }
+    }
+}
+
+impl<T> Stream for Queue<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        lock(&self.shared).poll_pop(cx)
+    }
+}
+
+impl<T> Sink<T> for Queue<T> {
+    type Error = Closed;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        let this = self.get_mut();
+        match this.poll_unsent(cx) {
+            Poll::Ready(Ok(())) => {}
+            other => return other,
+        }
+        let mut shared = lock(&this.shared);
+        if shared.closed {
+            return Poll::Ready(Err(Closed));
+        }
+        if shared.has_room() {
+            return Poll::Ready(Ok(()));
+        }
+        register(&mut shared.push_wakers, cx.waker());
+        Poll::Pending
+    }
+
+    fn start_send(self: Pin<&mut Self>, elem: T) -> Result<(), Closed> {
+        let this = self.get_mut();
+        let mut shared = lock(&this.shared);
+        if shared.closed {
+            return Err(Closed);
+        }
+        // full after all: hold on to it, and push it on the next poll
+        if let Err(elem) = shared.try_push(elem) {
+            this.unsent = Some(elem);
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        self.get_mut().poll_unsent(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        let this = self.get_mut();
+        match this.poll_unsent(cx) {
+            Poll::Ready(Ok(())) => {
+                this.close();
+                Poll::Ready(Ok(()))
+            }
+            other => other,
+        }
+    }
+}
+
+/// A channel holding at most `capacity` elements at a time, backed by the
+/// same linked queue as `Queue`.
+///
+/// # Panics
+///
+/// If `capacity` is zero.
+#[must_use]
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let queue = Queue::bounded(capacity);
+    lock(&queue.shared).senders = 1;
+    (
+        Sender {
+            shared: Arc::clone(&queue.shared),
+        },
+        Receiver {
+            shared: queue.shared,
+        },
+    )
+}
+
+pub struct Sender<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// The channel closed before the element could go in; here it is back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sending on a closed channel")
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for SendError<T> {}
+
+impl<T> Sender<T> {
+    /// Waits for room, then pushes. If the channel closes first, whether before
+    /// or during the wait, the element comes back.
+    pub async fn send(&self, elem: T) -> Result<(), SendError<T>> {
+        let mut elem = Some(elem);
+        poll_fn(|cx| {
+            let mut shared = lock(&self.shared);
+            let unsent = elem.take().expect("send polled after it finished");
+            if shared.closed {
+                return Poll::Ready(Err(SendError(unsent)));
+            }
+            match shared.try_push(unsent) {
+                Ok(()) => Poll::Ready(Ok(())),
+                Err(unsent) => {
+                    elem = Some(unsent);
+                    register(&mut shared.push_wakers, cx.waker());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Pushes without waiting. The element comes back if the channel is full or
+    /// closed.
+    pub fn try_send(&self, elem: T) -> Result<(), T> {
+        lock(&self.shared).try_push(elem)
+    }
+
+    /// Finishes once the channel is closed, by anyone: a `close`, or the
+    /// Receiver going away. Lets a producer notice nobody's listening any more
+    /// without having something to send.
+    pub async fn closed(&self) {
+        poll_fn(|cx| {
+            let mut shared = lock(&self.shared);
+            if shared.closed {
+                return Poll::Ready(());
+            }
+            register(&mut shared.close_wakers, cx.waker());
+            Poll::Pending
+        })
+        .await;
+    }
+
+    /// Closes the channel for every Sender. The Receiver still gets what's
+    /// already in it.
+    pub fn close(&self) {
+        lock(&self.shared).close();
+    }
+
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        lock(&self.shared).closed
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        lock(&self.shared).senders += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+// The last Sender out closes the channel, so the Receiver's stream ends.
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut shared = lock(&self.shared);
+        shared.senders -= 1;
+        if shared.senders == 0 {
+            shared.close();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// The next element, or None once the channel is closed and empty.
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| lock(&self.shared).poll_pop(cx)).await
+    }
+
+    pub fn try_recv(&mut self) -> Option<T> {
+        lock(&self.shared).pop()
+    }
+
+    /// Stops the Senders: their blocked and future sends fail. What's already
+    /// queued can still be received.
+    pub fn close(&mut self) {
+        lock(&self.shared).close();
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        lock(&self.shared).list.0.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        lock(&self.shared).poll_pop(cx)
+    }
+}
+
+// Nobody's popping any more: close, so the Senders stop rather than wait
+// forever for room. The elements still queued are dropped along with the list.
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut shared = lock(&self.shared);
+        shared.close();
+        shared.list.0.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Closed, Queue};
+    use futures::executor::block_on;
+    use futures::task::noop_waker_ref;
+    use futures::{future, stream, SinkExt, StreamExt};
+    use std::task::{Context, Poll};
+    use std::thread;
+
+    #[test]
+    fn forward_through_it() {
+        let queue = Queue::bounded(4);
+        let pump = stream::iter(0..100).map(Ok).forward(queue.clone());
+        let (pumped, drained) = block_on(future::join(pump, queue.collect::<Vec<_>>()));
+        assert_eq!(pumped, Ok(()));
+        assert!(drained.into_iter().eq(0..100));
+    }
+
+    #[test]
+    fn backpressure() {
+        let queue = Queue::bounded(2);
+        let mut sink = queue.clone();
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        block_on(sink.send(1)).unwrap();
+        block_on(sink.send(2)).unwrap();
+        // full: not ready for a third until something's popped
+        assert_eq!(sink.poll_ready_unpin(&mut cx), Poll::Pending);
+        assert_eq!(queue.try_pop(), Some(1));
+        assert_eq!(sink.poll_ready_unpin(&mut cx), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn another_handle_fills_it_first() {
+        let queue = Queue::bounded(1);
+        let mut sink = queue.clone();
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        assert_eq!(sink.poll_ready_unpin(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(queue.try_push(1), Ok(()));
+        // the slot's gone, so 2 waits in the handle until there's room
+        sink.start_send_unpin(2).unwrap();
+        assert_eq!(sink.poll_flush_unpin(&mut cx), Poll::Pending);
+        assert_eq!(queue.try_pop(), Some(1));
+        assert_eq!(sink.poll_flush_unpin(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(queue.try_pop(), Some(2));
+    }
+
+    #[test]
+    fn close() {
+        let mut queue = Queue::new();
+        block_on(queue.send(1)).unwrap();
+        queue.close();
+        assert!(queue.is_closed());
+        assert_eq!(block_on(queue.send(2)), Err(Closed));
+        assert_eq!(queue.try_push(3), Err(3));
+        // what was in there still comes out, then the stream ends
+        assert_eq!(block_on(queue.collect::<Vec<_>>()), [1]);
+    }
+
+    #[test]
+    fn across_threads() {
+        let queue = Queue::bounded(8);
+        let mut sink = queue.clone();
+        let producer = thread::spawn(move || {
+            block_on(async {
+                for i in 0..1000 {
+                    sink.send(i).await.unwrap();
+                }
+                SinkExt::close(&mut sink).await.unwrap();
+            });
+        });
+        let received = block_on(queue.collect::<Vec<_>>());
+        producer.join().unwrap();
+        assert!(received.into_iter().eq(0..1000));
+    }
+
+    #[test]
+    fn channel_basics() {
+        let (tx, mut rx) = super::channel(2);
+        let tx2 = tx.clone();
+        block_on(async {
+            tx.send(1).await.unwrap();
+            tx2.send(2).await.unwrap();
+            assert_eq!(tx.try_send(3), Err(3));
+            assert_eq!(rx.recv().await, Some(1));
+            assert_eq!(rx.len(), 1);
+        });
+        // the last Sender gone ends the stream, after what's left
+        drop((tx, tx2));
+        assert_eq!(block_on(rx.collect::<Vec<_>>()), [2]);
+    }
+
+    #[test]
+    fn close_wakes_blocked_senders() {
+        let (tx, mut rx) = super::channel(1);
+        block_on(tx.send(1)).unwrap();
+
+        let blocked = thread::spawn({
+            let tx = tx.clone();
+            move || block_on(tx.send(2))
+        });
+        let watcher = thread::spawn({
+            let tx = tx.clone();
+            move || block_on(tx.closed())
+        });
+        rx.close();
+        assert_eq!(blocked.join().unwrap(), Err(super::SendError(2)));
+        watcher.join().unwrap();
+        assert!(tx.is_closed());
+        // already in there before the close
+        assert_eq!(rx.try_recv(), Some(1));
+        assert_eq!(block_on(rx.recv()), None);
+    }
+
+    #[test]
+    fn dropping_the_receiver_disconnects() {
+        let (tx, rx) = super::channel(4);
+        let elem = std::sync::Arc::new(());
+        block_on(tx.send(std::sync::Arc::clone(&elem))).unwrap();
+        drop(rx);
+        // the queued element went with it, not with the last Sender
+        assert_eq!(std::sync::Arc::strong_count(&elem), 1);
+        assert!(tx.is_closed());
+        assert!(block_on(tx.send(elem)).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+// The Box stack and the unsafe queue again, but with their nodes coming out of a
+// bumpalo::Bump the caller hands over. Allocation is a pointer bump, and there's
+// no per-node free at all: the memory goes back in one go when the Bump is
+// dropped (or reset). Elements still get dropped properly — only the node memory
+// is left for the Bump to reclaim.
+//
+// Both lists borrow the Bump, so the borrow checker won't let them outlive it.
+
+use core::marker::PhantomData;
+use core::ptr;
+
+use bumpalo::boxed::Box;
+use bumpalo::Bump;
+
+/// The Box stack from `second`, with bumpalo's `Box` in place of the global one.
+/// Its `Box` runs the node's destructor but never frees the memory.
+///
+/// ```compile_fail
+/// use bumpalo::Bump;
+/// use too_many_linked_lists::bump::Stack;
+///
+/// let stack = {
+///     let bump = Bump::new();
+///     let mut stack = Stack::new_in(&bump);
+///     stack.push_front(1);
+///     stack
+/// }; // bump dropped while `stack` still points into it
+/// ```
+pub struct Stack<'bump, T> {
+    bump: &'bump Bump,
+    head: StackLink<'bump, T>,
+}
+
+type StackLink<'bump, T> = Option<Box<'bump, StackNode<'bump, T>>>;
+
+struct StackNode<'bump, T> {
+    elem: T,
+    next: StackLink<'bump, T>,
+}
+
+impl<'bump, T> Stack<'bump, T> {
+    #[must_use]
+    pub const fn new_in(bump: &'bump Bump) -> Self {
+        Self { bump, head: None }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let next = self.head.take();
+        self.head = Some(Box::new_in(StackNode { elem, next }, self.bump));
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.take().map(|node| {
+            let node = Box::into_inner(node);
+            self.head = node.next;
+            node.elem
+        })
+    }
+
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+}
+
+// Same recursion problem as second.rs: each Box would drop the next one from inside
+// its own destructor. Unlink iteratively instead.
+impl<T> Stack<'_, T> {
+    /// Drops the elements. Their nodes' memory goes back when the Bump does.
+    pub fn clear(&mut self) {
+        let mut link = self.head.take();
+        while let Some(mut node) = link {
+            link = node.next.take();
+        }
+    }
+}
+
+impl<T> Drop for Stack<'_, T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// The unsafe queue from `fifth`, with nodes from a Bump. Popping moves the element
+/// out and simply forgets the node.
+///
+/// ```compile_fail
+/// use bumpalo::Bump;
+/// use too_many_linked_lists::bump::Queue;
+///
+/// let bump = Bump::new();
+/// let mut queue = Queue::new_in(&bump);
+/// queue.push(1);
+/// drop(bump); // still borrowed by `queue`
+/// queue.pop();
+/// ```
+pub struct Queue<'bump, T> {
+    bump: &'bump Bump,
+    head: *mut QueueNode<T>,
+    tail: *mut QueueNode<T>,
+    // we own Ts, even though we only hold raw pointers to them
+    _elems: PhantomData<T>,
+}
+
+struct QueueNode<T> {
+    elem: T,
+    next: *mut QueueNode<T>,
+}
+
+impl<'bump, T> Queue<'bump, T> {
+    #[must_use]
+    pub const fn new_in(bump: &'bump Bump) -> Self {
+        Self {
+            bump,
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+            _elems: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let new_tail: *mut _ = self.bump.alloc(QueueNode {
+            elem,
+            next: ptr::null_mut(),
+        });
+        unsafe {
+            if self.tail.is_null() {
+                self.head = new_tail;
+            } else {
+                (*self.tail).next = new_tail;
+            }
+        }
+        self.tail = new_tail;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.head.is_null() {
+            return None;
+        }
+        unsafe {
+            let old_head = self.head;
+            self.head = (*old_head).next;
+            if self.head.is_null() {
+                self.tail = ptr::null_mut();
+            }
+            // Bitwise move out of the node. The Bump never runs destructors and we
+            // never look at this node again, so the elem can't be dropped twice.
+            Some(ptr::read(&(*old_head).elem))
+        }
+    }
+
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        unsafe { self.head.as_ref().map(|node| &node.elem) }
+    }
+}
+
+// The Bump frees node memory but won't drop our elements, so that's on us.
+impl<T> Queue<'_, T> {
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T> Drop for Queue<'_, T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Queue, Stack};
+    use bumpalo::Bump;
+    use std::rc::Rc;
+
+    #[test]
+    fn stack() {
+        let bump = Bump::new();
+        let mut stack = Stack::new_in(&bump);
+        assert_eq!(stack.pop_front(), None);
+
+        stack.push_front(1);
+        stack.push_front(2);
+        assert_eq!(stack.peek(), Some(&2));
+        assert_eq!(stack.pop_front(), Some(2));
+        stack.push_front(3);
+        assert_eq!(stack.pop_front(), Some(3));
+        assert_eq!(stack.pop_front(), Some(1));
+        assert_eq!(stack.pop_front(), None);
+    }
+
+    #[test]
+    fn queue() {
+        let bump = Bump::new();
+        let mut queue = Queue::new_in(&bump);
+        assert_eq!(queue.pop(), None);
+
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.pop(), Some(1));
+        queue.push(3);
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn lists_share_a_bump() {
+        let bump = Bump::new();
+        let mut stack = Stack::new_in(&bump);
+        let mut queue = Queue::new_in(&bump);
+        for i in 0..100 {
+            stack.push_front(i);
+            queue.push(i);
+        }
+        assert_eq!(stack.pop_front(), Some(99));
+        assert_eq!(queue.pop(), Some(0));
+    }
+
+    #[test]
+    fn elements_still_get_dropped() {
+        let canary = Rc::new(());
+        let bump = Bump::new();
+        {
+            let mut stack = Stack::new_in(&bump);
+            let mut queue = Queue::new_in(&bump);
+            for _ in 0..10 {
+                stack.push_front(Rc::clone(&canary));
+                queue.push(Rc::clone(&canary));
+            }
+            drop(queue.pop());
+            assert_eq!(Rc::strong_count(&canary), 20);
+        }
+        // the lists are gone, the bump isn't: elements dropped, memory not yet freed
+        assert_eq!(Rc::strong_count(&canary), 1);
+        assert!(bump.allocated_bytes() > 0);
+    }
+
+    #[test]
+    fn long_stack_drop() {
+        let bump = Bump::new();
+        let mut stack = Stack::new_in(&bump);
+        for i in 0..1_000_000 {
+            stack.push_front(i);
+        }
+    }
+}
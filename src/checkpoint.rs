@@ -0,0 +1,318 @@
+// Checkpointing a work queue to disk, in a compact binary format that notices
+// when it's been cut short or damaged. Any list that serializes works; the queue
+// (fifth.rs) and the deque (fourth.rs) are the ones this is for.
+//
+// A checkpoint is:
+//
+//     b"TMLL"    magic, so a random file is turned away up front
+//     3          format version, one byte
+//     len        how many bytes the list takes, 8 bytes little-endian
+//     len crc    CRC-32 of those 8 bytes, 4 bytes little-endian
+//     ...        the list in postcard: a varint length, then the elements
+//     crc        CRC-32 of the list bytes, 4 bytes little-endian
+//
+// (Version 2 had bincode with varint integers in the middle.)
+//
+// Saving streams: it walks the list twice, once to size it and once to write
+// it, working the checksum out on the bytes as they go past. Loading reads the
+// list's bytes in whole before decoding them. postcard's streaming reader needs
+// a scratch buffer as big as the biggest string in the list, which there's no
+// telling up front, and this way the checksum is checked before any of it is
+// decoded.
+//
+// The byte count is what lets a damaged file be told from a short one: running
+// out before that many bytes is truncation, and anything wrong within them is
+// corruption. It has its own checksum so that damage to it is caught before
+// it's trusted.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const MAGIC: &[u8; 4] = b"TMLL";
+const VERSION: u8 = 3;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// Doesn't start with the magic bytes.
+    NotACheckpoint,
+    /// Written by a version of the format this one doesn't read.
+    UnsupportedVersion(u8),
+    /// The input ended before the checkpoint did.
+    Truncated,
+    /// The bytes don't add up: a checksum is off, the elements don't decode,
+    /// or they don't take up the number of bytes the header says.
+    Corrupt,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "checkpoint i/o failed: {err}"),
+            Self::NotACheckpoint => f.write_str("not a checkpoint"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported checkpoint version {version}")
+            }
+            Self::Truncated => f.write_str("checkpoint is cut short"),
+            Self::Corrupt => f.write_str("checkpoint is corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            Self::Truncated
+        } else {
+            Self::Io(err)
+        }
+    }
+}
+
+// Only ever decoding errors: by the time postcard sees the bytes, they're all
+// there. (Saving's write errors are kept aside, see `Crc32::error`.)
+impl From<postcard::Error> for Error {
+    fn from(_: postcard::Error) -> Self {
+        Self::Corrupt
+    }
+}
+
+/// Writes `list` as a checkpoint. Give it a `BufWriter` for files.
+pub fn save<L: Serialize + ?Sized>(list: &L, mut writer: impl Write) -> Result<(), Error> {
+    let len = (postcard::experimental::serialized_size(list)? as u64).to_le_bytes();
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    writer.write_all(&len)?;
+    writer.write_all(&crc32(&len).to_le_bytes())?;
+    let mut body = Crc32::new(&mut writer);
+    if let Err(err) = postcard::to_io(list, &mut body) {
+        return Err(body.error.map_or_else(|| err.into(), Error::from));
+    }
+    let crc = body.crc;
+    writer.write_all(&crc.to_le_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a checkpoint written by `save`.
+pub fn load<L: DeserializeOwned>(mut reader: impl Read) -> Result<L, Error> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::NotACheckpoint);
+    }
+    let mut version = [0];
+    reader.read_exact(&mut version)?;
+    if version[0] != VERSION {
+        return Err(Error::UnsupportedVersion(version[0]));
+    }
+
+    let mut len = [0; 8];
+    reader.read_exact(&mut len)?;
+    let mut len_crc = [0; 4];
+    reader.read_exact(&mut len_crc)?;
+    if crc32(&len) != u32::from_le_bytes(len_crc) {
+        return Err(Error::Corrupt);
+    }
+    let len = u64::from_le_bytes(len);
+
+    // Reading to the end of what's there, rather than allocating len bytes up
+    // front, since a file that's been cut short can't make us allocate more
+    // than it has.
+    let mut bytes = Vec::new();
+    (&mut reader).take(len).read_to_end(&mut bytes)?;
+    if (bytes.len() as u64) < len {
+        return Err(Error::Truncated);
+    }
+    let mut expected = [0; 4];
+    reader.read_exact(&mut expected)?;
+    if crc32(&bytes) != u32::from_le_bytes(expected) {
+        return Err(Error::Corrupt);
+    }
+    let (list, rest) = postcard::take_from_bytes(&bytes)?;
+    if !rest.is_empty() {
+        // the elements ended early
+        return Err(Error::Corrupt);
+    }
+    Ok(list)
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new(());
+    crc.update(bytes);
+    crc.crc
+}
+
+// Passes bytes through to a writer, keeping a running CRC-32 (the zlib/PNG
+// one) of them.
+struct Crc32<S> {
+    stream: S,
+    crc: u32,
+    // postcard's to_io turns a failed write into its own error and drops the
+    // io::Error, so the real one is kept here.
+    error: Option<io::Error>,
+}
+
+impl<S> Crc32<S> {
+    const fn new(stream: S) -> Self {
+        Self {
+            stream,
+            crc: 0,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        let mut crc = !self.crc;
+        for &byte in bytes {
+            crc ^= u32::from(byte);
+            for _ in 0..8 {
+                crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+            }
+        }
+        self.crc = !crc;
+    }
+}
+
+impl<W: Write> Write for Crc32<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.stream.write(buf) {
+            Ok(written) => {
+                self.update(&buf[..written]);
+                Ok(written)
+            }
+            Err(err) => {
+                let kind = err.kind();
+                self.error = Some(err);
+                Err(kind.into())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, load, save, Error};
+    use crate::{fifth, fourth};
+
+    fn queue(n: u32) -> fifth::List<u32> {
+        let mut queue = fifth::List::new();
+        for i in 0..n {
+            queue.push(i);
+        }
+        queue
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        // the standard check value
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn round_trip() {
+        for n in [0, 1, 300] {
+            let mut file = Vec::new();
+            save(&queue(n), &mut file).unwrap();
+            let back: fifth::List<u32> = load(&file[..]).unwrap();
+            assert!(back.into_iter().eq(0..n));
+        }
+
+        let mut deque = fourth::List::new();
+        deque.extend_back(["a".to_string(), "b".to_string()]);
+        let mut file = Vec::new();
+        save(&deque, &mut file).unwrap();
+        let mut back: fourth::List<String> = load(&file[..]).unwrap();
+        assert_eq!(back.pop_front().as_deref(), Some("a"));
+        assert_eq!(back.pop_front().as_deref(), Some("b"));
+        assert_eq!(back.pop_front(), None);
+    }
+
+    #[test]
+    fn compact() {
+        let mut file = Vec::new();
+        save(&queue(100), &mut file).unwrap();
+        // header, byte count and its checksum, one length byte, one byte per
+        // small number, checksum
+        assert_eq!(file.len(), 5 + 8 + 4 + 1 + 100 + 4);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut file = Vec::new();
+        save(&queue(100), &mut file).unwrap();
+        for len in 0..file.len() {
+            let loaded = load::<fifth::List<u32>>(&file[..len]);
+            assert!(matches!(loaded, Err(Error::Truncated)), "{len}: {loaded:?}");
+        }
+    }
+
+    #[test]
+    fn corrupt() {
+        let mut file = Vec::new();
+        save(&queue(100), &mut file).unwrap();
+
+        // any single flipped bit past the header is caught, and called what it
+        // is: damage, not a short file
+        for byte in 5..file.len() {
+            for bit in 0..8 {
+                let mut damaged = file.clone();
+                damaged[byte] ^= 1 << bit;
+                let loaded = load::<fifth::List<u32>>(&damaged[..]);
+                assert!(
+                    matches!(loaded, Err(Error::Corrupt)),
+                    "{byte}.{bit}: {loaded:?}"
+                );
+            }
+        }
+
+        // an element count far past the end: more than the header's byte count
+        // allows, so it's stopped there rather than read until the input runs out
+        let count = 5 + 8 + 4;
+        assert_eq!(file[count], 100);
+        let mut damaged = file.clone();
+        // u32::MAX as a varint, with both checksums fixed up so that it's the
+        // decoding that has to catch it
+        damaged[count] = 0xFF;
+        damaged.splice(count + 1..count + 1, [0xFF, 0xFF, 0xFF, 0x0F]);
+        let end = damaged.len() - 4;
+        let body = (end - count) as u64;
+        damaged[5..13].copy_from_slice(&body.to_le_bytes());
+        damaged[13..17].copy_from_slice(&crc32(&body.to_le_bytes()).to_le_bytes());
+        let crc = crc32(&damaged[count..end]).to_le_bytes();
+        damaged[end..].copy_from_slice(&crc);
+        assert!(matches!(
+            load::<fifth::List<u32>>(&damaged[..]),
+            Err(Error::Corrupt)
+        ));
+
+        let mut damaged = file.clone();
+        damaged[0] = b'X';
+        assert!(matches!(
+            load::<fifth::List<u32>>(&damaged[..]),
+            Err(Error::NotACheckpoint)
+        ));
+        let mut damaged = file;
+        damaged[4] = 2;
+        assert!(matches!(
+            load::<fifth::List<u32>>(&damaged[..]),
+            Err(Error::UnsupportedVersion(2))
+        ));
+    }
+}
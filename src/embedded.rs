@@ -0,0 +1,143 @@
+// fifth.rs's queue, shareable between an interrupt handler and the main loop on a
+// microcontroller. There's no std Mutex there, so every operation runs inside
+// `critical_section::with` instead: with interrupts off (or a global lock, on a
+// host), nothing can get in halfway through a relink.
+//
+//     static RX: Queue<u8> = Queue::with_capacity(64);
+//
+//     fn on_uart_rx(byte: u8) { let _ = RX.try_push(byte); }  // in the ISR
+//     while let Some(byte) = RX.pop() { handle(byte) }         // in the main loop
+//
+// A bounded queue of up to fifth::MAX_BOUNDED_BLOCK (256) elements allocates
+// all its nodes as one block, the first time anything is pushed, and reuses
+// their slots after that: every push after the first is allocation-free. Push
+// something from the main loop first (and pop it) if the ISR mustn't allocate.
+// A bigger queue takes its nodes a block of 256 at a time, so any push, in an
+// ISR or not, may be the one that needs a new block.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+use crate::fifth::{List, WhenFull};
+
+pub struct Queue<T>(Mutex<RefCell<Owned<T>>>);
+
+// fifth::List isn't Send: after split_at and friends, nodes from one block can end
+// up in two lists, and the block's live count isn't atomic. This list never shares
+// its nodes — nothing that hands nodes out (take_all, split_at, into_iter, ...) is
+// exposed here — so moving it between contexts along with its Ts is fine.
+struct Owned<T>(List<T>);
+
+unsafe impl<T: Send> Send for Owned<T> {}
+
+impl<T> Queue<T> {
+    /// An unbounded queue, a node (and an allocation) per push.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(Owned(List::new()))))
+    }
+
+    /// A queue for at most `capacity` elements. `try_push` hands elements back
+    /// once it's full, and `push` panics, so ISRs want `try_push`.
+    #[must_use]
+    pub const fn with_capacity(capacity: usize) -> Self {
+        Self(Mutex::new(RefCell::new(Owned(List::with_capacity(
+            capacity,
+            WhenFull::Panic,
+        )))))
+    }
+
+    /// # Panics
+    ///
+    /// If the queue is bounded and full.
+    pub fn push(&self, elem: T) {
+        critical_section::with(|cs| self.0.borrow_ref_mut(cs).0.push(elem));
+    }
+
+    pub fn try_push(&self, elem: T) -> Result<(), T> {
+        critical_section::with(|cs| self.0.borrow_ref_mut(cs).0.try_push(elem))
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        critical_section::with(|cs| self.0.borrow_ref_mut(cs).0.pop())
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        critical_section::with(|cs| self.0.borrow_ref(cs).0.len())
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        critical_section::with(|cs| self.0.borrow_ref(cs).0.is_empty())
+    }
+
+    /// Copies out the front element.
+    #[must_use]
+    pub fn peek(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        critical_section::with(|cs| self.0.borrow_ref(cs).0.peek().cloned())
+    }
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Queue;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let queue = Queue::new();
+        assert_eq!(queue.pop(), None);
+        queue.push(1);
+        queue.push(2);
+        assert_eq!((queue.len(), queue.peek()), (2, Some(1)));
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn bounded() {
+        static QUEUE: Queue<u8> = Queue::with_capacity(2);
+        assert_eq!(QUEUE.try_push(1), Ok(()));
+        assert_eq!(QUEUE.try_push(2), Ok(()));
+        assert_eq!(QUEUE.try_push(3), Err(3));
+        assert_eq!(QUEUE.pop(), Some(1));
+        assert_eq!(QUEUE.try_push(3), Ok(()));
+    }
+
+    // A thread standing in for the interrupt handler, handing bytes to the "main
+    // loop" through a static.
+    #[test]
+    fn isr_to_main_loop() {
+        static RX: Queue<u8> = Queue::with_capacity(16);
+
+        let isr = thread::spawn(|| {
+            for byte in 0..100 {
+                while RX.try_push(byte).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut received = Vec::new();
+        while received.len() < 100 {
+            match RX.pop() {
+                Some(byte) => received.push(byte),
+                None => thread::yield_now(),
+            }
+        }
+        isr.join().unwrap();
+        assert!(received.into_iter().eq(0..100));
+    }
+}
@@ -0,0 +1,234 @@
+// A C-facing wrapper around the unsafe queue from fifth.rs.
+//
+// C has no generics, so the queue stores type-erased `void *` elements. The queue never looks
+// at (or frees!) what the pointers point to — that's the C caller's problem. All we own is the
+// chain of nodes, which tmll_list_free cleans up.
+//
+// With the `repr-c` feature, C can also take a queue apart into its nodes, walk
+// and relink them, add nodes of its own from tmll_list_node_new, and make a queue of
+// the result. Nodes are always allocated on the Rust side, so that Rust can free
+// them.
+//
+// Only with the `ffi` feature, so the #[no_mangle] symbols aren't in every build
+// of the crate, and all prefixed tmll_ so they can't clash with anything else a C
+// program links. The matching header lives in include/too_many_linked_lists.h.
+
+use alloc::boxed::Box;
+use core::ffi::c_void;
+use core::ptr;
+
+use crate::fifth::List;
+#[cfg(feature = "repr-c")]
+use crate::fifth::Node;
+
+/// Allocates a new, empty queue. Free it with `tmll_list_free`.
+#[no_mangle]
+pub extern "C" fn tmll_list_new() -> *mut List<*mut c_void> {
+    Box::into_raw(Box::new(List::new()))
+}
+
+/// Pushes `elem` onto the back of the queue.
+///
+/// # Safety
+///
+/// `list` must be a pointer returned by `tmll_list_new` that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tmll_list_push(list: *mut List<*mut c_void>, elem: *mut c_void) {
+    if let Some(list) = list.as_mut() {
+        list.push(elem);
+    }
+}
+
+/// Pops the front of the queue, or returns NULL if the queue is empty.
+///
+/// Note that NULL is also a perfectly pushable element, so callers storing NULLs can't tell
+/// the difference. Don't do that.
+///
+/// # Safety
+///
+/// `list` must be a pointer returned by `tmll_list_new` that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tmll_list_pop(list: *mut List<*mut c_void>) -> *mut c_void {
+    list.as_mut().and_then(List::pop).unwrap_or(ptr::null_mut())
+}
+
+/// Frees the queue and all of its nodes. The elements themselves are left alone.
+///
+/// # Safety
+///
+/// `list` must be a pointer returned by `tmll_list_new` that hasn't been freed yet, or NULL (which
+/// is a no-op). Using `list` afterwards is a use-after-free.
+#[no_mangle]
+pub unsafe extern "C" fn tmll_list_free(list: *mut List<*mut c_void>) {
+    if !list.is_null() {
+        // Reclaim the Box so Rust's drop glue walks the chain for us.
+        drop(Box::from_raw(list));
+    }
+}
+
+/// Takes the queue apart for C to walk: the head and tail nodes go in `*head`
+/// and `*tail` (both NULL if it's empty), and the length comes back. `list` is
+/// freed; the nodes aren't. Hand them back with `tmll_list_from_raw_parts`.
+///
+/// # Safety
+///
+/// `list` must be a pointer returned by `tmll_list_new` that hasn't been freed yet, and
+/// `head` and `tail` must be valid to write to.
+#[cfg(feature = "repr-c")]
+#[no_mangle]
+pub unsafe extern "C" fn tmll_list_into_raw_parts(
+    list: *mut List<*mut c_void>,
+    head: *mut *mut Node<*mut c_void>,
+    tail: *mut *mut Node<*mut c_void>,
+) -> usize {
+    let (first, last, len) = Box::from_raw(list).into_raw_parts();
+    head.write(first);
+    tail.write(last);
+    len
+}
+
+/// Makes a node for C to link into a chain of its own, or into one from
+/// `tmll_list_into_raw_parts`, before handing it to `tmll_list_from_raw_parts`. There's no
+/// freeing it by hand: the queue that adopts it does that.
+#[cfg(feature = "repr-c")]
+#[no_mangle]
+pub extern "C" fn tmll_list_node_new(elem: *mut c_void) -> *mut Node<*mut c_void> {
+    Node::new(elem)
+}
+
+/// Puts a queue together from a chain of nodes: ones from `tmll_list_into_raw_parts`,
+/// from `tmll_list_node_new`, or both, linked however C likes. Free it with
+/// `tmll_list_free`, as usual. Debug builds check the chain adds up first, and abort
+/// if it doesn't.
+///
+/// # Safety
+///
+/// Every node in the chain must come from `tmll_list_into_raw_parts` or
+/// `tmll_list_node_new`, and be given back exactly once. `tail` must be the last
+/// node, and `len` the number of nodes.
+#[cfg(feature = "repr-c")]
+#[no_mangle]
+pub unsafe extern "C" fn tmll_list_from_raw_parts(
+    head: *mut Node<*mut c_void>,
+    tail: *mut Node<*mut c_void>,
+    len: usize,
+) -> *mut List<*mut c_void> {
+    Box::into_raw(Box::new(List::from_raw_chain(head, tail, len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basics() {
+        let mut a = 1;
+        let mut b = 2;
+
+        unsafe {
+            let list = tmll_list_new();
+            assert!(tmll_list_pop(list).is_null());
+
+            tmll_list_push(list, ptr::addr_of_mut!(a).cast());
+            tmll_list_push(list, ptr::addr_of_mut!(b).cast());
+
+            assert_eq!(*tmll_list_pop(list).cast::<i32>(), 1);
+            assert_eq!(*tmll_list_pop(list).cast::<i32>(), 2);
+            assert!(tmll_list_pop(list).is_null());
+
+            // freeing a non-empty queue must not touch the elements
+            tmll_list_push(list, ptr::addr_of_mut!(a).cast());
+            tmll_list_free(list);
+        }
+
+        assert_eq!(a, 1);
+    }
+
+    #[test]
+    fn null_list() {
+        unsafe {
+            tmll_list_push(ptr::null_mut(), ptr::null_mut());
+            assert!(tmll_list_pop(ptr::null_mut()).is_null());
+            tmll_list_free(ptr::null_mut());
+        }
+    }
+
+    // What the header says a node looks like.
+    #[cfg(feature = "repr-c")]
+    #[repr(C)]
+    struct TmllListNode {
+        next: *mut TmllListNode,
+        block: *mut c_void,
+        elem: *mut c_void,
+    }
+
+    #[cfg(feature = "repr-c")]
+    #[test]
+    fn c_builds_a_chain() {
+        let mut elems = [10, 20, 30];
+
+        unsafe {
+            // a chain of C's own...
+            let [a, b, c] = elems
+                .each_mut()
+                .map(|elem| tmll_list_node_new(ptr::from_mut(elem).cast()));
+            (*a.cast::<TmllListNode>()).next = b.cast();
+            (*b.cast::<TmllListNode>()).next = c.cast();
+            assert!((*a.cast::<TmllListNode>()).block.is_null());
+            let list = tmll_list_from_raw_parts(a, c, 3);
+            assert_eq!(*tmll_list_pop(list).cast::<i32>(), 10);
+
+            // ...and spliced into one of ours
+            let mut new_tail = 40;
+            let (mut head, mut tail) = (ptr::null_mut(), ptr::null_mut());
+            assert_eq!(tmll_list_into_raw_parts(list, &mut head, &mut tail), 2);
+            let d = tmll_list_node_new(ptr::addr_of_mut!(new_tail).cast());
+            (*tail.cast::<TmllListNode>()).next = d.cast();
+            let list = tmll_list_from_raw_parts(head, d, 3);
+            tmll_list_push(list, ptr::addr_of_mut!(new_tail).cast());
+            assert_eq!(*tmll_list_pop(list).cast::<i32>(), 20);
+            assert_eq!(*tmll_list_pop(list).cast::<i32>(), 30);
+            assert_eq!(*tmll_list_pop(list).cast::<i32>(), 40);
+            // frees the pushed node, which came from a block
+            tmll_list_free(list);
+        }
+    }
+
+    #[cfg(feature = "repr-c")]
+    #[test]
+    fn c_walks_the_chain() {
+        let mut elems = [10, 20, 30];
+
+        unsafe {
+            let list = tmll_list_new();
+            for elem in &mut elems {
+                tmll_list_push(list, ptr::from_mut(elem).cast());
+            }
+            let (mut head, mut tail) = (ptr::null_mut(), ptr::null_mut());
+            assert_eq!(tmll_list_into_raw_parts(list, &mut head, &mut tail), 3);
+
+            // walk it the way C would, through the header's struct
+            let mut seen = Vec::new();
+            let mut node = head.cast::<TmllListNode>();
+            while !node.is_null() {
+                seen.push(*(*node).elem.cast::<i32>());
+                node = (*node).next;
+            }
+            assert_eq!(seen, [10, 20, 30]);
+            assert_eq!((*tail.cast::<TmllListNode>()).next, ptr::null_mut());
+
+            // and move the head round to the back before handing it over again
+            let first = head.cast::<TmllListNode>();
+            let second = (*first).next;
+            (*first).next = ptr::null_mut();
+            (*tail.cast::<TmllListNode>()).next = first;
+
+            let list = tmll_list_from_raw_parts(second.cast(), first.cast(), 3);
+            assert_eq!(*tmll_list_pop(list).cast::<i32>(), 20);
+            assert_eq!(*tmll_list_pop(list).cast::<i32>(), 30);
+            assert_eq!(*tmll_list_pop(list).cast::<i32>(), 10);
+            assert!(tmll_list_pop(list).is_null());
+            tmll_list_free(list);
+        }
+    }
+}
@@ -1,4 +1,15 @@
-use std::ptr;
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use alloc::boxed::Box;
+use alloc::collections::LinkedList;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Add, AddAssign, Index, IndexMut};
+use core::ptr;
+
+use crate::trace::trace;
 
 // an OK unsafe queue
 
@@ -6,16 +17,175 @@ use std::ptr;
 // own the same Node. That's no good, and we're tired of the Rc-RefCell
 // solution. We're resorting to unsafety.
 // Also, head is following suit. Mixing ptrs with refs is messy.
-struct List<T> {
+pub struct List<T> {
     head: Link<T>,
     tail: Link<T>,
+    // the block we're currently carving new nodes out of (null until the first push)
+    block: *mut Block<T>,
+    block_size: usize,
+    len: usize,
+    // None when unbounded
+    capacity: Option<usize>,
+    when_full: WhenFull,
+    #[cfg(feature = "stats")]
+    stats: crate::stats::Counters,
+}
+
+/// What `push` does when a queue made `with_capacity` is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhenFull {
+    /// Refuse, loudly. Use `try_push` to get the element back instead.
+    Panic,
+    /// Double the capacity and carry on.
+    Grow,
 }
 
 type Link<T> = *mut Node<T>;
 
-struct Node<T> {
-    elem: T,
+/// The biggest block `List::with_capacity` allocates its nodes in.
+pub const MAX_BOUNDED_BLOCK: usize = 256;
+
+/// A queue node. It only shows up outside this module as a raw pointer: the
+/// ones `List::into_raw_parts` hands out, or a standalone node from
+/// [`Node::new`].
+///
+/// Opaque by default. With the `repr-c` feature it's `#[repr(C)]`, and C can
+/// walk a chain from `into_raw_parts` itself:
+///
+/// ```c
+/// struct node {
+///     struct node *next;  /* NULL after the tail */
+///     void *block;        /* the block it was carved from, NULL if standalone;
+///                            don't touch */
+///     T elem;
+/// };
+/// ```
+///
+/// C may read and write `elem`, and relink nodes (keeping the tail and the
+/// count right for `from_raw_chain`). It can add nodes too, but only ones made
+/// by `Node::new`: nodes are freed with Rust's allocator, so C can't `malloc`
+/// its own.
+#[cfg_attr(feature = "repr-c", repr(C))]
+pub struct Node<T> {
     next: Link<T>,
+    block: *mut Block<T>,
+    elem: T,
+}
+
+// elem, next, and the block it came from (see below), or null for a standalone
+// node from Node::new.
+const _: () = assert!(size_of::<Node<u64>>() == size_of::<(u64, usize, usize)>());
+const _: () = assert!(align_of::<Node<u64>>() == align_of::<(u64, usize, usize)>());
+
+impl<T> Node<T> {
+    /// A node of its own, not carved out of any block, for building a chain by
+    /// hand to give to [`List::from_raw_chain`]. The list that adopts it frees
+    /// it like any other node.
+    #[must_use = "the node leaks unless a list adopts it"]
+    pub fn new(elem: T) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            next: ptr::null_mut(),
+            block: ptr::null_mut(),
+            elem,
+        }))
+    }
+}
+
+// Nodes don't get an allocation each: they're carved out of blocks of
+// `block_size` slots, one allocator call per block. A block is just this header
+// with the slots laid out right behind it.
+//
+// Nodes hop between queues all the time (concat, split_at, partition...), so a
+// block can't belong to any one queue. Instead each node remembers its block,
+// and the block counts its live nodes. It's freed when the last one goes — but
+// only once it's `retired`, i.e. its queue has stopped carving from it.
+//
+// Until then, freed slots go on the block's free list (threaded through their
+// `next` fields) and are handed out again before any fresh ones. So a queue that
+// never holds more than `block_size` elements at once allocates one block and
+// then never calls the allocator again, however much traffic goes through it.
+// A longer queue fills its block, retires it and starts another: steady push/pop
+// traffic through it costs one allocation per `block_size` pushes.
+struct Block<T> {
+    live: usize,
+    carved: usize,
+    capacity: usize,
+    retired: bool,
+    // freed slots, ready to be carved again
+    free: Link<T>,
+    _nodes: PhantomData<Node<T>>,
+}
+
+impl<T> Block<T> {
+    // (layout of the whole block, offset of the first slot)
+    fn layout(capacity: usize) -> (Layout, usize) {
+        Layout::new::<Self>()
+            .extend(Layout::array::<Node<T>>(capacity).expect("block size overflows"))
+            .expect("block size overflows")
+    }
+
+    fn new(capacity: usize) -> *mut Self {
+        let (layout, _) = Self::layout(capacity);
+        unsafe {
+            let block = alloc(layout).cast::<Self>();
+            if block.is_null() {
+                handle_alloc_error(layout);
+            }
+            block.write(Self {
+                live: 0,
+                carved: 0,
+                capacity,
+                retired: false,
+                free: ptr::null_mut(),
+                _nodes: PhantomData,
+            });
+            block
+        }
+    }
+
+    // Hands out a freed slot if there is one, else the next never-used one, or
+    // null if the block is full.
+    unsafe fn carve(block: *mut Self) -> Link<T> {
+        let slot = if !(*block).free.is_null() {
+            let slot = (*block).free;
+            (*block).free = ptr::addr_of!((*slot).next).read();
+            slot
+        } else if (*block).carved < (*block).capacity {
+            let (_, offset) = Self::layout((*block).capacity);
+            let first = block.cast::<u8>().add(offset).cast::<Node<T>>();
+            (*block).carved += 1;
+            first.add((*block).carved - 1)
+        } else {
+            return ptr::null_mut();
+        };
+        (*block).live += 1;
+        slot
+    }
+
+    // One of our nodes is gone (its elem already moved out): its slot goes on the
+    // free list, unless nobody will be carving from us again.
+    unsafe fn release(block: *mut Self, slot: Link<T>) {
+        (*block).live -= 1;
+        if (*block).retired {
+            Self::free_if_done(block);
+        } else {
+            ptr::addr_of_mut!((*slot).next).write((*block).free);
+            (*block).free = slot;
+        }
+    }
+
+    // Its queue is done carving from it.
+    unsafe fn retire(block: *mut Self) {
+        (*block).retired = true;
+        Self::free_if_done(block);
+    }
+
+    unsafe fn free_if_done(block: *mut Self) {
+        if (*block).retired && (*block).live == 0 {
+            let (layout, _) = Self::layout((*block).capacity);
+            dealloc(block.cast(), layout);
+        }
+    }
 }
 
 // For our singly-linked queue, we can either:
@@ -26,30 +196,203 @@ struct Node<T> {
 // traversal. Instead, we'll push to the back, which moves the tail forwards
 // at O(1).
 impl<T> List<T> {
+    // One node per block: an allocator call per push, like a plain Box would be.
     pub const fn new() -> Self {
+        Self::with_block_size(1)
+    }
+
+    /// A queue that allocates its nodes `block_size` at a time, so a burst of n
+    /// pushes costs n / `block_size` allocator calls instead of n. Slots freed
+    /// by pops are reused, so a queue that stays under `block_size` elements
+    /// only ever allocates once.
+    ///
+    /// ```
+    /// use too_many_linked_lists::fifth::List;
+    ///
+    /// let mut list = List::with_block_size(64);
+    /// for i in 0..1000 {
+    ///     list.push(i);
+    /// }
+    /// assert_eq!(list.pop(), Some(0));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `block_size` is zero.
+    #[must_use]
+    pub const fn with_block_size(block_size: usize) -> Self {
+        assert!(block_size > 0, "block size must be non-zero");
         Self {
             head: ptr::null_mut(), // nullable mut ptr
             tail: ptr::null_mut(),
+            block: ptr::null_mut(),
+            block_size,
+            len: 0,
+            capacity: None,
+            when_full: WhenFull::Grow,
+            #[cfg(feature = "stats")]
+            stats: crate::stats::Counters::new(),
+        }
+    }
+
+    /// A queue that holds at most `capacity` elements, for use as a fixed-size
+    /// buffer: `try_push` hands the element back once it's full, and `push` does
+    /// whatever `when_full` says. Nodes come in blocks of `capacity`, up to
+    /// `MAX_BOUNDED_BLOCK`: a buffer that small takes a single allocation and
+    /// then reuses its slots, and a bigger one doesn't grab its whole capacity
+    /// up front.
+    ///
+    /// ```
+    /// use too_many_linked_lists::fifth::{List, WhenFull};
+    ///
+    /// let mut buffer = List::with_capacity(2, WhenFull::Panic);
+    /// assert_eq!(buffer.try_push(1), Ok(()));
+    /// assert_eq!(buffer.try_push(2), Ok(()));
+    /// assert_eq!(buffer.try_push(3), Err(3));
+    /// ```
+    ///
+    /// Only the methods that add elements (`push`, `try_push`, `insert_sorted`)
+    /// look at the capacity. Splicing whole queues together with `+=` or
+    /// `interleave` doesn't, and the queues `split_at`, `partition` and `chunks`
+    /// hand back are unbounded.
+    #[must_use]
+    pub const fn with_capacity(capacity: usize, when_full: WhenFull) -> Self {
+        // (no `clamp` in a const fn)
+        let block_size = if capacity == 0 {
+            1
+        } else if capacity > MAX_BOUNDED_BLOCK {
+            MAX_BOUNDED_BLOCK
+        } else {
+            capacity
+        };
+        let mut list = Self::with_block_size(block_size);
+        list.capacity = Some(capacity);
+        list.when_full = when_full;
+        list
+    }
+
+    /// None if the queue is unbounded.
+    #[must_use]
+    pub const fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drops every element. The capacity and block size stay, and so does the
+    /// current block, with all its slots free again: a queue that fits in one
+    /// block (a bounded one up to `MAX_BOUNDED_BLOCK`) fills right back up
+    /// without allocating. A bigger one reuses that block, then allocates.
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Pushes at the tail, or hands `elem` back if the queue is at capacity.
+    pub fn try_push(&mut self, elem: T) -> Result<(), T> {
+        if self.capacity.is_some_and(|capacity| self.len >= capacity) {
+            return Err(elem);
+        }
+        self.push(elem);
+        Ok(())
+    }
+
+    // Called before adding `additional` elements: deals with a full queue per
+    // `when_full`.
+    fn make_room(&mut self, additional: usize) {
+        let Some(capacity) = &mut self.capacity else {
+            return;
+        };
+        let needed = self.len.saturating_add(additional);
+        if needed <= *capacity {
+            return;
+        }
+        match self.when_full {
+            WhenFull::Panic => panic!("queue is full (capacity {capacity})"),
+            WhenFull::Grow => {
+                while *capacity < needed {
+                    *capacity = capacity.saturating_mul(2).max(1);
+                }
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    // Carves a node for `elem` out of the current block, starting a new block if
+    // that one's used up.
+    fn alloc_node(&mut self, elem: T) -> Link<T> {
+        unsafe {
+            let mut node = if self.block.is_null() {
+                ptr::null_mut()
+            } else {
+                Block::carve(self.block)
+            };
+            if node.is_null() {
+                self.retire_block();
+                self.block = Block::new(self.block_size);
+                trace!(ptr = ?self.block, "new block");
+                node = Block::carve(self.block);
+            }
+            node.write(Node {
+                elem,
+                next: ptr::null_mut(),
+                block: self.block,
+            });
+            node
+        }
+    }
+
+    // Moves the elem out of an unlinked node and gives its slot back to its block.
+    unsafe fn free_node(node: Link<T>) -> T {
+        let block = (*node).block;
+        if block.is_null() {
+            // standalone, from Node::new
+            return Box::from_raw(node).elem;
+        }
+        let Node { elem, .. } = node.read();
+        Block::release(block, node);
+        elem
+    }
+
+    fn retire_block(&mut self) {
+        let block = mem::replace(&mut self.block, ptr::null_mut());
+        if !block.is_null() {
+            unsafe { Block::retire(block) };
         }
     }
 
     // push at the tail
     pub fn push(&mut self, new_elem: T) {
+        self.make_room(1);
         unsafe {
-            // create a Box and convert it into a raw ptr
-            let new_tail = Box::into_raw(Box::new(Node {
-                elem: new_elem,
-                next: ptr::null_mut(),
-            }));
+            let new_tail = self.alloc_node(new_elem);
+            trace!(ptr = ?new_tail, "alloc node");
 
             // before updating the list's tail...
             if self.tail.is_null() {
                 self.head = new_tail;
+                trace!("set head");
             } else {
                 (*self.tail).next = new_tail;
+                trace!(ptr = ?self.tail, "relink tail.next");
             }
 
             self.tail = new_tail;
+            trace!("set tail");
+            self.len += 1;
+            #[cfg(feature = "stats")]
+            self.stats.push();
         }
     }
 
@@ -60,22 +403,35 @@ impl<T> List<T> {
                 // list is currently empty
                 None
             } else {
-                // own the current head by turning it into a Box!
-                // This also cleans up the data via the Box drop
-                let old_head = Box::from_raw(self.head);
-
-                self.head = old_head.next;
+                let old_head = self.head;
+                self.head = (*old_head).next;
+                trace!(ptr = ?self.head, "advance head");
 
                 // list is now emptied
                 if self.head.is_null() {
                     self.tail = ptr::null_mut();
+                    trace!("null tail");
                 }
+                self.len -= 1;
+                #[cfg(feature = "stats")]
+                self.stats.pop();
 
-                Some(old_head.elem)
+                // relinked first, so a panic can't leave us pointing at a freed node
+                trace!(ptr = ?old_head, "free node");
+                Some(Self::free_node(old_head))
             }
         }
     }
 
+    /// Pops the front only if `pred` says so, as one check-and-pop.
+    pub fn pop_if<F: FnOnce(&T) -> bool>(&mut self, pred: F) -> Option<T> {
+        if self.peek().is_some_and(pred) {
+            self.pop()
+        } else {
+            None
+        }
+    }
+
     pub fn peek(&self) -> Option<&T> {
         unsafe { self.head.as_ref().map(|node| &node.elem) }
     }
@@ -85,9 +441,330 @@ impl<T> List<T> {
     }
 }
 
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "stats")]
+impl<T> List<T> {
+    #[must_use]
+    pub fn stats(&self) -> crate::stats::Stats {
+        self.stats.snapshot()
+    }
+}
+
+// Moving to and from std's LinkedList, one element at a time. Order is preserved both ways.
+impl<T> From<LinkedList<T>> for List<T> {
+    fn from(std_list: LinkedList<T>) -> Self {
+        let mut list = Self::new();
+        for elem in std_list {
+            list.push(elem);
+        }
+        list
+    }
+}
+
+impl<T> From<List<T>> for LinkedList<T> {
+    fn from(list: List<T>) -> Self {
+        list.into_iter().collect()
+    }
+}
+
+/// Concatenation, `String`-style. Thanks to the tail pointer it's O(1).
+///
+/// ```
+/// use too_many_linked_lists::fifth::List;
+///
+/// let mut a = List::new();
+/// a.push(1);
+/// let mut b = List::new();
+/// b.push(2);
+///
+/// let mut ab = a + b;
+/// ab += List::new();
+/// assert_eq!(ab.iter().copied().collect::<Vec<_>>(), [1, 2]);
+/// ```
+///
+/// Both sides are moved in, so neither is usable afterwards:
+///
+/// ```compile_fail
+/// use too_many_linked_lists::fifth::List;
+///
+/// let a: List<i32> = List::new();
+/// let b = List::new();
+/// let ab = a + b;
+/// b.peek();
+/// ```
+impl<T> Add for List<T> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl<T> AddAssign for List<T> {
+    fn add_assign(&mut self, mut other: Self) {
+        #[cfg(feature = "stats")]
+        self.stats.absorb(&other.stats);
+        // empty other out, so its Drop doesn't free nodes we now own
+        let head = mem::replace(&mut other.head, ptr::null_mut());
+        let tail = mem::replace(&mut other.tail, ptr::null_mut());
+        if head.is_null() {
+            return;
+        }
+        self.len += mem::take(&mut other.len);
+        if self.tail.is_null() {
+            self.head = head;
+        } else {
+            unsafe { (*self.tail).next = head };
+        }
+        self.tail = tail;
+    }
+}
+
+// One pass, front to back. Pushing at the tail is O(1), so there's no building
+// the copy backwards and reversing it, as a stack would have to. The clone
+// allocates in blocks of the same size as the original.
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        let mut copy = Self::with_block_size(self.block_size);
+        for elem in self {
+            copy.push(elem.clone());
+        }
+        copy
+    }
+}
+
+impl<T> List<T> {
+    /// Takes the queue apart into (head, tail, len), for handing the chain across
+    /// an FFI boundary or stashing it somewhere a `List` can't go. Both pointers
+    /// are null for an empty queue. Nothing is freed: the nodes are yours until
+    /// `from_raw_parts` puts them back in a `List`.
+    ///
+    /// The queue is consumed, so it can't free the chain out from under you:
+    ///
+    /// ```compile_fail
+    /// use too_many_linked_lists::fifth::List;
+    ///
+    /// let mut list = List::new();
+    /// list.push(1);
+    /// let (head, tail, len) = list.into_raw_parts();
+    /// list.pop();
+    /// ```
+    #[must_use = "the chain leaks unless it's given back to `List::from_raw_parts`"]
+    pub fn into_raw_parts(mut self) -> (*mut Node<T>, *mut Node<T>, usize) {
+        let parts = (
+            mem::replace(&mut self.head, ptr::null_mut()),
+            mem::replace(&mut self.tail, ptr::null_mut()),
+            mem::take(&mut self.len),
+        );
+        // now empty, so dropping it only retires its block (which lives on
+        // until the last of our nodes is freed)
+        drop(self);
+        parts
+    }
+
+    /// Puts a queue back together from `into_raw_parts`. Its capacity and block
+    /// size aren't part of the chain, so it comes back unbounded with the
+    /// default block size.
+    ///
+    /// # Safety
+    ///
+    /// The parts must come from `into_raw_parts` on a `List<T>` of the same `T`,
+    /// and must be given back exactly once: two lists made from the same parts
+    /// would both free the chain.
+    pub unsafe fn from_raw_parts(head: *mut Node<T>, tail: *mut Node<T>, len: usize) -> Self {
+        let mut list = Self::new();
+        list.head = head;
+        list.tail = tail;
+        list.len = len;
+        list
+    }
+
+    /// Adopts a chain built somewhere else: nodes from `into_raw_parts`, cut,
+    /// joined and reordered freely (they don't care which list they're in), and
+    /// standalone ones from [`Node::new`], all linked through their `next`s.
+    /// Nothing is copied. Debug builds walk the chain first, and panic (leaking
+    /// it, rather than freeing it wrong) if `tail` or `len` don't match what's
+    /// there or the `next`s loop. Release builds take the caller's word for it.
+    ///
+    /// # Safety
+    ///
+    /// - Every node must have come from `into_raw_parts` on a `List<T>`, or from
+    ///   `Node::<T>::new`. A `Node<T>` allocated any other way (by C's `malloc`,
+    ///   say) can't be freed, and adopting one is undefined behaviour.
+    /// - No node may be in any other list, or be adopted twice.
+    /// - Following `next` from `head` must reach `tail` after exactly `len`
+    ///   nodes, and `tail`'s `next` must be null. An empty chain is two nulls
+    ///   and a zero.
+    ///
+    /// Only the last of these is checked, and only in debug builds.
+    pub unsafe fn from_raw_chain(head: *mut Node<T>, tail: *mut Node<T>, len: usize) -> Self {
+        let list = mem::ManuallyDrop::new(Self::from_raw_parts(head, tail, len));
+        #[cfg(debug_assertions)]
+        list.assert_valid();
+        mem::ManuallyDrop::into_inner(list)
+    }
+}
+
+impl<T: Clone> List<T> {
+    /// Pushes clones of everything in `elems`, in order — the batch producer's
+    /// `push`. The new nodes are chained to each other first, then hung off our
+    /// tail with a single store, instead of updating the tail once per element.
+    pub fn push_slice(&mut self, elems: &[T]) {
+        let Some((first, rest)) = elems.split_first() else {
+            return;
+        };
+        self.make_room(elems.len());
+        unsafe {
+            // If a clone panics, the chain so far isn't ours yet and leaks. Leaks
+            // are safe.
+            let head = self.alloc_node(first.clone());
+            let mut tail = head;
+            for elem in rest {
+                let node = self.alloc_node(elem.clone());
+                (*tail).next = node;
+                tail = node;
+            }
+            trace!(ptr = ?head, len = elems.len(), "alloc chain");
+
+            if self.tail.is_null() {
+                self.head = head;
+            } else {
+                (*self.tail).next = head;
+            }
+            self.tail = tail;
+        }
+        self.len += elems.len();
+        #[cfg(feature = "stats")]
+        for _ in elems {
+            self.stats.push();
+        }
+    }
+}
+
+// `{:?}` prints the elements like a Vec. `{:#?}` adds where head and tail point,
+// which is usually what you're after when the unsafe code is misbehaving.
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Elems<'a, T>(&'a List<T>);
+
+        impl<T: fmt::Debug> fmt::Debug for Elems<'_, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list().entries(self.0.iter()).finish()
+            }
+        }
+
+        if f.alternate() {
+            f.debug_struct("List")
+                .field("elems", &Elems(self))
+                .field("head", &self.head)
+                .field("tail", &self.tail)
+                .finish()
+        } else {
+            Elems(self).fmt(f)
+        }
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
-        while self.pop().is_some() {}
+        self.clear();
+        self.retire_block();
+    }
+}
+
+// A bad edit to a next pointer can loop the chain back on itself, and then every
+// walk down it (Drop included) goes round forever. Floyd's tortoise and hare
+// spots that in O(n) time and O(1) space: walk one pointer a node at a time and
+// another two at a time, and they only meet if there's a loop.
+impl<T> List<T> {
+    #[must_use]
+    pub fn has_cycle(&self) -> bool {
+        !self.meeting_point().is_null()
+    }
+
+    /// The index of the node the loop leads back to, if there is one.
+    #[must_use]
+    pub fn cycle_start(&self) -> Option<usize> {
+        let mut meeting = self.meeting_point();
+        if meeting.is_null() {
+            return None;
+        }
+        // The meeting point is as far from the loop's start (going round) as head
+        // is, so walking on from both at the same pace, they meet right there.
+        let mut from_head = self.head;
+        let mut index = 0;
+        while from_head != meeting {
+            unsafe {
+                from_head = (*from_head).next;
+                meeting = (*meeting).next;
+            }
+            index += 1;
+        }
+        Some(index)
+    }
+
+    // Where the tortoise and hare meet, or null if the hare runs off the end.
+    fn meeting_point(&self) -> *mut Node<T> {
+        let (mut tortoise, mut hare) = (self.head, self.head);
+        unsafe {
+            while !hare.is_null() && !(*hare).next.is_null() {
+                hare = (*(*hare).next).next;
+                tortoise = (*tortoise).next;
+                if tortoise == hare {
+                    return hare;
+                }
+            }
+        }
+        ptr::null_mut()
+    }
+
+    // Points tail's next back at the node at `to`, for testing the checks above.
+    // Untangle it (tail.next = null) before the queue drops.
+    #[cfg(test)]
+    fn make_cycle_for_test(&mut self, to: usize) {
+        let mut target = self.head;
+        for _ in 0..to {
+            target = unsafe { (*target).next };
+        }
+        assert!(!target.is_null(), "no node {to} to loop back to");
+        unsafe { (*self.tail).next = target };
+    }
+}
+
+// Walks the queue and panics if the raw pointers don't add up: no loops, the walk
+// from head has to end exactly at tail, after exactly `len` nodes.
+// O(n), so debug builds only.
+#[cfg(debug_assertions)]
+impl<T> List<T> {
+    pub fn assert_valid(&self) {
+        assert_eq!(
+            self.head.is_null(),
+            self.tail.is_null(),
+            "only one of head and tail is set"
+        );
+        if let Some(start) = self.cycle_start() {
+            panic!("next pointers loop back to node {start}");
+        }
+
+        let mut walked = 0;
+        let mut last = ptr::null_mut();
+        let mut current = self.head;
+        while !current.is_null() {
+            walked += 1;
+            last = current;
+            current = unsafe { (*current).next };
+        }
+        assert_eq!(
+            last, self.tail,
+            "tail isn't the last node reachable from head"
+        );
+        assert_eq!(walked, self.len, "len is off");
     }
 }
 
@@ -95,32 +772,85 @@ pub struct IntoIter<T>(List<T>);
 
 pub struct Iter<'a, T> {
     next: Option<&'a Node<T>>,
+    #[cfg(feature = "stats")]
+    stats: &'a crate::stats::Counters,
 }
 
 pub struct IterMut<'a, T> {
     next: Option<&'a mut Node<T>>,
+    #[cfg(feature = "stats")]
+    stats: &'a crate::stats::Counters,
 }
 
-impl<T> List<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
         IntoIter(self)
     }
+}
+
+// So `for elem in &list` and `for elem in &mut list` work, like they do for Vec.
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
 
-    pub fn iter(&self) -> Iter<T> {
+impl<T> List<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         unsafe {
             Iter {
                 next: self.head.as_ref(),
+                #[cfg(feature = "stats")]
+                stats: &self.stats,
             }
         }
     }
 
-    pub fn iter_mut(&self) -> IterMut<T> {
+    /// Takes `&mut self`. (It used to take `&self`, which let two `IterMut`s hand
+    /// out aliasing `&mut T`s to the same element.)
+    ///
+    /// ```compile_fail
+    /// use too_many_linked_lists::fifth::List;
+    ///
+    /// let mut list = List::new();
+    /// list.push(1);
+    /// let mut a = list.iter_mut();
+    /// let mut b = list.iter_mut();
+    /// *a.next().unwrap() += *b.next().unwrap();
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         unsafe {
             IterMut {
                 next: self.head.as_mut(),
+                #[cfg(feature = "stats")]
+                stats: &self.stats,
             }
         }
     }
+
+    /// Gives up the queue for good, like `Vec::leak`: neither the nodes nor their
+    /// blocks are ever freed, so every element can be borrowed for `'static`.
+    pub fn leak(self) -> IterMut<'static, T>
+    where
+        T: 'static,
+    {
+        Box::leak(Box::new(self)).iter_mut()
+    }
 }
 
 impl<T> Iterator for IntoIter<T> {
@@ -129,8 +859,14 @@ impl<T> Iterator for IntoIter<T> {
     fn next(&mut self) -> Option<Self::Item> {
         self.0.pop()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
 }
 
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
 
@@ -138,6 +874,8 @@ impl<'a, T> Iterator for Iter<'a, T> {
         unsafe {
             self.next.map(|f| {
                 self.next = f.next.as_ref();
+                #[cfg(feature = "stats")]
+                self.stats.step();
                 &f.elem
             })
         }
@@ -151,73 +889,863 @@ impl<'a, T> Iterator for IterMut<'a, T> {
         unsafe {
             self.next.take().map(|f| {
                 self.next = f.next.as_mut();
+                #[cfg(feature = "stats")]
+                self.stats.step();
                 &mut f.elem
             })
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// O(n) random access by walking from the head, for looking at queued work
+// without draining it. We know the length, so past the end is O(1).
+impl<T> List<T> {
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        self.iter().nth(index)
+    }
 
-    #[test]
-    fn basics() {
-        let mut queue = List::new();
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        self.iter_mut().nth(index)
+    }
+}
 
-        queue.push(1);
-        queue.push(2);
+// Batches of up to `size` elements, as owned queues, for pulling work off in
+// bulk. Each batch is a run of nodes cut off the front: no copies, no allocations.
+pub struct Chunks<T> {
+    rest: List<T>,
+    size: usize,
+}
 
-        assert_eq!(queue.pop(), Some(1));
-        assert_eq!(queue.pop(), Some(2));
-        assert_eq!(queue.pop(), None);
+impl<T> List<T> {
+    /// # Panics
+    ///
+    /// If `size` is 0, like `slice::chunks`.
+    pub fn chunks(self, size: usize) -> Chunks<T> {
+        assert!(size != 0, "chunk size must be non-zero");
+        Chunks { rest: self, size }
+    }
+}
 
-        queue.push(3);
-        queue.push(4);
+impl<T> Iterator for Chunks<T> {
+    type Item = List<T>;
 
-        assert_eq!(queue.pop(), Some(3));
-        assert_eq!(queue.pop(), Some(4));
-        assert_eq!(queue.pop(), None);
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &mut self.rest;
+        if rest.head.is_null() {
+            return None;
+        }
+        unsafe {
+            let mut end = rest.head;
+            let mut len = 1;
+            while len < self.size && !(*end).next.is_null() {
+                end = (*end).next;
+                len += 1;
+            }
+
+            let mut chunk = List::with_block_size(rest.block_size);
+            chunk.head = rest.head;
+            chunk.tail = end;
+            chunk.len = len;
+            rest.len -= len;
+            rest.head = (*end).next;
+            (*end).next = ptr::null_mut();
+            if rest.head.is_null() {
+                rest.tail = ptr::null_mut();
+            }
+            Some(chunk)
+        }
     }
+}
 
-    #[test]
-    fn iter() {
-        let mut queue = List::new();
+// The element n from the end (from_end(0) is the last), found with the runner
+// technique: a lead pointer n nodes ahead of the trailer. Raw pointers don't mind
+// that one of them ends up handed out mutably.
+impl<T> List<T> {
+    #[must_use]
+    pub fn from_end(&self, n: usize) -> Option<&T> {
+        unsafe { self.runner(n).as_ref().map(|node| &node.elem) }
+    }
 
-        queue.push(1);
-        queue.push(2);
+    pub fn from_end_mut(&mut self, n: usize) -> Option<&mut T> {
+        unsafe { self.runner(n).as_mut().map(|node| &mut node.elem) }
+    }
 
-        let mut iter = queue.iter();
+    // null if the queue has n or fewer elements
+    fn runner(&self, n: usize) -> Link<T> {
+        unsafe {
+            let mut lead = self.head;
+            for _ in 0..n {
+                if lead.is_null() {
+                    return ptr::null_mut();
+                }
+                lead = (*lead).next;
+                #[cfg(feature = "stats")]
+                self.stats.step();
+            }
+            if lead.is_null() {
+                return ptr::null_mut();
+            }
+            let mut trail = self.head;
+            while !(*lead).next.is_null() {
+                lead = (*lead).next;
+                trail = (*trail).next;
+                #[cfg(feature = "stats")]
+                self.stats.step();
+            }
+            trail
+        }
+    }
 
-        assert_eq!(iter.next(), Some(&1));
-        assert_eq!(iter.next(), Some(&2));
-        assert_eq!(iter.next(), None);
+    /// The middle element: for an even length, the last one of the first half.
+    /// Found with a runner that moves two nodes to the trailer's one, rather than
+    /// from `len`, since that's the trick merge sort and friends build on.
+    #[must_use]
+    pub fn middle(&self) -> Option<&T> {
+        unsafe { self.midpoint().1.as_ref().map(|node| &node.elem) }
     }
 
-    #[test]
-    fn into_iter() {
-        let mut queue = List::new();
+    /// Where `middle()` is, counting from the front.
+    #[must_use]
+    pub fn middle_index(&self) -> Option<usize> {
+        let (index, node) = self.midpoint();
+        (!node.is_null()).then_some(index)
+    }
 
-        queue.push(1);
-        queue.push(2);
+    // (index, node), with a null node if we're empty
+    fn midpoint(&self) -> (usize, Link<T>) {
+        let (mut trail, mut lead) = (self.head, self.head);
+        let mut index = 0;
+        unsafe {
+            while !lead.is_null() && !(*lead).next.is_null() && !(*(*lead).next).next.is_null() {
+                lead = (*(*lead).next).next;
+                trail = (*trail).next;
+                index += 1;
+            }
+        }
+        (index, trail)
+    }
+}
 
-        let mut iter = queue.into_iter();
+// Panics on out of range, like Vec.
+impl<T> Index<usize> for List<T> {
+    type Output = T;
 
-        assert_eq!(iter.next(), Some(1));
-        assert_eq!(iter.next(), Some(2));
-        assert_eq!(iter.next(), None);
+    fn index(&self, index: usize) -> &T {
+        let len = self.len;
+        self.get(index).unwrap_or_else(|| {
+            panic!("index out of bounds: the len is {len} but the index is {index}")
+        })
     }
+}
 
-    #[test]
-    fn iter_mut() {
-        let mut queue = List::new();
+impl<T> IndexMut<usize> for List<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len;
+        self.get_mut(index).unwrap_or_else(|| {
+            panic!("index out of bounds: the len is {len} but the index is {index}")
+        })
+    }
+}
 
-        queue.push(1);
-        queue.push(2);
+impl<T> List<T> {
+    /// Splits the queue into (matching, non-matching), order preserved in both, by
+    /// relinking the existing nodes.
+    pub fn partition<F: FnMut(&T) -> bool>(mut self, mut pred: F) -> (Self, Self) {
+        let (mut matching, mut rest) = (
+            Self::with_block_size(self.block_size),
+            Self::with_block_size(self.block_size),
+        );
+        unsafe {
+            while !self.head.is_null() {
+                // Unhook the node before calling pred, so if pred panics, self only
+                // drops what it still owns. (The node in hand leaks. Leaks are safe.)
+                let node = self.head;
+                self.head = (*node).next;
+                (*node).next = ptr::null_mut();
+                self.len -= 1;
 
-        let iter_mut = queue.iter_mut();
-        for elem in iter_mut {
-            *elem *= 10;
+                let out = if pred(&(*node).elem) {
+                    &mut matching
+                } else {
+                    &mut rest
+                };
+                if out.tail.is_null() {
+                    out.head = node;
+                } else {
+                    (*out.tail).next = node;
+                }
+                out.tail = node;
+                out.len += 1;
+            }
+            self.tail = ptr::null_mut();
+        }
+        (matching, rest)
+    }
+
+    /// Splits into the first `mid` elements and everything after, in one walk.
+    ///
+    /// # Panics
+    ///
+    /// If `mid` is past the end of the queue, same as slices.
+    pub fn split_at(mut self, mid: usize) -> (Self, Self) {
+        let mut back = Self::with_block_size(self.block_size);
+        unsafe {
+            let mut front_end = ptr::null_mut();
+            let mut back_start = self.head;
+            for walked in 0..mid {
+                assert!(!back_start.is_null(), "mid > len ({mid} > {walked})");
+                front_end = back_start;
+                back_start = (*back_start).next;
+                #[cfg(feature = "stats")]
+                self.stats.step();
+            }
+            if back_start.is_null() {
+                // nothing to hand over
+                return (self, back);
+            }
+
+            back.head = back_start;
+            back.tail = self.tail;
+            back.len = self.len - mid;
+            self.len = mid;
+            // the cut: front's last node stops pointing into back, and becomes the tail
+            if front_end.is_null() {
+                self.head = ptr::null_mut();
+            } else {
+                (*front_end).next = ptr::null_mut();
+            }
+            self.tail = front_end;
+        }
+        (self, back)
+    }
+
+    /// Detaches every node in O(1) and hands them back as an owning iterator,
+    /// leaving the queue empty (capacity and all) and ready for more pushes. No
+    /// allocation: the iterator just takes over the chain. So a consumer can grab
+    /// the whole batch under a short lock and drain it after letting go.
+    pub fn take_all(&mut self) -> IntoIter<T> {
+        let mut batch = Self::with_block_size(self.block_size);
+        batch.head = mem::replace(&mut self.head, ptr::null_mut());
+        batch.tail = mem::replace(&mut self.tail, ptr::null_mut());
+        batch.len = mem::take(&mut self.len);
+        IntoIter(batch)
+    }
+
+    /// Splices `lists` together in order. The tail pointer earns its keep here: each
+    /// list is O(1) to attach, however long it is.
+    pub fn concat<I: IntoIterator<Item = Self>>(lists: I) -> Self {
+        let mut joined = Self::new();
+        for list in lists {
+            joined += list;
+        }
+        joined
+    }
+
+    /// Zips `other`'s nodes in between ours: a, x, b, y, ... Leftovers from the
+    /// longer queue go on the end. Handy for round-robin merging two work queues.
+    pub fn interleave(&mut self, mut other: Self) {
+        unsafe {
+            // we're taking every node, so other must not free any of them
+            let mut theirs = mem::replace(&mut other.head, ptr::null_mut());
+            let their_tail = mem::replace(&mut other.tail, ptr::null_mut());
+            self.len += mem::take(&mut other.len);
+
+            if self.head.is_null() {
+                self.head = theirs;
+                self.tail = their_tail;
+                return;
+            }
+
+            let mut ours = self.head;
+            while !theirs.is_null() {
+                let our_next = (*ours).next;
+                let their_next = (*theirs).next;
+                (*ours).next = theirs;
+                if our_next.is_null() {
+                    // we ran out: the rest of theirs is already hanging off this node
+                    self.tail = their_tail;
+                    return;
+                }
+                (*theirs).next = our_next;
+                ours = our_next;
+                theirs = their_next;
+            }
+            // they ran out first, so our tail is still the tail
+        }
+    }
+}
+
+impl<T: PartialEq> List<T> {
+    /// Whether the queue reads the same both ways, in O(n) time and no extra
+    /// space: cut after the middle, reverse the back half in place, compare the
+    /// halves side by side, then reverse it back and reattach it. Hence `&mut`.
+    /// The reattaching happens on the way out even if `==` panics, so the queue
+    /// is whole again either way.
+    pub fn is_palindrome(&mut self) -> bool {
+        // Puts the back half back, in order, when it goes out of scope.
+        struct Reattach<T> {
+            middle: *mut Node<T>,
+            back: Link<T>,
+        }
+
+        impl<T> Drop for Reattach<T> {
+            fn drop(&mut self) {
+                unsafe { (*self.middle).next = List::reverse_chain(self.back) }
+            }
+        }
+
+        let (_, middle) = self.midpoint();
+        if middle.is_null() {
+            return true;
+        }
+        unsafe {
+            let back = Self::reverse_chain(mem::replace(&mut (*middle).next, ptr::null_mut()));
+            let _reattach = Reattach { middle, back };
+
+            let (mut ours, mut theirs) = (self.head, back);
+            // the back half is never the longer one
+            while !theirs.is_null() {
+                if (*ours).elem != (*theirs).elem {
+                    return false;
+                }
+                ours = (*ours).next;
+                theirs = (*theirs).next;
+            }
+            true
+        }
+    }
+}
+
+impl<T> List<T> {
+    // Flips a detached chain, returning its new first node. Its old first node
+    // ends up last, with a null next.
+    unsafe fn reverse_chain(mut remaining: Link<T>) -> Link<T> {
+        let mut reversed = ptr::null_mut();
+        while !remaining.is_null() {
+            let node = remaining;
+            remaining = (*node).next;
+            (*node).next = reversed;
+            reversed = node;
+        }
+        reversed
+    }
+
+    /// Reverses each run of `k` nodes in place: with k = 3, 1 2 3 4 5 6 7 becomes
+    /// 3 2 1 6 5 4 7. A last group shorter than `k` stays as it is, unless
+    /// `reverse_rest` says to flip it too. Only relinks.
+    ///
+    /// # Panics
+    ///
+    /// If `k` is 0.
+    pub fn reverse_in_groups(&mut self, k: usize, reverse_rest: bool) {
+        assert!(k != 0, "group size must be non-zero");
+        unsafe {
+            let mut remaining = mem::replace(&mut self.head, ptr::null_mut());
+            // last node of what we've put back so far
+            let mut end: Link<T> = ptr::null_mut();
+            while !remaining.is_null() {
+                let first = remaining;
+                let mut last = first;
+                let mut taken = 1;
+                while taken < k && !(*last).next.is_null() {
+                    last = (*last).next;
+                    taken += 1;
+                }
+                remaining = mem::replace(&mut (*last).next, ptr::null_mut());
+
+                // reversed, the group's first node is its last
+                let (group_head, group_tail) = if taken == k || reverse_rest {
+                    (Self::reverse_chain(first), first)
+                } else {
+                    (first, last)
+                };
+                if end.is_null() {
+                    self.head = group_head;
+                } else {
+                    (*end).next = group_head;
+                }
+                end = group_tail;
+            }
+            self.tail = end;
+        }
+    }
+}
+
+impl<T: PartialEq> List<T> {
+    /// Unlinks the first node holding `value`. Returns whether there was one.
+    pub fn remove_first(&mut self, value: &T) -> bool {
+        unsafe {
+            let mut prev = ptr::null_mut();
+            let mut current = self.head;
+            while !current.is_null() && (*current).elem != *value {
+                prev = current;
+                current = (*current).next;
+            }
+            if current.is_null() {
+                return false;
+            }
+            self.unlink(prev, current);
+            true
+        }
+    }
+
+    /// Unlinks every node holding `value`, in one pass. Returns how many went.
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        let mut removed = 0;
+        unsafe {
+            let mut prev = ptr::null_mut();
+            let mut current = self.head;
+            while !current.is_null() {
+                let next = (*current).next;
+                if (*current).elem == *value {
+                    self.unlink(prev, current);
+                    removed += 1;
+                } else {
+                    prev = current;
+                }
+                current = next;
+            }
+        }
+        removed
+    }
+}
+
+impl<T> List<T> {
+    /// Keeps only the elements `pred` likes, in order, unlinking the rest in one
+    /// pass. `unlink` moves the tail back to the last survivor when the old tail
+    /// goes, and nulls both ends when nothing survives.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut pred: F) {
+        unsafe {
+            let mut prev = ptr::null_mut();
+            let mut current = self.head;
+            while !current.is_null() {
+                let next = (*current).next;
+                if pred(&(*current).elem) {
+                    prev = current;
+                } else {
+                    self.unlink(prev, current);
+                }
+                current = next;
+            }
+        }
+    }
+
+    // Cuts `node` out and frees it. `prev` is the node before it, or null if it's
+    // the head. Everything is relinked *before* the free, so a panicking Drop on
+    // the element can't leave us pointing at freed memory.
+    unsafe fn unlink(&mut self, prev: Link<T>, node: Link<T>) {
+        let next = (*node).next;
+        if prev.is_null() {
+            self.head = next;
+        } else {
+            (*prev).next = next;
+        }
+        if node == self.tail {
+            self.tail = prev;
+        }
+        self.len -= 1;
+        drop(Self::free_node(node));
+    }
+}
+
+// Sorted means ascending from head to tail, so pop() hands back the smallest.
+impl<T: Ord> List<T> {
+    #[must_use]
+    pub fn is_sorted(&self) -> bool {
+        self.iter().is_sorted()
+    }
+
+    /// Inserts `elem` after every element that is <= it. Assumes the queue is
+    /// already sorted.
+    pub fn insert_sorted(&mut self, elem: T) {
+        self.make_room(1);
+        unsafe {
+            let new = self.alloc_node(elem);
+            trace!(ptr = ?new, "alloc node");
+            self.len += 1;
+            #[cfg(feature = "stats")]
+            self.stats.push();
+
+            // goes in front of everything: it's the new head
+            if self.head.is_null() || (*new).elem < (*self.head).elem {
+                (*new).next = self.head;
+                self.head = new;
+                trace!("set head");
+                if self.tail.is_null() {
+                    self.tail = new;
+                    trace!("set tail");
+                }
+                return;
+            }
+
+            // otherwise find the last node that's <= us and slot in behind it
+            let mut prev = self.head;
+            while !(*prev).next.is_null() && (*(*prev).next).elem <= (*new).elem {
+                prev = (*prev).next;
+                #[cfg(feature = "stats")]
+                self.stats.step();
+            }
+            (*new).next = (*prev).next;
+            (*prev).next = new;
+            trace!(ptr = ?prev, "relink prev.next");
+            if prev == self.tail {
+                self.tail = new;
+                trace!("set tail");
+            }
+        }
+    }
+}
+
+impl<T: Ord> List<T> {
+    /// Splits the queue three ways around `pivot`: (less, equal, greater), each in
+    /// its original order. The Dutch national flag problem, done by relinking —
+    /// handy for bucketing work items, or as the heart of a list quicksort.
+    pub fn partition3(mut self, pivot: &T) -> (Self, Self, Self) {
+        let (mut less, mut equal, mut greater) = (
+            Self::with_block_size(self.block_size),
+            Self::with_block_size(self.block_size),
+            Self::with_block_size(self.block_size),
+        );
+        unsafe {
+            while !self.head.is_null() {
+                // unhooked before comparing, same as partition, in case cmp panics
+                let node = self.head;
+                self.head = (*node).next;
+                (*node).next = ptr::null_mut();
+                self.len -= 1;
+
+                let out = match (*node).elem.cmp(pivot) {
+                    Ordering::Less => &mut less,
+                    Ordering::Equal => &mut equal,
+                    Ordering::Greater => &mut greater,
+                };
+                if out.tail.is_null() {
+                    out.head = node;
+                } else {
+                    (*out.tail).next = node;
+                }
+                out.tail = node;
+                out.len += 1;
+            }
+            self.tail = ptr::null_mut();
+        }
+        (less, equal, greater)
+    }
+
+    /// Merges two sorted queues into one, by relinking their nodes. On ties, ours
+    /// come first, so merging is stable.
+    #[must_use]
+    pub fn merge(mut self, mut other: Self) -> Self {
+        let mut merged = Self::new();
+        unsafe {
+            // both queues hand over every node, so neither may free any
+            let mut ours = mem::replace(&mut self.head, ptr::null_mut());
+            let our_tail = mem::replace(&mut self.tail, ptr::null_mut());
+            let mut theirs = mem::replace(&mut other.head, ptr::null_mut());
+            let their_tail = mem::replace(&mut other.tail, ptr::null_mut());
+            merged.len = mem::take(&mut self.len) + mem::take(&mut other.len);
+
+            // (if Ord panics in here, whatever's unmerged leaks, which is safe)
+            while !ours.is_null() && !theirs.is_null() {
+                let from = if (*theirs).elem < (*ours).elem {
+                    &mut theirs
+                } else {
+                    &mut ours
+                };
+                let node = *from;
+                *from = (*node).next;
+                if merged.tail.is_null() {
+                    merged.head = node;
+                } else {
+                    (*merged.tail).next = node;
+                }
+                merged.tail = node;
+            }
+
+            // whichever side's left over is still sorted, and goes on as is
+            let (rest, rest_tail) = if ours.is_null() {
+                (theirs, their_tail)
+            } else {
+                (ours, our_tail)
+            };
+            if !rest.is_null() {
+                if merged.tail.is_null() {
+                    merged.head = rest;
+                } else {
+                    (*merged.tail).next = rest;
+                }
+                merged.tail = rest_tail;
+            }
+        }
+        merged
+    }
+
+    /// Merges any number of sorted lists, pairing them off and merging each pair,
+    /// round after round: O(n log k) for n elements over k lists, versus O(nk)
+    /// folding them into one list at a time. Only relinks. Ties keep list order.
+    #[must_use]
+    pub fn merge_k(mut lists: Vec<Self>) -> Self {
+        while lists.len() > 1 {
+            let mut merged = Vec::with_capacity(lists.len().div_ceil(2));
+            let mut pairs = lists.into_iter();
+            while let Some(first) = pairs.next() {
+                merged.push(match pairs.next() {
+                    Some(second) => first.merge(second),
+                    None => first,
+                });
+            }
+            lists = merged;
+        }
+        lists.pop().unwrap_or_default()
+    }
+}
+
+// Serialize front to back. We have a tail, so deserializing can push each element as it
+// arrives — no intermediate buffer.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        // Some formats (bincode) insist on knowing the length up front.
+        let mut seq = serializer.serialize_seq(Some(self.len))?;
+        for elem in self.iter() {
+            seq.serialize_element(elem)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ListVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for ListVisitor<T> {
+            type Value = List<T>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a sequence")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut list = List::new();
+                while let Some(elem) = seq.next_element()? {
+                    list.push(elem);
+                }
+                Ok(list)
+            }
+        }
+
+        deserializer.deserialize_seq(ListVisitor(std::marker::PhantomData))
+    }
+}
+
+// Archives as a flat ArchivedVec, like second::List. Only the elements go in: the
+// blocks they sat in (and the block size) are the allocator's business, so the
+// queue that comes back out is a fresh one with default-sized blocks.
+#[cfg(feature = "rkyv")]
+impl<T: rkyv::Archive> rkyv::Archive for List<T> {
+    type Archived = rkyv::vec::ArchivedVec<T::Archived>;
+    type Resolver = rkyv::vec::VecResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: rkyv::Place<Self::Archived>) {
+        rkyv::vec::ArchivedVec::resolve_from_len(self.len, resolver, out);
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, S> rkyv::Serialize<S> for List<T>
+where
+    T: rkyv::Serialize<S>,
+    S: rkyv::rancor::Fallible + rkyv::ser::Allocator + rkyv::ser::Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        // The archiver wants an ExactSizeIterator it can clone, so gather up refs first.
+        let elems: Vec<&T> = self.iter().collect();
+        rkyv::vec::ArchivedVec::serialize_from_iter::<T, _, _>(elems.iter().copied(), serializer)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, D> rkyv::Deserialize<List<T>, D> for rkyv::vec::ArchivedVec<T::Archived>
+where
+    T: rkyv::Archive,
+    [T::Archived]: rkyv::DeserializeUnsized<[T], D>,
+    D: rkyv::rancor::Fallible + ?Sized,
+    D::Error: rkyv::rancor::Source,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<List<T>, D::Error> {
+        let elems: Vec<T> = self.deserialize(deserializer)?;
+        let mut list = List::new();
+        for elem in elems {
+            list.push(elem);
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(feature = "viz")]
+impl<T: std::fmt::Debug> List<T> {
+    fn graph(&self) -> crate::viz::Graph {
+        let mut graph = crate::viz::Graph::new();
+        let mut current = self.head;
+        while !current.is_null() {
+            unsafe {
+                graph.node(current, &(*current).elem);
+                if !(*current).next.is_null() {
+                    graph.edge(current, (*current).next, "next");
+                }
+                current = (*current).next;
+            }
+        }
+        if !self.head.is_null() {
+            graph.marker("head", self.head);
+            graph.marker("tail", self.tail);
+        }
+        graph
+    }
+
+    /// Dumps the list's structure as a Graphviz DOT graph.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        self.graph().to_dot()
+    }
+
+    /// Same as [`List::to_dot`], but as a Mermaid `graph LR` diagram, ready to paste into
+    /// markdown.
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        self.graph().to_mermaid()
+    }
+
+    /// Draws the list as ASCII art, e.g. `HEAD -> [2] -> [5] -> ∅`.
+    #[must_use]
+    pub fn render_ascii(&self) -> String {
+        self.graph().to_ascii()
+    }
+
+    /// Same as [`List::to_dot`], but as JSON, for drawing somewhere other than a
+    /// terminal (examples/wasm-viz draws it in a browser).
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        self.graph().to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    // assert_valid is debug-only; let the tests still build under --release
+    #[cfg(not(debug_assertions))]
+    impl<T> List<T> {
+        fn assert_valid(&self) {}
+    }
+
+    fn from(elems: &[i32]) -> List<i32> {
+        let mut queue = List::new();
+        queue.push_slice(elems);
+        queue
+    }
+
+    #[test]
+    fn basics() {
+        let mut queue = List::new();
+
+        queue.push(1);
+        queue.assert_valid();
+        queue.push(2);
+        queue.assert_valid();
+
+        assert_eq!(queue.pop(), Some(1));
+        queue.assert_valid();
+        assert_eq!(queue.pop(), Some(2));
+        queue.assert_valid();
+        assert_eq!(queue.pop(), None);
+        queue.assert_valid();
+
+        queue.push(3);
+        queue.assert_valid();
+        queue.push(4);
+        queue.assert_valid();
+
+        assert_eq!(queue.pop(), Some(3));
+        queue.assert_valid();
+        assert_eq!(queue.pop(), Some(4));
+        queue.assert_valid();
+        assert_eq!(queue.pop(), None);
+        queue.assert_valid();
+    }
+
+    #[test]
+    fn iter() {
+        let mut queue = List::new();
+
+        queue.push(1);
+        queue.assert_valid();
+        queue.push(2);
+        queue.assert_valid();
+
+        let mut iter = queue.iter();
+
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut queue = List::new();
+
+        queue.push(1);
+        queue.assert_valid();
+        queue.push(2);
+        queue.assert_valid();
+
+        let mut iter = queue.into_iter();
+
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn leak() {
+        let mut queue = List::new();
+        queue.push(1);
+        queue.push(2);
+
+        let leaked: Vec<&'static mut i32> = queue.leak().collect();
+        for elem in leaked {
+            *elem += 10;
+            assert!(*elem > 10);
+        }
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut queue = List::new();
+
+        queue.push(1);
+        queue.assert_valid();
+        queue.push(2);
+        queue.assert_valid();
+
+        let iter_mut = queue.iter_mut();
+        for elem in iter_mut {
+            *elem *= 10;
         }
 
         let mut iter = queue.iter();
@@ -226,4 +1754,1113 @@ mod tests {
         assert_eq!(iter.next(), Some(&20));
         assert_eq!(iter.next(), None);
     }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn rkyv_round_trip() {
+        use rkyv::{rancor::Error, vec::ArchivedVec, Archived};
+
+        // one node per block, and the first few popped, so what's archived is a
+        // chain scattered over blocks that no longer start at the head
+        let mut list: List<i32> = List::with_block_size(1);
+        list.push_slice(&[0, 0, 1, 2, 3]);
+        list.pop();
+        list.pop();
+        let bytes = rkyv::to_bytes::<Error>(&list).unwrap();
+
+        let archived = rkyv::access::<ArchivedVec<Archived<i32>>, Error>(&bytes).unwrap();
+        assert_eq!(archived.as_slice(), [1, 2, 3]);
+        let mut list: List<i32> = rkyv::deserialize::<_, Error>(archived).unwrap();
+        list.assert_valid();
+        assert_eq!(list.block_size(), List::<i32>::new().block_size());
+        // tail's the real tail
+        list.push(4);
+        assert!(list.iter().eq(&[1, 2, 3, 4]));
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn to_dot() {
+        use crate::viz::dot_id;
+
+        let mut queue = List::new();
+        queue.push(1);
+        queue.assert_valid();
+        queue.push(2);
+        queue.assert_valid();
+
+        let dot = queue.to_dot();
+        let (one, two) = (dot_id(&dot, "1"), dot_id(&dot, "2"));
+        assert!(dot.contains(&format!("head -> {one};")));
+        assert!(dot.contains(&format!("tail -> {two};")));
+        assert!(dot.contains(&format!("{one} -> {two} [label=\"next\"];")));
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn render_ascii() {
+        let mut queue = List::new();
+        assert_eq!(queue.render_ascii(), "HEAD -> ∅\n");
+
+        queue.push(1);
+        queue.assert_valid();
+        queue.push(2);
+        queue.assert_valid();
+        assert_eq!(
+            queue.render_ascii(),
+            "HEAD -> [1] -> [2] -> ∅\nTAIL -> [2]\n"
+        );
+    }
+
+    #[test]
+    fn std_conversions() {
+        let std_list: LinkedList<i32> = [1, 2, 3].into_iter().collect();
+
+        let queue = List::from(std_list);
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let std_list = LinkedList::from(queue);
+        assert_eq!(std_list.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn trace() {
+        let log = crate::trace::capture(|| {
+            let mut list = List::new();
+            list.push(1);
+            list.assert_valid();
+            list.pop();
+            list.assert_valid();
+        });
+        assert_eq!(
+            log,
+            [
+                "new block",
+                "alloc node",
+                "set head",
+                "set tail",
+                "advance head",
+                "null tail",
+                "free node",
+            ]
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats() {
+        let mut list = List::new();
+        list.push(1);
+        list.assert_valid();
+        list.push(2);
+        list.assert_valid();
+        list.push(3);
+        list.assert_valid();
+        list.pop();
+        list.assert_valid();
+        assert_eq!(list.iter().count(), 2);
+        list.iter_mut().for_each(|elem| *elem *= 10);
+
+        let stats = list.stats();
+        assert_eq!((stats.pushes, stats.pops, stats.allocations), (3, 1, 3));
+        assert_eq!(stats.steps, 4);
+    }
+
+    // push, pop and the splices are what the tail pointer is for: the same zero
+    // steps at 16 nodes as at 4096. The walk at the end is there to show a
+    // traversal would get counted.
+    #[cfg(feature = "stats")]
+    #[test]
+    fn push_pop_splice_never_walk() {
+        fn filled(n: usize) -> List<usize> {
+            let mut list = List::new();
+            (0..n).for_each(|i| list.push(i));
+            list
+        }
+
+        for n in [16, 4096] {
+            let mut list = filled(n);
+            let before = list.stats();
+
+            list.push(n);
+            assert_eq!(list.try_push(n + 1), Ok(()));
+            assert_eq!(list.pop(), Some(0));
+            assert_eq!(list.peek(), Some(&1));
+            list += filled(n);
+            assert_eq!(list.take_all().count(), 2 * n + 1);
+
+            let after = list.stats();
+            assert_eq!(after.steps, before.steps, "n = {n}");
+            // += brings the n pushes that built the other list along with it
+            assert_eq!(
+                (after.pushes, after.pops),
+                (before.pushes + 2 + n, before.pops + 1)
+            );
+
+            // concat hands the pieces' counters on to what it builds, so a link
+            // it followed itself would show up on top of theirs
+            let pieces = [list, filled(n), filled(n)];
+            assert_eq!(pieces[1].iter().count(), n);
+            let total: usize = pieces.iter().map(|piece| piece.stats().steps).sum();
+            let joined = List::concat(pieces);
+            assert_eq!(joined.stats().steps, total, "n = {n}");
+
+            let (front, _) = joined.split_at(n / 2);
+            assert_eq!(front.stats().steps, total + n / 2);
+        }
+    }
+
+    // The layout Node's docs promise C.
+    #[cfg(feature = "repr-c")]
+    #[test]
+    fn repr_c_layout() {
+        use core::mem::offset_of;
+
+        let word = size_of::<usize>();
+        assert_eq!(offset_of!(Node<u8>, next), 0);
+        assert_eq!(offset_of!(Node<u8>, block), word);
+        assert_eq!(offset_of!(Node<u8>, elem), 2 * word);
+        assert_eq!(size_of::<Node<u8>>(), 3 * word);
+        // a big-aligned elem still comes last, padded out to its alignment
+        #[repr(align(32))]
+        struct Wide(#[allow(dead_code)] u8);
+        assert_eq!(offset_of!(Node<Wide>, elem), 32);
+        assert_eq!(align_of::<Node<Wide>>(), 32);
+    }
+
+    #[test]
+    fn from_raw_chain() {
+        let mut list = List::new();
+        (1..=4).for_each(|i| list.push(i));
+        let (head, tail, len) = list.into_raw_parts();
+
+        // hand the back half to another "subsystem": cut after the second node
+        unsafe {
+            let second = (*head).next;
+            let third = (*second).next;
+            (*second).next = ptr::null_mut();
+
+            let front = List::from_raw_chain(head, second, 2);
+            let back = List::from_raw_chain(third, tail, len - 2);
+            assert!(front.into_iter().eq([1, 2]));
+            assert!(back.into_iter().eq([3, 4]));
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn from_raw_chain_checks_the_chain() {
+        use std::panic::catch_unwind;
+
+        let mut list = List::new();
+        (1..=3).for_each(|i| list.push(i));
+        let (head, tail, len) = list.into_raw_parts();
+        let (head, tail) = (head as usize, tail as usize);
+
+        let wrong_len = catch_unwind(|| unsafe {
+            List::<i32>::from_raw_chain(head as *mut _, tail as *mut _, len + 1)
+        });
+        assert!(wrong_len.is_err());
+        let wrong_tail = catch_unwind(|| unsafe {
+            List::<i32>::from_raw_chain(head as *mut _, head as *mut _, len)
+        });
+        assert!(wrong_tail.is_err());
+
+        // both leaked rather than freed, so it's all still ours
+        let list = unsafe { List::<i32>::from_raw_chain(head as *mut _, tail as *mut _, len) };
+        assert!(list.into_iter().eq([1, 2, 3]));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn assert_valid_catches_a_cycle() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut queue = List::new();
+        queue.push(1);
+        queue.push(2);
+        queue.make_cycle_for_test(0);
+
+        let result = catch_unwind(AssertUnwindSafe(|| queue.assert_valid()));
+        // untangle it, or Drop frees its way around the loop forever
+        unsafe { (*queue.tail).next = ptr::null_mut() };
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn index() {
+        let mut queue = List::new();
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+
+        assert_eq!(queue.get(2), Some(&3));
+        assert_eq!(queue.get(3), None);
+        assert_eq!(queue.get(usize::MAX), None);
+        assert_eq!(queue.get_mut(3), None);
+        assert_eq!(queue[0], 1);
+
+        queue[1] *= 10;
+        *queue.get_mut(0).unwrap() = 0;
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [0, 20, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds: the len is 0 but the index is 0")]
+    fn index_out_of_bounds() {
+        let mut queue: List<i32> = List::new();
+        queue[0] = 1;
+    }
+
+    #[test]
+    fn insert_sorted() {
+        let mut queue = List::new();
+        assert!(queue.is_sorted());
+
+        // new head, new tail, and somewhere in the middle
+        for elem in [5, 1, 9, 4, 1, 0] {
+            queue.insert_sorted(elem);
+            queue.assert_valid();
+            assert!(queue.is_sorted());
+        }
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 1, 4, 5, 9]
+        );
+
+        // the tail must still be right for plain pushes
+        queue.push(3);
+        queue.assert_valid();
+        assert!(!queue.is_sorted());
+        assert_eq!(queue.iter().last(), Some(&3));
+    }
+
+    #[test]
+    fn partition() {
+        let mut queue = List::new();
+        for elem in 1..=6 {
+            queue.push(elem);
+        }
+        let last: *const i32 = queue.iter().last().unwrap();
+
+        let (small, big) = queue.partition(|&elem| elem <= 2);
+        small.assert_valid();
+        big.assert_valid();
+        assert_eq!(small.iter().copied().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(big.iter().copied().collect::<Vec<_>>(), [3, 4, 5, 6]);
+        assert_eq!(big.iter().last().unwrap() as *const i32, last);
+
+        // tails have to be right for pushing afterwards
+        let (mut none, mut all) = big.partition(|_| false);
+        none.push(0);
+        all.push(7);
+        none.assert_valid();
+        all.assert_valid();
+        assert_eq!(none.iter().copied().collect::<Vec<_>>(), [0]);
+        assert_eq!(all.iter().copied().collect::<Vec<_>>(), [3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn split_at() {
+        for mid in 0..=4 {
+            let mut queue = List::new();
+            for elem in 0..4 {
+                queue.push(elem);
+            }
+
+            let (mut front, mut back) = queue.split_at(mid);
+            front.assert_valid();
+            back.assert_valid();
+            assert_eq!(
+                front.iter().copied().collect::<Vec<_>>(),
+                (0..mid).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                back.iter().copied().collect::<Vec<_>>(),
+                (mid..4).collect::<Vec<_>>()
+            );
+
+            // both tails got fixed up, so pushing lands in the right place
+            front.push(10);
+            back.push(20);
+            front.assert_valid();
+            back.assert_valid();
+            assert_eq!(front.iter().last(), Some(&10));
+            assert_eq!(back.iter().last(), Some(&20));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "mid > len (1 > 0)")]
+    fn split_at_past_the_end() {
+        let queue: List<i32> = List::new();
+        let _ = queue.split_at(1);
+    }
+
+    #[test]
+    fn interleave() {
+        for (ours, theirs, zipped) in [
+            (&[1, 3, 5][..], &[2, 4, 6][..], &[1, 2, 3, 4, 5, 6][..]),
+            (&[1, 3], &[2, 4, 5, 6], &[1, 2, 3, 4, 5, 6]),
+            (&[1, 3, 4, 5], &[2], &[1, 2, 3, 4, 5]),
+            (&[], &[1, 2], &[1, 2]),
+            (&[1, 2], &[], &[1, 2]),
+        ] {
+            let mut queue = from(ours);
+            queue.interleave(from(theirs));
+            queue.assert_valid();
+            assert_eq!(queue.iter().copied().collect::<Vec<_>>(), zipped);
+
+            queue.push(7);
+            queue.assert_valid();
+            assert_eq!(queue.iter().last(), Some(&7));
+        }
+    }
+
+    #[test]
+    fn concat() {
+        let lists = [&[1, 2][..], &[], &[3], &[4, 5, 6], &[]].map(|elems| {
+            let mut queue = List::new();
+            for &elem in elems {
+                queue.push(elem);
+            }
+            queue
+        });
+
+        let mut joined = List::concat(lists);
+        joined.assert_valid();
+        joined.push(7);
+        joined.assert_valid();
+        assert_eq!(
+            joined.iter().copied().collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5, 6, 7]
+        );
+
+        let mut empty = List::concat([List::new(), List::new()]);
+        empty.assert_valid();
+        empty.push(1);
+        assert_eq!(empty.pop(), Some(1));
+    }
+
+    #[test]
+    fn add() {
+        let mut queue = List::new();
+        queue.push(1);
+        let mut other = List::new();
+        other.push(2);
+
+        let mut queue = List::new() + queue + other;
+        queue.assert_valid();
+        queue += List::new();
+        queue.push(3);
+        queue.assert_valid();
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn remove() {
+        let mut queue = List::new();
+        for elem in [3, 1, 3, 2, 3, 3] {
+            queue.push(elem);
+        }
+
+        assert!(queue.remove_first(&2));
+        assert!(!queue.remove_first(&2));
+        assert!(queue.remove_first(&3));
+        queue.assert_valid();
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 3, 3, 3]);
+
+        // the tail is one of the victims, so it has to move back
+        assert_eq!(queue.remove_all(&3), 3);
+        queue.assert_valid();
+        queue.push(4);
+        queue.assert_valid();
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 4]);
+
+        assert_eq!(queue.remove_all(&1) + queue.remove_all(&4), 2);
+        queue.assert_valid();
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn from_end() {
+        let mut queue = List::new();
+        assert_eq!(queue.from_end(0), None);
+        for elem in 1..=4 {
+            queue.push(elem);
+        }
+
+        assert_eq!(queue.from_end(0), Some(&4));
+        assert_eq!(queue.from_end(3), Some(&1));
+        assert_eq!(queue.from_end(4), None);
+
+        *queue.from_end_mut(1).unwrap() = 30;
+        assert_eq!(queue.from_end_mut(4), None);
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 2, 30, 4]);
+    }
+
+    #[test]
+    fn chunks() {
+        let mut queue = List::new();
+        for elem in 1..=6 {
+            queue.push(elem);
+        }
+
+        let mut chunks = queue.chunks(2);
+        for expected in [[1, 2], [3, 4], [5, 6]] {
+            let mut chunk = chunks.next().unwrap();
+            chunk.assert_valid();
+            assert_eq!(chunk.iter().copied().collect::<Vec<_>>(), expected);
+            // every chunk is a proper queue with a working tail
+            chunk.push(0);
+            chunk.assert_valid();
+            assert_eq!(chunk.iter().last(), Some(&0));
+        }
+        assert!(chunks.next().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be non-zero")]
+    fn zero_sized_chunks() {
+        let _ = List::<i32>::new().chunks(0);
+    }
+
+    #[test]
+    fn blocks() {
+        let canary = Rc::new(());
+        let mut list = List::with_block_size(4);
+        assert_eq!(list.block_size(), 4);
+        // bursts that straddle block boundaries
+        for round in 0..3 {
+            for _ in 0..10 {
+                list.push(Rc::clone(&canary));
+            }
+            list.assert_valid();
+            for _ in 0..7 + round {
+                drop(list.pop());
+            }
+            list.assert_valid();
+        }
+        assert_eq!(Rc::strong_count(&canary), 1 + 30 - 24);
+        drop(list);
+        assert_eq!(Rc::strong_count(&canary), 1);
+    }
+
+    #[test]
+    fn blocks_reuse_freed_slots() {
+        let addrs = |list: &List<i32>| {
+            let mut addrs: Vec<_> = list.iter().map(ptr::from_ref).collect();
+            addrs.sort();
+            addrs
+        };
+        let mut list = List::with_block_size(4);
+        list.push_slice(&[1, 2, 3, 4]);
+        let first_fill = addrs(&list);
+        // steady traffic, never more than a block's worth at once
+        for i in 5..100 {
+            list.pop();
+            list.push(i);
+            list.assert_valid();
+        }
+        assert!(list.iter().eq(&[96, 97, 98, 99]));
+        assert_eq!(addrs(&list), first_fill);
+
+        // slots freed through another queue come back to the one carving from
+        // their block (split_at's front half keeps it)
+        let (mut front, back) = list.split_at(2);
+        drop(back);
+        front.push(100);
+        front.push(101);
+        assert_eq!(addrs(&front), first_fill);
+    }
+
+    #[test]
+    fn adopts_standalone_nodes() {
+        let canary = Rc::new(());
+        let [a, b, c] = [0, 1, 2].map(|i| Node::new((i, Rc::clone(&canary))));
+        unsafe {
+            (*a).next = b;
+            (*b).next = c;
+        }
+        let mut list = unsafe { List::from_raw_chain(a, c, 3) };
+        list.push((3, Rc::clone(&canary)));
+        list.assert_valid();
+        assert_eq!(list.pop().map(|(i, _)| i), Some(0));
+        assert!(list.iter().map(|(i, _)| *i).eq(1..4));
+
+        // taken apart again, a block node and standalone ones mixed
+        let (head, tail, len) = list.into_raw_parts();
+        let mut list = unsafe { List::from_raw_chain(head, tail, len) };
+        assert_eq!(list.len(), 3);
+        list.retain(|(i, _)| i % 2 == 1);
+        drop(list);
+        assert_eq!(Rc::strong_count(&canary), 1);
+    }
+
+    #[test]
+    fn blocks_outlive_their_queue() {
+        // b's nodes live in b's blocks; after the concat, b is gone but ab still
+        // needs them
+        let mut a = List::with_block_size(8);
+        let mut b = List::with_block_size(3);
+        for i in 0..5 {
+            a.push(i);
+            b.push(i + 5);
+        }
+        let mut ab = a + b;
+        ab.assert_valid();
+        ab.push(10);
+        let (front, back) = ab.split_at(4);
+        drop(front);
+        assert_eq!(back.block_size(), 8);
+        assert_eq!(
+            back.iter().copied().collect::<Vec<_>>(),
+            [4, 5, 6, 7, 8, 9, 10]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "block size must be non-zero")]
+    fn zero_block_size() {
+        let _ = List::<i32>::with_block_size(0);
+    }
+
+    #[test]
+    fn into_iterator_forms() {
+        fn sum<'a>(elems: impl IntoIterator<Item = &'a i32>) -> i32 {
+            elems.into_iter().sum()
+        }
+
+        let mut list = List::new();
+        for elem in 1..=3 {
+            list.push(elem);
+        }
+        for elem in &mut list {
+            *elem *= 10;
+        }
+        let mut seen = Vec::new();
+        for elem in &list {
+            seen.push(*elem);
+        }
+        assert_eq!(seen, [10, 20, 30]);
+        assert_eq!(sum(&list), 60);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [10, 20, 30]);
+    }
+
+    #[test]
+    fn debug() {
+        let mut list = List::new();
+        assert_eq!(format!("{list:?}"), "[]");
+        for elem in 1..=3 {
+            list.push(elem);
+        }
+        assert_eq!(format!("{list:?}"), "[1, 2, 3]");
+
+        let pretty = format!("{list:#?}");
+        assert!(pretty.starts_with("List {\n    elems: [\n        1,\n"));
+        assert!(pretty.contains(&format!("head: {:#?},", list.head)));
+        assert!(pretty.contains(&format!("tail: {:#?},", list.tail)));
+        // and the list is still all there
+        assert_eq!(list.pop(), Some(1));
+    }
+
+    #[test]
+    fn clone() {
+        let mut original = List::with_block_size(4);
+        for elem in 1..=5 {
+            original.push(elem);
+        }
+        let mut copy = original.clone();
+        copy.assert_valid();
+        assert_eq!(copy.block_size(), 4);
+
+        for elem in &mut copy {
+            *elem *= 10;
+        }
+        copy.push(60);
+        assert_eq!(copy.pop(), Some(10));
+        assert_eq!(
+            original.iter().copied().collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            copy.iter().copied().collect::<Vec<_>>(),
+            [20, 30, 40, 50, 60]
+        );
+
+        assert_eq!(List::<i32>::new().clone().pop(), None);
+    }
+
+    #[test]
+    fn retain() {
+        fn retained(elems: &[i32], pred: impl FnMut(&i32) -> bool) -> Vec<i32> {
+            let mut list = List::with_block_size(2);
+            for &elem in elems {
+                list.push(elem);
+            }
+            list.retain(pred);
+            list.assert_valid();
+            // the tail has to be right for this to land at the end
+            list.push(100);
+            list.assert_valid();
+            list.into_iter().collect()
+        }
+
+        assert_eq!(retained(&[1, 2, 3, 4, 5], |e| e % 2 == 0), [2, 4, 100]);
+        // head and tail both go
+        assert_eq!(retained(&[1, 2, 3], |&e| e == 2), [2, 100]);
+        // only the tail goes
+        assert_eq!(retained(&[1, 2, 3], |&e| e != 3), [1, 2, 100]);
+        assert_eq!(retained(&[1, 2, 3], |_| false), [100]);
+        assert_eq!(retained(&[1, 2, 3], |_| true), [1, 2, 3, 100]);
+        assert_eq!(retained(&[], |_| false), [100]);
+    }
+
+    #[test]
+    fn retain_drops_the_rest() {
+        let canary = Rc::new(());
+        let mut list = List::new();
+        for i in 0..6 {
+            list.push((i, Rc::clone(&canary)));
+        }
+        list.retain(|(i, _)| *i < 2);
+        assert_eq!(Rc::strong_count(&canary), 3);
+        drop(list);
+        assert_eq!(Rc::strong_count(&canary), 1);
+    }
+
+    #[test]
+    fn bounded() {
+        let mut buffer = List::with_capacity(3, WhenFull::Panic);
+        assert_eq!(buffer.capacity(), Some(3));
+        assert!(buffer.is_empty());
+
+        for round in 0..3 {
+            // fill it to the brim...
+            for elem in 0..3 {
+                assert_eq!(buffer.try_push(round * 10 + elem), Ok(()));
+            }
+            assert_eq!(buffer.len(), 3);
+            assert_eq!(buffer.try_push(99), Err(99));
+            buffer.assert_valid();
+
+            // ...one out makes room for one in
+            assert_eq!(buffer.pop(), Some(round * 10));
+            assert_eq!(buffer.try_push(99), Ok(()));
+            assert_eq!(buffer.try_push(99), Err(99));
+
+            // ...and drain it
+            while buffer.pop().is_some() {}
+            assert!(buffer.is_empty());
+            buffer.assert_valid();
+        }
+        assert_eq!(List::<i32>::new().capacity(), None);
+    }
+
+    #[test]
+    fn huge_capacity() {
+        // the blocks are capped, so a big buffer doesn't allocate its whole
+        // capacity on the first push; and usize::MAX is a capacity like any other
+        let mut buffer = List::with_capacity(usize::MAX, WhenFull::Panic);
+        assert_eq!(buffer.capacity(), Some(usize::MAX));
+        assert_eq!(buffer.block_size(), MAX_BOUNDED_BLOCK);
+        buffer.push(1);
+        assert_eq!(buffer.try_push(2), Ok(()));
+        assert_eq!(buffer.len(), 2);
+
+        let small = List::<u8>::with_capacity(3, WhenFull::Panic);
+        assert_eq!(small.block_size(), 3);
+        let big = List::<u8>::with_capacity(1_000_000, WhenFull::Panic);
+        assert_eq!(big.block_size(), MAX_BOUNDED_BLOCK);
+    }
+
+    #[test]
+    #[should_panic(expected = "queue is full (capacity 2)")]
+    fn bounded_push_panics() {
+        let mut buffer = List::with_capacity(2, WhenFull::Panic);
+        buffer.push(1);
+        buffer.push(2);
+        buffer.push(3);
+    }
+
+    #[test]
+    fn bounded_push_grows() {
+        let mut buffer = List::with_capacity(2, WhenFull::Grow);
+        buffer.push(1);
+        buffer.push(2);
+        assert_eq!(buffer.try_push(3), Err(3));
+        buffer.push(3);
+        assert_eq!(buffer.capacity(), Some(4));
+        buffer.insert_sorted(0);
+        assert_eq!(buffer.try_push(5), Err(5));
+        buffer.assert_valid();
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3]);
+
+        let mut empty = List::with_capacity(0, WhenFull::Grow);
+        assert_eq!(empty.try_push(1), Err(1));
+        empty.push(1);
+        assert_eq!(empty.capacity(), Some(1));
+    }
+
+    #[test]
+    fn push_slice() {
+        let mut queue = List::with_block_size(3);
+        queue.push_slice(&[]);
+        queue.assert_valid();
+        queue.push_slice(&[1, 2]);
+        queue.assert_valid();
+        queue.push(3);
+        queue.push_slice(&[4, 5, 6, 7]);
+        queue.assert_valid();
+        assert_eq!(queue.len(), 7);
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            [2, 3, 4, 5, 6, 7]
+        );
+
+        let names = [String::from("a"), String::from("b")];
+        let mut cloned = List::new();
+        cloned.push_slice(&names);
+        assert_eq!(cloned.iter().collect::<Vec<_>>(), [&names[0], &names[1]]);
+    }
+
+    #[test]
+    fn push_slice_into_bounded() {
+        let mut grows = List::with_capacity(2, WhenFull::Grow);
+        grows.push_slice(&[1, 2, 3, 4, 5]);
+        assert_eq!(grows.capacity(), Some(8));
+
+        let mut panics = List::with_capacity(2, WhenFull::Panic);
+        panics.push(0);
+        // all or nothing: the whole slice is refused up front
+        let full = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            panics.push_slice(&[1, 2]);
+        }));
+        assert!(full.is_err());
+        panics.assert_valid();
+        assert_eq!(panics.len(), 1);
+    }
+
+    #[test]
+    fn raw_parts() {
+        let canary = Rc::new(());
+        let mut queue = List::with_block_size(4);
+        for _ in 0..6 {
+            queue.push(Rc::clone(&canary));
+        }
+
+        let (head, tail, len) = queue.into_raw_parts();
+        // the chain survived its list: nothing dropped, nothing freed
+        assert_eq!((len, Rc::strong_count(&canary)), (6, 7));
+
+        let mut queue = unsafe { List::from_raw_parts(head, tail, len) };
+        queue.assert_valid();
+        drop(queue.pop());
+        queue.push(Rc::clone(&canary));
+        assert_eq!(queue.len(), 6);
+        // freed exactly once, by the rebuilt list
+        drop(queue);
+        assert_eq!(Rc::strong_count(&canary), 1);
+
+        let (head, tail, len) = List::<i32>::new().into_raw_parts();
+        assert!(head.is_null() && tail.is_null() && len == 0);
+        let empty = unsafe { List::<i32>::from_raw_parts(head, tail, len) };
+        empty.assert_valid();
+    }
+
+    #[test]
+    fn take_all() {
+        let canary = Rc::new(());
+        let mut queue = List::with_capacity(4, WhenFull::Panic);
+        for _ in 0..4 {
+            queue.push(Rc::clone(&canary));
+        }
+
+        let mut batch = queue.take_all();
+        assert!(queue.is_empty());
+        assert_eq!(queue.capacity(), Some(4));
+        queue.assert_valid();
+        // the whole capacity is free again, while the batch still holds its nodes
+        for _ in 0..4 {
+            assert!(queue.try_push(Rc::clone(&canary)).is_ok());
+        }
+        assert_eq!(Rc::strong_count(&canary), 9);
+
+        drop(batch.next());
+        drop(batch);
+        assert_eq!(Rc::strong_count(&canary), 5);
+        assert_eq!(queue.take_all().count(), 4);
+        assert_eq!(queue.take_all().next(), None);
+        assert_eq!(Rc::strong_count(&canary), 1);
+    }
+
+    #[test]
+    fn pop_if() {
+        let mut queue = List::new();
+        assert_eq!(queue.pop_if(|_| true), None);
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(queue.pop_if(|&elem| elem == 2), None);
+        assert_eq!(queue.pop_if(|&elem| elem == 1), Some(1));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop_if(|&elem| elem == 2), Some(2));
+        queue.assert_valid();
+    }
+
+    #[test]
+    fn cycle_detection() {
+        let mut queue = List::new();
+        assert_eq!((queue.has_cycle(), queue.cycle_start()), (false, None));
+        for elem in 0..5 {
+            queue.push(elem);
+            assert!(!queue.has_cycle());
+        }
+
+        for to in 0..5 {
+            queue.make_cycle_for_test(to);
+            assert_eq!((queue.has_cycle(), queue.cycle_start()), (true, Some(to)));
+            unsafe { (*queue.tail).next = ptr::null_mut() };
+        }
+        queue.assert_valid();
+    }
+
+    #[test]
+    fn middle() {
+        let mut queue = List::new();
+        assert_eq!((queue.middle(), queue.middle_index()), (None, None));
+        for len in 1..=6 {
+            queue.push(len - 1);
+            let middle = (len - 1) / 2;
+            assert_eq!(queue.middle(), Some(&middle));
+            assert_eq!(queue.middle_index(), Some(middle));
+        }
+    }
+
+    #[test]
+    fn is_palindrome() {
+        for (elems, palindrome) in [
+            (&[][..], true),
+            (&[1], true),
+            (&[1, 2], false),
+            (&[1, 2, 1], true),
+            (&[1, 2, 2, 1], true),
+            (&[1, 2, 3, 1], false),
+            (&[1, 2, 3, 2, 1], true),
+            (&[2, 2, 3, 1, 2], false),
+        ] {
+            let mut queue = List::new();
+            queue.push_slice(elems);
+            assert_eq!(queue.is_palindrome(), palindrome, "{elems:?}");
+            queue.assert_valid();
+            assert!(queue.iter().eq(elems));
+            // tail's still the real tail
+            queue.push(9);
+            assert_eq!(queue.from_end(0), Some(&9));
+        }
+    }
+
+    #[test]
+    fn is_palindrome_panicking_eq() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        // panics when asked about 0
+        #[derive(Debug)]
+        struct Touchy(i32);
+
+        impl PartialEq for Touchy {
+            fn eq(&self, other: &Self) -> bool {
+                assert!(self.0 != 0 && other.0 != 0, "touched");
+                self.0 == other.0
+            }
+        }
+
+        let mut queue = List::new();
+        for elem in [1, 0, 3, 0, 1] {
+            queue.push(Touchy(elem));
+        }
+        assert!(catch_unwind(AssertUnwindSafe(|| queue.is_palindrome())).is_err());
+        // the back half is back where it was, and tail with it
+        queue.assert_valid();
+        queue.push(Touchy(9));
+        assert!(queue.iter().map(|elem| elem.0).eq([1, 0, 3, 0, 1, 9]));
+    }
+
+    #[test]
+    fn merge() {
+        let merged = from(&[1, 4, 6]).merge(from(&[2, 3, 7, 8]));
+        merged.assert_valid();
+        assert!(merged.iter().eq(&[1, 2, 3, 4, 6, 7, 8]));
+        assert!(List::<i32>::merge_k(vec![]).is_empty());
+        let mut merged = List::merge_k(vec![from(&[5]), from(&[]), from(&[1, 9]), from(&[2, 5])]);
+        merged.assert_valid();
+        assert!(merged.iter().eq(&[1, 2, 5, 5, 9]));
+        merged.push(10);
+        assert_eq!(merged.from_end(1), Some(&9));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn merge_k_sorts(mut runs in proptest::collection::vec(
+            proptest::collection::vec(-20i32..20, 0..16),
+            0..8,
+        )) {
+            let lists = runs
+                .iter_mut()
+                .map(|run| {
+                    run.sort_unstable();
+                    let mut queue = List::new();
+                    queue.push_slice(run);
+                    queue
+                })
+                .collect();
+            let merged = List::merge_k(lists);
+
+            let mut expected = runs.concat();
+            expected.sort_unstable();
+            merged.assert_valid();
+            proptest::prop_assert_eq!(merged.len(), expected.len());
+            proptest::prop_assert!(merged.iter().eq(&expected));
+        }
+    }
+
+    #[test]
+    fn reverse_in_groups() {
+        for (k, reverse_rest, reversed) in [
+            (1, false, &[1, 2, 3, 4, 5, 6, 7][..]),
+            (2, false, &[2, 1, 4, 3, 6, 5, 7]),
+            (2, true, &[2, 1, 4, 3, 6, 5, 7]),
+            (3, false, &[3, 2, 1, 6, 5, 4, 7]),
+            (4, false, &[4, 3, 2, 1, 5, 6, 7]),
+            (4, true, &[4, 3, 2, 1, 7, 6, 5]),
+            (7, false, &[7, 6, 5, 4, 3, 2, 1]),
+            (10, false, &[1, 2, 3, 4, 5, 6, 7]),
+            (10, true, &[7, 6, 5, 4, 3, 2, 1]),
+        ] {
+            let mut queue = List::new();
+            queue.push_slice(&[1, 2, 3, 4, 5, 6, 7]);
+            queue.reverse_in_groups(k, reverse_rest);
+            queue.assert_valid();
+            assert!(queue.iter().eq(reversed), "k = {k}");
+            // tail moved along with everything else
+            queue.push(8);
+            assert_eq!(queue.from_end(1), reversed.last());
+        }
+
+        let mut empty = List::<i32>::new();
+        empty.reverse_in_groups(3, true);
+        empty.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "group size must be non-zero")]
+    fn reverse_in_groups_of_zero() {
+        List::<i32>::new().reverse_in_groups(0, false);
+    }
+
+    #[test]
+    fn partition3() {
+        let mut queue = List::new();
+        queue.push_slice(&[5, 1, 9, 5, 3, 7, 5, 0]);
+
+        let (less, equal, greater) = queue.partition3(&5);
+        for part in [&less, &equal, &greater] {
+            part.assert_valid();
+        }
+        assert!(less.iter().eq(&[1, 3, 0]));
+        assert!(equal.iter().eq(&[5, 5, 5]));
+        assert!(greater.iter().eq(&[9, 7]));
+
+        let (less, equal, greater) = greater.partition3(&100);
+        assert_eq!((less.len(), equal.len(), greater.len()), (2, 0, 0));
+        equal.assert_valid();
+    }
+
+    #[test]
+    fn clear() {
+        let mut queue = List::with_capacity(1_000_000, WhenFull::Panic);
+        for i in 0..1_000_000 {
+            queue.push(i);
+        }
+        queue.clear();
+        queue.assert_valid();
+        assert_eq!((queue.len(), queue.capacity()), (0, Some(1_000_000)));
+        // still bounded, and room for it all again
+        for i in 0..1_000_000 {
+            queue.push(i);
+        }
+        assert_eq!(queue.try_push(0), Err(0));
+
+        let taken = std::mem::take(&mut queue);
+        assert_eq!((taken.len(), queue.len()), (1_000_000, 0));
+    }
+}
+
+// Model checking with Kani (`cargo kani`). Each harness runs every sequence of
+// operations up to its bound, with every possible value, and Kani checks each
+// raw pointer dereference along the way is to live memory, on top of the
+// asserts. Bounds are kept small: the state space doubles with every step.
+#[cfg(kani)]
+mod verification {
+    use super::List;
+
+    const OPS: usize = 6;
+
+    // Pushes and pops in any order pop in FIFO order, and len keeps count.
+    #[kani::proof]
+    #[kani::unwind(8)]
+    fn push_pop() {
+        let mut queue = List::new();
+        // what should be in the queue: model[front..back]
+        let mut model = [0u8; OPS];
+        let (mut front, mut back) = (0, 0);
+        for _ in 0..OPS {
+            if kani::any() {
+                let elem: u8 = kani::any();
+                queue.push(elem);
+                model[back] = elem;
+                back += 1;
+            } else {
+                let popped = queue.pop();
+                if front == back {
+                    assert!(popped.is_none());
+                } else {
+                    assert_eq!(popped, Some(model[front]));
+                    front += 1;
+                }
+            }
+            assert_eq!(queue.len(), back - front);
+            queue.assert_valid();
+        }
+    }
+
+    // Splitting anywhere leaves two valid queues that add back up to the one
+    // split, and concat puts them back together.
+    #[kani::proof]
+    #[kani::unwind(6)]
+    fn split_at_then_concat() {
+        let len: usize = kani::any();
+        kani::assume(len <= 4);
+        let mid: usize = kani::any();
+        kani::assume(mid <= len);
+
+        let mut queue = List::new();
+        for elem in 0..len {
+            queue.push(elem);
+        }
+        let (mut front, mut back) = queue.split_at(mid);
+        front.assert_valid();
+        back.assert_valid();
+        assert_eq!((front.len(), back.len()), (mid, len - mid));
+
+        // both halves still work on their own
+        front.push(len);
+        back.push(len);
+        assert_eq!(back.pop(), Some(if mid == len { len } else { mid }));
+
+        let joined = List::concat([front, back]);
+        joined.assert_valid();
+        assert_eq!(joined.len(), len + 1);
+    }
 }
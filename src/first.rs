@@ -13,12 +13,16 @@
 // Also, creating a pub struct that wraps these two allows us to keep the other two
 // private.
 
-use std::mem;
+use crate::trace::trace;
+use alloc::boxed::Box;
+use core::mem;
 
 // struct w single field -> zero cost abstraction!
 #[derive(Debug)]
 pub struct List<T> {
     head: Link<T>,
+    #[cfg(feature = "stats")]
+    stats: crate::stats::Counters,
 }
 
 #[derive(Debug)]
@@ -39,10 +43,31 @@ enum Link<T> {
     Cons(Box<Node<T>>),
 }
 
+// ...which we can hold the compiler to: Nil is the null pointer, so a Link is
+// exactly one Box wide, and a node is its value plus that one pointer.
+const _: () = assert!(size_of::<Link<u64>>() == size_of::<usize>());
+const _: () = assert!(size_of::<Node<u64>>() == size_of::<(u64, usize)>());
+const _: () = assert!(align_of::<Node<u64>>() == align_of::<(u64, usize)>());
+
+#[cfg(any(feature = "serde", feature = "viz"))]
+impl<T> Link<T> {
+    // Our homemade Option::as_deref — handy for walking the list by reference.
+    const fn as_node(&self) -> Option<&Node<T>> {
+        match self {
+            Self::Nil => None,
+            Self::Cons(boxed_node) => Some(boxed_node),
+        }
+    }
+}
+
 impl<T> List<T> {
     #[must_use] // linter error if invoked without binding return value
     pub const fn new() -> Self {
-        Self { head: Link::Nil }
+        Self {
+            head: Link::Nil,
+            #[cfg(feature = "stats")]
+            stats: crate::stats::Counters::new(),
+        }
     }
 
     pub fn push_front(&mut self, new_value: T) {
@@ -55,8 +80,12 @@ impl<T> List<T> {
             // below.
             next: mem::replace(&mut self.head, Link::Nil),
         };
+        trace!("replace head with Nil");
 
         self.head = Link::Cons(Box::new(new_node));
+        trace!("box new node as head");
+        #[cfg(feature = "stats")]
+        self.stats.push();
     }
 
     pub fn pop_front(&mut self) -> Option<T> {
@@ -71,7 +100,11 @@ impl<T> List<T> {
         match mem::replace(&mut self.head, Link::Nil) {
             Link::Nil => None,
             Link::Cons(node) => {
+                trace!("replace head with Nil");
                 self.head = node.next;
+                trace!("relink head to next");
+                #[cfg(feature = "stats")]
+                self.stats.pop();
                 Some(node.value)
             }
         }
@@ -84,11 +117,19 @@ impl<T> Default for List<T> {
     }
 }
 
+#[cfg(feature = "stats")]
+impl<T> List<T> {
+    #[must_use]
+    pub fn stats(&self) -> crate::stats::Stats {
+        self.stats.snapshot()
+    }
+}
+
 // Default Drop isn't fully tail recursive! Namely, Box<Node> must drop its Node before
 // deallocating itself.
 // To fix this, we change all links in the list to Nil to avoid recursive drops.
-impl<T> Drop for List<T> {
-    fn drop(&mut self) {
+impl<T> List<T> {
+    pub fn clear(&mut self) {
         let mut current_link = mem::replace(&mut self.head, Link::Nil);
         while let Link::Cons(mut boxed_node) = current_link {
             current_link = mem::replace(&mut boxed_node.next, Link::Nil);
@@ -96,7 +137,84 @@ impl<T> Drop for List<T> {
     }
 }
 
-fn main() {}
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+// Lists serialize as plain sequences, front to back. A stack can only grow at the front,
+// so deserializing means collecting everything first and pushing back-to-front.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let nodes = || std::iter::successors(self.head.as_node(), |node| node.next.as_node());
+
+        // Some formats (bincode) insist on knowing the length up front, so we walk twice.
+        let mut seq = serializer.serialize_seq(Some(nodes().count()))?;
+        for node in nodes() {
+            seq.serialize_element(&node.value)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let elems = Vec::<T>::deserialize(deserializer)?;
+        let mut list = Self::new();
+        for elem in elems.into_iter().rev() {
+            list.push_front(elem);
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(feature = "viz")]
+impl<T: std::fmt::Debug> List<T> {
+    fn graph(&self) -> crate::viz::Graph {
+        let mut graph = crate::viz::Graph::new();
+        for node in std::iter::successors(self.head.as_node(), |node| node.next.as_node()) {
+            graph.node(node, &node.value);
+            if let Some(next) = node.next.as_node() {
+                graph.edge(node, next, "next");
+            }
+        }
+        if let Some(head) = self.head.as_node() {
+            graph.marker("head", head);
+        }
+        graph
+    }
+
+    /// Dumps the list's structure as a Graphviz DOT graph.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        self.graph().to_dot()
+    }
+
+    /// Same as [`List::to_dot`], but as a Mermaid `graph LR` diagram, ready to paste into
+    /// markdown.
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        self.graph().to_mermaid()
+    }
+
+    /// Draws the list as ASCII art, e.g. `HEAD -> [2] -> [5] -> ∅`.
+    #[must_use]
+    pub fn render_ascii(&self) -> String {
+        self.graph().to_ascii()
+    }
+
+    /// Same as [`List::to_dot`], but as JSON, for drawing somewhere other than a
+    /// terminal (examples/wasm-viz draws it in a browser).
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        self.graph().to_json()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -118,4 +236,72 @@ mod tests {
         assert_eq!(list.pop_front(), Some(7));
         assert_eq!(list.pop_front(), None);
     }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn to_dot() {
+        use crate::viz::dot_id;
+
+        assert!(!List::<i32>::new().to_dot().contains("->"));
+
+        let mut list = List::new();
+        list.push_front(5);
+        list.push_front(2);
+
+        let dot = list.to_dot();
+        let (two, five) = (dot_id(&dot, "2"), dot_id(&dot, "5"));
+        assert!(dot.contains(&format!("head -> {two};")));
+        assert!(dot.contains(&format!("{two} -> {five} [label=\"next\"];")));
+        assert_eq!(dot.matches("->").count(), 2);
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn trace() {
+        let log = crate::trace::capture(|| {
+            let mut list = List::new();
+            list.push_front(1);
+            list.pop_front();
+        });
+        assert_eq!(
+            log,
+            [
+                "replace head with Nil",
+                "box new node as head",
+                "replace head with Nil",
+                "relink head to next",
+            ]
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats() {
+        let mut list = List::new();
+        list.push_front(1);
+        list.push_front(2);
+        list.pop_front();
+        list.pop_front();
+        list.pop_front();
+
+        let stats = list.stats();
+        assert_eq!((stats.pushes, stats.allocations), (2, 2));
+        // popping an empty list isn't a pop
+        assert_eq!(stats.pops, 2);
+    }
+
+    #[test]
+    fn long_clear() {
+        // one stack frame per node would blow the stack here
+        let mut list = List::new();
+        for i in 0..1_000_000 {
+            list.push_front(i);
+        }
+        list.clear();
+        assert_eq!(list.pop_front(), None);
+
+        list.push_front(1);
+        let mut taken = std::mem::take(&mut list);
+        assert_eq!((taken.pop_front(), list.pop_front()), (Some(1), None));
+    }
 }
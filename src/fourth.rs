@@ -1,11 +1,28 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
+use alloc::collections::LinkedList;
+use alloc::rc::{Rc, Weak};
+use alloc::vec::Vec;
+use core::cell::{Ref, RefCell, RefMut};
+use core::fmt;
+use core::mem;
+use core::ops::{Add, AddAssign};
+
+use crate::third::{MemoryUsage, RcBox};
+use crate::trace::trace;
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 
+// Still one pointer per link. Each node is its elem plus two links, and the
+// RefCell around it adds exactly one word: the borrow flag.
+const _: () = assert!(size_of::<Link<u64>>() == size_of::<usize>());
+const _: () = assert!(size_of::<Node<u64>>() == size_of::<(u64, usize, usize)>());
+const _: () = assert!(align_of::<Node<u64>>() == align_of::<(u64, usize, usize)>());
+const _: () = assert!(size_of::<RefCell<Node<u64>>>() == size_of::<(isize, Node<u64>)>());
+
 pub struct List<T> {
     head: Link<T>,
     tail: Link<T>,
+    #[cfg(feature = "stats")]
+    stats: crate::stats::Counters,
 }
 
 struct Node<T> {
@@ -16,71 +33,132 @@ struct Node<T> {
 
 impl<T> List<T> {
     #[must_use]
-    const fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
             head: None,
             tail: None,
+            #[cfg(feature = "stats")]
+            stats: crate::stats::Counters::new(),
         }
     }
 
-    fn push_front(&mut self, elem: T) {
+    pub fn push_front(&mut self, elem: T) {
         let new_head = Node::new(elem);
         if let Some(old_head) = self.head.take() {
+            trace!("take head");
             self.head = Some(new_head.clone());
             old_head.borrow_mut().prev = Some(new_head.clone());
+            trace!(strong = Rc::strong_count(&new_head), "relink prev");
             new_head.borrow_mut().next = Some(old_head);
+            trace!("relink next");
         } else {
             self.head = Some(new_head.clone());
             self.tail = Some(new_head);
+            trace!("set head and tail");
         }
+        #[cfg(feature = "stats")]
+        self.stats.push();
     }
 
-    fn push_back(&mut self, elem: T) {
+    pub fn push_back(&mut self, elem: T) {
         let new_tail = Node::new(elem);
 
         if let Some(old_tail) = self.tail.take() {
+            trace!("take tail");
             self.tail = Some(new_tail.clone());
             old_tail.borrow_mut().next = Some(new_tail.clone());
+            trace!(strong = Rc::strong_count(&new_tail), "relink next");
             new_tail.borrow_mut().prev = Some(old_tail);
+            trace!("relink prev");
         } else {
-            self.tail = Some(new_tail.clone());
+            self.head = Some(new_tail.clone());
             self.tail = Some(new_tail);
+            trace!("set head and tail");
         }
+        #[cfg(feature = "stats")]
+        self.stats.push();
     }
 
-    fn pop_front(&mut self) -> Option<T> {
+    pub fn pop_front(&mut self) -> Option<T> {
         self.head.take().map(|old_head| {
+            trace!("take head");
             match old_head.borrow_mut().next.take() {
                 Some(new_head) => {
+                    trace!("take next");
                     new_head.borrow_mut().prev.take();
+                    trace!("unlink prev");
                     self.head = Some(new_head);
                 }
                 None => {
                     // list is emptied after this pop
                     self.tail.take();
+                    trace!("take tail");
                 }
             }
+            trace!(strong = Rc::strong_count(&old_head), "unwrap old head");
+            #[cfg(feature = "stats")]
+            self.stats.pop();
             Rc::try_unwrap(old_head).ok().unwrap().into_inner().elem
         })
     }
 
-    fn pop_back(&mut self) -> Option<T> {
+    pub fn pop_back(&mut self) -> Option<T> {
         self.tail.take().map(|old_tail| {
+            trace!("take tail");
             match old_tail.borrow_mut().prev.take() {
                 Some(new_tail) => {
+                    trace!("take prev");
                     new_tail.borrow_mut().next.take();
+                    trace!("unlink next");
                     self.tail = Some(new_tail);
                 }
                 None => {
                     // list is emptied after this pop
                     self.head.take();
+                    trace!("take head");
                 }
             }
+            trace!(strong = Rc::strong_count(&old_tail), "unwrap old tail");
+            #[cfg(feature = "stats")]
+            self.stats.pop();
             Rc::try_unwrap(old_tail).ok().unwrap().into_inner().elem
         })
     }
 
-    fn peek_front(&self) -> Option<Ref<T>> {
+    /// Pops the front only if `pred` says so. The peek's `Ref` is dropped before
+    /// the pop, so there's no borrow left to trip over.
+    pub fn pop_front_if<F: FnOnce(&T) -> bool>(&mut self, pred: F) -> Option<T> {
+        if self.peek_front().is_some_and(|elem| pred(&elem)) {
+            self.pop_front()
+        } else {
+            None
+        }
+    }
+
+    pub fn pop_back_if<F: FnOnce(&T) -> bool>(&mut self, pred: F) -> Option<T> {
+        if self.peek_back().is_some_and(|elem| pred(&elem)) {
+            self.pop_back()
+        } else {
+            None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        loop {
+            match self.try_pop_front() {
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                // can't have its elem, so just cut it loose; whoever holds it
+                // drops it
+                Err(SharedNode) => {
+                    let head = self.head.clone().unwrap();
+                    self.unlink(&head);
+                }
+            }
+        }
+    }
+
+    pub fn peek_front(&self) -> Option<Ref<'_, T>> {
         // Returning Option<T> would be SO HARD with RefCells. RefCells produce
         // Ref[Mut]<'_, T>, which helps enforce runtime reference validation.
         // We can't access T without going through a Ref first.
@@ -91,31 +169,277 @@ impl<T> List<T> {
             .map(|node| Ref::map(node.borrow(), |node| &node.elem))
     }
 
-    fn peek_front_mut(&self) -> Option<RefMut<T>> {
+    pub fn peek_front_mut(&self) -> Option<RefMut<'_, T>> {
         self.head
             .as_ref()
             .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
     }
 
-    fn peek_back(&self) -> Option<Ref<T>> {
+    pub fn peek_back(&self) -> Option<Ref<'_, T>> {
         self.tail
             .as_ref()
             .map(|node| Ref::map(node.borrow(), |node| &node.elem))
     }
 
-    fn peek_back_mut(&self) -> Option<RefMut<T>> {
+    pub fn peek_back_mut(&self) -> Option<RefMut<'_, T>> {
         self.tail
             .as_ref()
             .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
     }
+}
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> List<T> {
+    /// A copy with brand new nodes, front to back.
+    ///
+    /// Why not just clone the head and tail Rcs? Then two lists would share every
+    /// node, and each would think it owns them: popping from one relinks nodes
+    /// the other is still walking, and `pop_front`'s `Rc::try_unwrap` panics on
+    /// the first node whose count isn't 1. A deque only works if it's the sole
+    /// owner of its chain.
+    #[must_use]
+    pub fn deep_clone(&self) -> Self {
+        let mut copy = Self::new();
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            copy.push_back(node.borrow().elem.clone());
+            current = node.borrow().next.clone();
+        }
+        copy
+    }
+}
+
+// Same as std's LinkedList: extending means the back.
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.extend_back(iter);
+    }
+}
+
+// Clone can only mean the deep kind; see above.
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.deep_clone()
+    }
+}
+
+#[cfg(feature = "stats")]
+impl<T> List<T> {
+    #[must_use]
+    pub fn stats(&self) -> crate::stats::Stats {
+        self.stats.snapshot()
+    }
+}
+
+// Moving to and from std's LinkedList, one element at a time. Order is preserved both ways.
+impl<T> From<LinkedList<T>> for List<T> {
+    fn from(std_list: LinkedList<T>) -> Self {
+        let mut list = Self::new();
+        for elem in std_list {
+            list.push_back(elem);
+        }
+        list
+    }
+}
+
+impl<T> From<List<T>> for LinkedList<T> {
+    fn from(list: List<T>) -> Self {
+        list.into_iter().collect()
+    }
+}
+
+// Same again for Vec, front to back.
+impl<T> From<Vec<T>> for List<T> {
+    fn from(vec: Vec<T>) -> Self {
+        let mut list = Self::new();
+        list.extend_back(vec);
+        list
+    }
+}
+
+impl<T> From<List<T>> for Vec<T> {
+    fn from(list: List<T>) -> Self {
+        list.into_iter().collect()
+    }
+}
+
+// So `assert_eq!(list, vec![1, 2, 3])` works. The deque can't lend out plain
+// references, so these walk it comparing one borrowed node at a time.
+impl<T> List<T> {
+    fn eq_elems<U>(&self, others: &[U]) -> bool
+    where
+        T: PartialEq<U>,
+    {
+        let mut others = others.iter();
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            match others.next() {
+                Some(other) if node.borrow().elem == *other => {}
+                _ => return false,
+            }
+            current = node.borrow().next.clone();
+        }
+        others.next().is_none()
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<&[U]> for List<T> {
+    fn eq(&self, other: &&[U]) -> bool {
+        self.eq_elems(other)
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for List<T> {
+    fn eq(&self, other: &Vec<U>) -> bool {
+        self.eq_elems(other)
+    }
+}
+
+// Prints like a Vec: [1, 2, 3].
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            list.entry(&node.borrow().elem);
+            current = node.borrow().next.clone();
+        }
+        list.finish()
+    }
+}
+
+/// Concatenation, `String`-style: `a + b` eats both lists. Relinking the two
+/// ends is O(1), no matter how long either list is.
+///
+/// ```
+/// use too_many_linked_lists::fourth::List;
+///
+/// let mut a = List::new();
+/// a.push_back(1);
+/// let mut b = List::new();
+/// b.push_back(2);
+///
+/// let mut ab = a + b;
+/// ab += List::new();
+/// assert_eq!(ab.into_iter().collect::<Vec<_>>(), [1, 2]);
+/// ```
+///
+/// `+=` moves the right-hand side in, so it's gone afterwards:
+///
+/// ```compile_fail
+/// use too_many_linked_lists::fourth::List;
+///
+/// let mut a: List<i32> = List::new();
+/// let b = List::new();
+/// a += b;
+/// b.peek_front();
+/// ```
+impl<T> Add for List<T> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl<T> AddAssign for List<T> {
+    fn add_assign(&mut self, mut other: Self) {
+        #[cfg(feature = "stats")]
+        self.stats.absorb(&other.stats);
+        let (Some(their_head), Some(their_tail)) = (other.head.take(), other.tail.take()) else {
+            return;
+        };
+        match self.tail.take() {
+            Some(our_tail) => {
+                their_head.borrow_mut().prev = Some(our_tail.clone());
+                our_tail.borrow_mut().next = Some(their_head);
+            }
+            None => self.head = Some(their_head),
+        }
+        self.tail = Some(their_tail);
+    }
+}
+
+impl<T> List<T> {
+    /// Borrows the list through `std::collections::LinkedList`'s method names, so code
+    /// written against std can move over a bit at a time.
+    pub fn as_std_compat(&mut self) -> StdCompat<'_, T> {
+        StdCompat(self)
+    }
+}
+
+/// A [`List`] wearing `std::collections::LinkedList`'s API. Peeks still hand out `Ref`s
+/// instead of plain references — we can't get around the RefCells.
+pub struct StdCompat<'a, T>(&'a mut List<T>);
+
+impl<T> StdCompat<'_, T> {
+    #[must_use]
+    pub fn front(&self) -> Option<Ref<'_, T>> {
+        self.0.peek_front()
+    }
+
+    #[must_use]
+    pub fn back(&self) -> Option<Ref<'_, T>> {
+        self.0.peek_back()
+    }
+
+    pub fn front_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.0.peek_front_mut()
+    }
+
+    pub fn back_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.0.peek_back_mut()
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.0.push_front(elem);
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.0.push_back(elem);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.head.is_none()
+    }
+
+    // Unlike std's, this is O(n): we don't keep a count.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        core::iter::successors(self.0.head.clone(), |node| node.borrow().next.clone()).count()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
     }
 }
 
 pub struct IntoIter<T>(List<T>);
 
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
 
@@ -131,128 +455,1785 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
     }
 }
 
-// We must self-implement drop to avoid reference cycles.
-impl<T> Drop for List<T> {
-    fn drop(&mut self) {
-        while self.pop_front().is_some() {}
+impl<T> IntoIter<T> {
+    /// `next`, but an end node that's still shared comes back as an error instead
+    /// of a panic. The node stays where it is, so this keeps failing until
+    /// whoever holds it lets go (which a forgotten `ExtractIf` never does).
+    pub fn try_next(&mut self) -> Option<Result<T, SharedNode>> {
+        self.0.try_pop_front().transpose()
     }
-}
 
-impl<T> Node<T> {
-    #[must_use]
-    fn new(elem: T) -> Rc<RefCell<Self>> {
-        Rc::new(RefCell::new(Self {
-            elem,
-            next: None,
-            prev: None,
-        }))
+    /// `next_back`, with the same deal as `try_next`.
+    pub fn try_next_back(&mut self) -> Option<Result<T, SharedNode>> {
+        self.0.try_pop_back().transpose()
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::List;
+/// An end node is held by an Rc outside the deque, so its element can't be moved
+/// out of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedNode;
 
-    #[test]
-    fn basics() {
-        let mut list = List::new();
+impl fmt::Display for SharedNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("node is still shared outside the deque")
+    }
+}
 
-        // Check empty list behaves right
-        assert_eq!(list.pop_front(), None);
+impl core::error::Error for SharedNode {}
 
-        // Populate list
-        list.push_front(1);
-        list.push_front(2);
-        list.push_front(3);
+// pop_front and pop_back `Rc::try_unwrap(..).unwrap()`, which panics if anything
+// else still holds the node. An end node is always held exactly twice from
+// inside: by head (or tail), and by its neighbour (or the other end, if it's the
+// only node). Any more than that is someone outside, so check first and leave
+// the list untouched. From safe code, that someone is an ExtractIf: it holds
+// the node it'll show pred next, and if it's mem::forget-ed, it always will.
+impl<T> List<T> {
+    pub fn try_pop_front(&mut self) -> Result<Option<T>, SharedNode> {
+        match &self.head {
+            Some(head) if Rc::strong_count(head) > 2 => Err(SharedNode),
+            _ => Ok(self.pop_front()),
+        }
+    }
 
-        // Check normal removal
-        assert_eq!(list.pop_front(), Some(3));
-        assert_eq!(list.pop_front(), Some(2));
+    pub fn try_pop_back(&mut self) -> Result<Option<T>, SharedNode> {
+        match &self.tail {
+            Some(tail) if Rc::strong_count(tail) > 2 => Err(SharedNode),
+            _ => Ok(self.pop_back()),
+        }
+    }
+}
 
-        // Push some more just to make sure nothing's corrupted
-        list.push_front(4);
-        list.push_front(5);
+// The same accounting as third.rs, for comparison. Deques never share nodes
+// with each other, so `bytes` and `unshared_bytes` always agree here; what
+// costs more is each node, with two links and a RefCell.
+impl<T> List<T> {
+    /// How many distinct nodes the deques hold between them.
+    #[must_use]
+    pub fn reachable_nodes<'a>(lists: impl IntoIterator<Item = &'a Self>) -> usize
+    where
+        T: 'a,
+    {
+        Self::memory_usage(lists).nodes
+    }
 
-        // Check normal removal
-        assert_eq!(list.pop_front(), Some(5));
-        assert_eq!(list.pop_front(), Some(4));
+    /// Tallies up the distinct nodes, by address, and their bytes, Rc counts
+    /// and borrow flags included.
+    #[must_use]
+    pub fn memory_usage<'a>(lists: impl IntoIterator<Item = &'a Self>) -> MemoryUsage
+    where
+        T: 'a,
+    {
+        let node_bytes = size_of::<RcBox<RefCell<Node<T>>>>();
+        let mut seen = alloc::collections::BTreeSet::new();
+        let mut usage = MemoryUsage::default();
+        for list in lists {
+            let mut current = list.head.clone();
+            while let Some(node) = current {
+                usage.unshared_bytes += node_bytes;
+                if seen.insert(Rc::as_ptr(&node)) {
+                    usage.nodes += 1;
+                    usage.bytes += node_bytes;
+                }
+                current = node.borrow().next.clone();
+            }
+        }
+        usage
+    }
+}
 
-        // Check exhaustion
-        assert_eq!(list.pop_front(), Some(1));
-        assert_eq!(list.pop_front(), None);
+// We must self-implement drop to avoid reference cycles.
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
 
-        // ---- back -----
+impl<T: PartialEq> List<T> {
+    /// Unlinks the first node holding `value`. Returns whether there was one.
+    pub fn remove_first(&mut self, value: &T) -> bool {
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            if node.borrow().elem == *value {
+                self.unlink(&node);
+                return true;
+            }
+            current = node.borrow().next.clone();
+        }
+        false
+    }
 
-        // Check empty list behaves right
-        assert_eq!(list.pop_back(), None);
+    /// Unlinks every node holding `value`, in one pass. Returns how many went.
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        let mut removed = 0;
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            // grab next first: unlinking clears it
+            current = node.borrow().next.clone();
+            if node.borrow().elem == *value {
+                self.unlink(&node);
+                removed += 1;
+            }
+        }
+        removed
+    }
+}
 
-        // Populate list
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
+impl<T> List<T> {
+    /// Reverses the deque in place, in one walk: every node swaps its next and
+    /// prev, then head and tail trade places. Nothing is allocated or freed.
+    pub fn reverse(&mut self) {
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            let mut node = node.borrow_mut();
+            let node = &mut *node;
+            mem::swap(&mut node.next, &mut node.prev);
+            // what was next is prev now
+            current = node.prev.clone();
+        }
+        mem::swap(&mut self.head, &mut self.tail);
+        trace!("swap head and tail");
+    }
 
-        // Check normal removal
-        assert_eq!(list.pop_back(), Some(3));
-        assert_eq!(list.pop_back(), Some(2));
+    /// Pushes everything from `iter` onto the back, in order.
+    pub fn extend_back<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
 
-        // Push some more just to make sure nothing's corrupted
-        list.push_back(4);
-        list.push_back(5);
+    /// Puts everything from `iter` in front of what's already here, *in iterator
+    /// order*: extending `[3, 4]` with `1, 2` gives `[1, 2, 3, 4]`, not the
+    /// `[2, 1, 3, 4]` that `push_front` one at a time would.
+    pub fn extend_front<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut iter = iter.into_iter();
+        let Some(first) = iter.next() else {
+            return;
+        };
+        self.push_front(first);
+        // each new node goes in right behind the one before it
+        let mut last = self.head.clone().expect("just pushed");
+        for elem in iter {
+            last = self.insert_after(&last, elem);
+        }
+    }
 
-        // Check normal removal
-        assert_eq!(list.pop_back(), Some(5));
-        assert_eq!(list.pop_back(), Some(4));
+    /// Splits the deque in two at index `at`, like std's `LinkedList::split_off`:
+    /// we keep `[0, at)` and return `[at, len)`. Finding the spot is a walk; the
+    /// cut itself is two links and a tail.
+    ///
+    /// # Panics
+    ///
+    /// If `at` is past the end of the deque.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let mut back = Self::new();
+        // the last node we keep, and the first one we don't
+        let mut last: Link<T> = None;
+        let mut first = self.head.clone();
+        for walked in 0..at {
+            let node = first.unwrap_or_else(|| panic!("at > len ({at} > {walked})"));
+            first = node.borrow().next.clone();
+            last = Some(node);
+            #[cfg(feature = "stats")]
+            self.stats.step();
+        }
+        let Some(first) = first else {
+            // at == len: nothing to hand over
+            return back;
+        };
 
-        // Check exhaustion
-        assert_eq!(list.pop_back(), Some(1));
-        assert_eq!(list.pop_back(), None);
+        match last {
+            Some(last) => {
+                last.borrow_mut().next = None;
+                first.borrow_mut().prev = None;
+                back.tail = self.tail.replace(last);
+            }
+            None => {
+                // at == 0: everything goes
+                self.head = None;
+                back.tail = self.tail.take();
+            }
+        }
+        back.head = Some(first);
+        back
     }
 
-    #[test]
-    fn peek() {
-        let mut list = List::new();
-        assert!(list.peek_front().is_none());
+    /// Swaps the nodes at indices `a` and `b`: finds them in one walk, O(n), and
+    /// then does what [`List::swap_handles`] does. If you already have handles,
+    /// use that.
+    ///
+    /// # Panics
+    ///
+    /// If either index is out of bounds.
+    pub fn swap_nodes(&mut self, a: usize, b: usize) {
+        let (a, b) = (a.min(b), a.max(b));
+        let (mut first, mut second) = (None, None);
+        let mut len = 0;
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            if len == a {
+                first = Some(Handle(Rc::downgrade(&node)));
+            }
+            if len == b {
+                second = Some(Handle(Rc::downgrade(&node)));
+                break;
+            }
+            current = node.borrow().next.clone();
+            len += 1;
+            #[cfg(feature = "stats")]
+            self.stats.step();
+        }
+        let Some(second) = second else {
+            panic!("index out of bounds: the len is {len} but the index is {b}");
+        };
+        self.swap_handles(&first.expect("a <= b"), &second);
+    }
 
-        list.push_front(5);
-        list.push_front(3);
-        list.push_front(2);
+    /// A handle on the front node, for [`List::swap_handles`].
+    #[must_use]
+    pub fn front_handle(&self) -> Option<Handle<T>> {
+        self.head.as_ref().map(|node| Handle(Rc::downgrade(node)))
+    }
 
-        assert_eq!(*list.peek_front().unwrap(), 2);
-        assert_eq!(*list.peek_back().unwrap(), 5);
+    /// A handle on the back node, for [`List::swap_handles`].
+    #[must_use]
+    pub fn back_handle(&self) -> Option<Handle<T>> {
+        self.tail.as_ref().map(|node| Handle(Rc::downgrade(node)))
     }
 
-    #[test]
-    fn peek_mut() {
-        let mut list = List::new();
-        assert!(list.peek_front().is_none());
+    /// Swaps two nodes by relinking them, in O(1) — the elements themselves
+    /// never move, so it's as cheap for a huge `T` as for a small one, and a
+    /// handle keeps pointing at the same element, now in its new place.
+    ///
+    /// # Panics
+    ///
+    /// If either node has been popped. In debug builds, also if either handle
+    /// is from another deque; release builds don't walk the deque to check, and
+    /// a foreign handle leaves both deques' links scrambled (but nothing unsafe).
+    pub fn swap_handles(&mut self, a: &Handle<T>, b: &Handle<T>) {
+        let (Some(mut first), Some(mut second)) = (a.0.upgrade(), b.0.upgrade()) else {
+            panic!("handle to a node that's been popped");
+        };
+        debug_assert!(
+            self.holds(&first) && self.holds(&second),
+            "handle from another deque"
+        );
+        if Rc::ptr_eq(&first, &second) {
+            return;
+        }
+        let next_is = |node: &Rc<RefCell<Node<T>>>, other: &Rc<RefCell<Node<T>>>| {
+            node.borrow()
+                .next
+                .as_ref()
+                .is_some_and(|next| Rc::ptr_eq(next, other))
+        };
+        if next_is(&second, &first) {
+            mem::swap(&mut first, &mut second);
+        }
 
-        list.push_front(3);
-        list.push_front(2);
+        let first_prev = first.borrow_mut().prev.take();
+        let second_next = second.borrow_mut().next.take();
+        if next_is(&first, &second) {
+            // neighbours: first_prev <-> second <-> first <-> second_next
+            first.borrow_mut().next = None;
+            self.relink_next(first_prev.as_ref(), Some(Rc::clone(&second)));
+            self.relink_prev(second_next.as_ref(), Some(Rc::clone(&first)));
+            let mut second_links = second.borrow_mut();
+            second_links.prev = first_prev;
+            second_links.next = Some(Rc::clone(&first));
+            let mut first_links = first.borrow_mut();
+            first_links.prev = Some(Rc::clone(&second));
+            first_links.next = second_next;
+        } else {
+            // each takes over the other's neighbours
+            let first_next = first.borrow_mut().next.take();
+            let second_prev = second.borrow_mut().prev.take();
+            self.relink_next(first_prev.as_ref(), Some(Rc::clone(&second)));
+            self.relink_prev(first_next.as_ref(), Some(Rc::clone(&second)));
+            self.relink_next(second_prev.as_ref(), Some(Rc::clone(&first)));
+            self.relink_prev(second_next.as_ref(), Some(Rc::clone(&first)));
+            let mut second_links = second.borrow_mut();
+            second_links.prev = first_prev;
+            second_links.next = first_next;
+            let mut first_links = first.borrow_mut();
+            first_links.prev = second_prev;
+            first_links.next = second_next;
+        }
+    }
 
-        assert_eq!(*list.peek_front().unwrap(), 2);
-        assert_eq!(*list.peek_back().unwrap(), 3);
+    /// Relinks a node at the front, in O(1): the "just used" step of an LRU
+    /// cache (see `lru.rs`). Its handle keeps pointing at it.
+    ///
+    /// # Panics
+    ///
+    /// If the node has been popped. In debug builds, also if the handle is from
+    /// another deque.
+    pub fn move_to_front(&mut self, handle: &Handle<T>) {
+        let Some(node) = handle.0.upgrade() else {
+            panic!("handle to a node that's been popped");
+        };
+        debug_assert!(self.holds(&node), "handle from another deque");
+        if self
+            .head
+            .as_ref()
+            .is_some_and(|head| Rc::ptr_eq(head, &node))
+        {
+            return;
+        }
+        self.unlink(&node);
+        let old_head = self.head.take().expect("node wasn't the only one");
+        old_head.borrow_mut().prev = Some(Rc::clone(&node));
+        node.borrow_mut().next = Some(old_head);
+        self.head = Some(node);
+    }
 
-        *list.peek_front_mut().unwrap() = 10;
-        *list.peek_back_mut().unwrap() = 5;
+    // Whether node is one of ours: a walk, so only for debug checks.
+    fn holds(&self, node: &Rc<RefCell<Node<T>>>) -> bool {
+        core::iter::successors(self.head.clone(), Self::next_of).any(|ours| Rc::ptr_eq(&ours, node))
+    }
 
-        assert_eq!(*list.peek_front().unwrap(), 10);
-        assert_eq!(*list.peek_back().unwrap(), 5);
+    // Points `prev`'s next link at `to` — or the head, if there's no prev.
+    fn relink_next(&mut self, prev: Option<&Rc<RefCell<Node<T>>>>, to: Link<T>) {
+        match prev {
+            Some(prev) => prev.borrow_mut().next = to,
+            None => self.head = to,
+        }
     }
 
-    #[test]
-    fn into_iter() {
-        let mut list = List::new();
-        assert!(list.peek_front().is_none());
+    // Points `next`'s prev link at `to` — or the tail, if there's no next.
+    fn relink_prev(&mut self, next: Option<&Rc<RefCell<Node<T>>>>, to: Link<T>) {
+        match next {
+            Some(next) => next.borrow_mut().prev = to,
+            None => self.tail = to,
+        }
+    }
 
-        list.push_front(1);
-        list.push_front(2);
-        list.push_front(3);
+    /// Moves the front node round to the back, by relinking alone: a ring buffer's
+    /// "advance by one", in O(1).
+    pub fn rotate_forward(&mut self) {
+        let Some(old_head) = self.head.take() else {
+            return;
+        };
+        let next = old_head.borrow_mut().next.take();
+        let Some(new_head) = next else {
+            // a single node is its own rotation
+            self.head = Some(old_head);
+            return;
+        };
+        new_head.borrow_mut().prev = None;
+        let old_tail = self.tail.take().expect("head without a tail");
+        old_tail.borrow_mut().next = Some(Rc::clone(&old_head));
+        old_head.borrow_mut().prev = Some(old_tail);
+        self.head = Some(new_head);
+        self.tail = Some(old_head);
+    }
 
-        let mut it = list.into_iter();
+    /// The other way round: the back node becomes the front one.
+    pub fn rotate_backward(&mut self) {
+        let Some(old_tail) = self.tail.take() else {
+            return;
+        };
+        let prev = old_tail.borrow_mut().prev.take();
+        let Some(new_tail) = prev else {
+            self.tail = Some(old_tail);
+            return;
+        };
+        new_tail.borrow_mut().next = None;
+        let old_head = self.head.take().expect("tail without a head");
+        old_head.borrow_mut().prev = Some(Rc::clone(&old_tail));
+        old_tail.borrow_mut().next = Some(old_head);
+        self.tail = Some(new_tail);
+        self.head = Some(old_tail);
+    }
+
+    /// Keeps only the elements `pred` likes, in order, unlinking the rest in one
+    /// pass. A removed node's last Rc is the one in our hand, so it's freed right
+    /// there.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut pred: F) {
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            // grab next first: unlinking clears it
+            current = node.borrow().next.clone();
+            if !pred(&node.borrow().elem) {
+                self.unlink(&node);
+            }
+        }
+    }
+
+    /// `retain` turned inside out, and lazy: each `next()` unlinks the next element
+    /// `pred` picks and hands it over. Drop the iterator early and everything it
+    /// hadn't reached yet stays put.
+    pub fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, pred: F) -> ExtractIf<'_, T, F> {
+        ExtractIf {
+            next: self.head.clone(),
+            list: self,
+            pred,
+        }
+    }
+
+    // Links a new node in right after `node`, and hands it back.
+    fn insert_after(&mut self, node: &Rc<RefCell<Node<T>>>, elem: T) -> Rc<RefCell<Node<T>>> {
+        let new = Node::new(elem);
+        let next = node.borrow_mut().next.replace(Rc::clone(&new));
+        match &next {
+            Some(next) => next.borrow_mut().prev = Some(Rc::clone(&new)),
+            None => self.tail = Some(Rc::clone(&new)),
+        }
+        {
+            let mut links = new.borrow_mut();
+            links.prev = Some(Rc::clone(node));
+            links.next = next;
+        }
+        #[cfg(feature = "stats")]
+        self.stats.push();
+        new
+    }
+
+    // Stitches node's neighbours (or head/tail) to each other. Afterwards nothing in
+    // the list points at node, so it's freed as soon as the caller lets go of it.
+    fn unlink(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let mut node = node.borrow_mut();
+        let prev = node.prev.take();
+        let next = node.next.take();
+        match &prev {
+            Some(prev) => prev.borrow_mut().next.clone_from(&next),
+            None => self.head.clone_from(&next),
+        }
+        match next {
+            Some(next) => next.borrow_mut().prev = prev,
+            None => self.tail = prev,
+        }
+    }
+}
+
+/// Points at one of a deque's nodes, from [`List::front_handle`] and friends,
+/// for [`List::swap_handles`]. It holds the node weakly: it doesn't keep the
+/// node alive, and doesn't get in the way of popping it the way an `Rc` would.
+pub struct Handle<T>(Weak<RefCell<Node<T>>>);
+
+impl<T> Handle<T> {
+    /// The node after this one, or None at the back (or once it's been popped).
+    #[must_use]
+    pub fn next(&self) -> Option<Self> {
+        let node = self.0.upgrade()?;
+        let next = node.borrow().next.as_ref().map(Rc::downgrade);
+        next.map(Handle)
+    }
+
+    /// The node before this one, or None at the front (or once it's been popped).
+    #[must_use]
+    pub fn prev(&self) -> Option<Self> {
+        let node = self.0.upgrade()?;
+        let prev = node.borrow().prev.as_ref().map(Rc::downgrade);
+        prev.map(Handle)
+    }
+}
+
+// not derived: that would want T: Clone
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self(Weak::clone(&self.0))
+    }
+}
+
+pub struct ExtractIf<'a, T, F> {
+    list: &'a mut List<T>,
+    // the next node to show pred; we never hold on to one pred rejected
+    next: Link<T>,
+    pred: F,
+}
+
+impl<T, F: FnMut(&mut T) -> bool> Iterator for ExtractIf<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.next.take() {
+            self.next = node.borrow().next.clone();
+            if (self.pred)(&mut node.borrow_mut().elem) {
+                self.list.unlink(&node);
+                // unlinked, so ours is the last Rc
+                return Some(Rc::try_unwrap(node).ok().unwrap().into_inner().elem);
+            }
+        }
+        None
+    }
+}
+
+impl<T> Node<T> {
+    #[must_use]
+    fn new(elem: T) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            elem,
+            next: None,
+            prev: None,
+        }))
+    }
+}
+
+// Floyd's tortoise and hare, over the next links: one walker goes a node at a time,
+// the other two, and they only ever meet if the chain loops. Every next is an Rc,
+// so a loop is a leak as well as an endless walk.
+impl<T> List<T> {
+    #[must_use]
+    pub fn has_cycle(&self) -> bool {
+        self.meeting_point().is_some()
+    }
+
+    /// The index of the node the next links loop back to, if they do.
+    #[must_use]
+    pub fn cycle_start(&self) -> Option<usize> {
+        let mut meeting = self.meeting_point()?;
+        // as far round the loop from its start as head is from it
+        let mut from_head = self.head.clone()?;
+        let mut index = 0;
+        while !Rc::ptr_eq(&from_head, &meeting) {
+            from_head = Self::next_of(&from_head)?;
+            meeting = Self::next_of(&meeting)?;
+            index += 1;
+        }
+        Some(index)
+    }
+
+    fn meeting_point(&self) -> Link<T> {
+        let (mut tortoise, mut hare) = (self.head.clone()?, self.head.clone()?);
+        loop {
+            hare = Self::next_of(&hare).and_then(|hare| Self::next_of(&hare))?;
+            tortoise = Self::next_of(&tortoise)?;
+            if Rc::ptr_eq(&tortoise, &hare) {
+                return Some(hare);
+            }
+        }
+    }
+
+    fn next_of(node: &Rc<RefCell<Node<T>>>) -> Link<T> {
+        node.borrow().next.clone()
+    }
+
+    // Points tail's next back at the node at `to`. Take it back off
+    // (tail.next = None) before the deque drops.
+    #[cfg(test)]
+    fn make_cycle_for_test(&mut self, to: usize) {
+        let target = core::iter::successors(self.head.clone(), Self::next_of)
+            .nth(to)
+            .unwrap_or_else(|| panic!("no node {to} to loop back to"));
+        self.tail.as_ref().unwrap().borrow_mut().next = Some(target);
+    }
+}
+
+// Walks the deque in both directions and panics the moment two links disagree.
+// It's O(n) and allocates a BTreeSet, so it only exists in debug builds.
+#[cfg(debug_assertions)]
+impl<T> List<T> {
+    pub fn assert_valid(&self) {
+        use alloc::collections::BTreeSet;
+
+        let (Some(head), Some(tail)) = (&self.head, &self.tail) else {
+            assert!(
+                self.head.is_none() && self.tail.is_none(),
+                "only one of head and tail is set"
+            );
+            return;
+        };
+        assert!(head.borrow().prev.is_none(), "head has a prev");
+        assert!(tail.borrow().next.is_none(), "tail has a next");
+        if let Some(start) = self.cycle_start() {
+            panic!("next links loop back to node {start}");
+        }
+
+        // forwards, checking that every next points straight back at us
+        let mut seen = BTreeSet::new();
+        let mut current = Rc::clone(head);
+        loop {
+            assert!(
+                seen.insert(Rc::as_ptr(&current)),
+                "cycle after {} nodes going forwards",
+                seen.len()
+            );
+            let next = current.borrow().next.clone();
+            let Some(next) = next else { break };
+            let back = next.borrow().prev.clone();
+            assert!(
+                back.is_some_and(|back| Rc::ptr_eq(&back, &current)),
+                "node {}'s next doesn't point back at it",
+                seen.len()
+            );
+            current = next;
+        }
+        assert!(Rc::ptr_eq(&current, tail), "tail isn't reachable from head");
+        let forwards = seen.len();
+
+        // backwards, which had better see just as many nodes
+        seen.clear();
+        let mut current = Rc::clone(tail);
+        loop {
+            assert!(
+                seen.insert(Rc::as_ptr(&current)),
+                "cycle after {} nodes going backwards",
+                seen.len()
+            );
+            let prev = current.borrow().prev.clone();
+            let Some(prev) = prev else { break };
+            current = prev;
+        }
+        assert!(Rc::ptr_eq(&current, head), "head isn't reachable from tail");
+        assert_eq!(
+            forwards,
+            seen.len(),
+            "forwards and backwards lengths disagree"
+        );
+    }
+}
+
+// Serialize front to back. We have a tail, so deserializing can push_back each element as it
+// arrives — no intermediate buffer.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        // Some formats (bincode) insist on knowing the length up front, so we walk twice.
+        let len =
+            std::iter::successors(self.head.clone(), |node| node.borrow().next.clone()).count();
+
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            let node = node.borrow();
+            seq.serialize_element(&node.elem)?;
+            current = node.next.clone();
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ListVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for ListVisitor<T> {
+            type Value = List<T>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a sequence")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut list = List::new();
+                while let Some(elem) = seq.next_element()? {
+                    list.push_back(elem);
+                }
+                Ok(list)
+            }
+        }
+
+        deserializer.deserialize_seq(ListVisitor(std::marker::PhantomData))
+    }
+}
+
+// Archives as a flat ArchivedVec, like second::List. The deque doesn't count
+// itself, so resolving walks it for the length, and deserializing has to relink
+// every prev pointer by pushing the elements back on one at a time.
+#[cfg(feature = "rkyv")]
+impl<T: rkyv::Archive> rkyv::Archive for List<T> {
+    type Archived = rkyv::vec::ArchivedVec<T::Archived>;
+    type Resolver = rkyv::vec::VecResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: rkyv::Place<Self::Archived>) {
+        rkyv::vec::ArchivedVec::resolve_from_len(
+            std::iter::successors(self.head.clone(), |node| node.borrow().next.clone()).count(),
+            resolver,
+            out,
+        );
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, S> rkyv::Serialize<S> for List<T>
+where
+    T: rkyv::Serialize<S>,
+    S: rkyv::rancor::Fallible + rkyv::ser::Allocator + rkyv::ser::Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        // Every element sits behind a RefCell, so we hold a Ref to each node for the duration.
+        let nodes: Vec<_> =
+            std::iter::successors(self.head.clone(), |node| node.borrow().next.clone()).collect();
+        let guards: Vec<Ref<Node<T>>> = nodes.iter().map(|node| node.borrow()).collect();
+        rkyv::vec::ArchivedVec::serialize_from_iter::<T, _, _>(
+            guards.iter().map(|node| &node.elem),
+            serializer,
+        )
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, D> rkyv::Deserialize<List<T>, D> for rkyv::vec::ArchivedVec<T::Archived>
+where
+    T: rkyv::Archive,
+    [T::Archived]: rkyv::DeserializeUnsized<[T], D>,
+    D: rkyv::rancor::Fallible + ?Sized,
+    D::Error: rkyv::rancor::Source,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<List<T>, D::Error> {
+        let elems: Vec<T> = self.deserialize(deserializer)?;
+        let mut list = List::new();
+        for elem in elems {
+            list.push_back(elem);
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(feature = "viz")]
+impl<T: std::fmt::Debug> List<T> {
+    fn graph(&self) -> crate::viz::Graph {
+        let mut graph = crate::viz::Graph::new();
+        graph.doubly_linked();
+        for node in std::iter::successors(self.head.clone(), |node| node.borrow().next.clone()) {
+            // minus one for the `node` handle we're holding right now
+            let strong_count = Rc::strong_count(&node) - 1;
+            let node_ref = node.borrow();
+            graph.rc_node(Rc::as_ptr(&node), &node_ref.elem, strong_count);
+            if let Some(next) = &node_ref.next {
+                graph.edge(Rc::as_ptr(&node), Rc::as_ptr(next), "next");
+            }
+            if let Some(prev) = &node_ref.prev {
+                graph.edge(Rc::as_ptr(&node), Rc::as_ptr(prev), "prev");
+            }
+        }
+        if let Some(head) = &self.head {
+            graph.marker("head", Rc::as_ptr(head));
+        }
+        if let Some(tail) = &self.tail {
+            graph.marker("tail", Rc::as_ptr(tail));
+        }
+        graph
+    }
+
+    /// Dumps the list's structure as a Graphviz DOT graph, with both next and prev links and
+    /// each node's Rc strong count (2 for a healthy interior node: one from each neighbour).
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        self.graph().to_dot()
+    }
+
+    /// Same as [`List::to_dot`], but as a Mermaid `graph LR` diagram, ready to paste into
+    /// markdown.
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        self.graph().to_mermaid()
+    }
+
+    /// Draws the list as ASCII art, e.g. `HEAD -> [2] -> [5] -> ∅`.
+    #[must_use]
+    pub fn render_ascii(&self) -> String {
+        self.graph().to_ascii()
+    }
+
+    /// Same as [`List::to_dot`], but as JSON, for drawing somewhere other than a
+    /// terminal (examples/wasm-viz draws it in a browser).
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        self.graph().to_json()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{List, Node};
+    use std::rc::Rc;
+
+    fn from(elems: &[i32]) -> List<i32> {
+        let mut list = List::new();
+        for &elem in elems {
+            list.push_back(elem);
+        }
+        list
+    }
+
+    #[test]
+    fn memory_usage() {
+        use crate::third::{MemoryUsage, RcBox};
+        use core::cell::RefCell;
+
+        let mut a = List::new();
+        a.extend_back([1, 2, 3]);
+        let mut b = List::new();
+        b.extend_back([4, 5]);
+        let node = size_of::<RcBox<RefCell<Node<i32>>>>();
+        assert_eq!(
+            List::memory_usage([&a, &b]),
+            MemoryUsage {
+                nodes: 5,
+                bytes: 5 * node,
+                unshared_bytes: 5 * node,
+            }
+        );
+        assert_eq!(List::reachable_nodes([&a, &a]), 3);
+        // on 64-bit: two counts, the borrow flag, the i32 (padded) and two links
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(node, 48);
+    }
+
+    // assert_valid is debug-only; let the tests still build under --release
+    #[cfg(not(debug_assertions))]
+    impl<T> List<T> {
+        fn assert_valid(&self) {}
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = List::new();
+
+        // Check empty list behaves right
+        assert_eq!(list.pop_front(), None);
+        list.assert_valid();
+
+        // Populate list
+        list.push_front(1);
+        list.assert_valid();
+        list.push_front(2);
+        list.assert_valid();
+        list.push_front(3);
+        list.assert_valid();
+
+        // Check normal removal
+        assert_eq!(list.pop_front(), Some(3));
+        list.assert_valid();
+        assert_eq!(list.pop_front(), Some(2));
+        list.assert_valid();
+
+        // Push some more just to make sure nothing's corrupted
+        list.push_front(4);
+        list.assert_valid();
+        list.push_front(5);
+        list.assert_valid();
+
+        // Check normal removal
+        assert_eq!(list.pop_front(), Some(5));
+        list.assert_valid();
+        assert_eq!(list.pop_front(), Some(4));
+        list.assert_valid();
+
+        // Check exhaustion
+        assert_eq!(list.pop_front(), Some(1));
+        list.assert_valid();
+        assert_eq!(list.pop_front(), None);
+        list.assert_valid();
+
+        // ---- back -----
+
+        // Check empty list behaves right
+        assert_eq!(list.pop_back(), None);
+        list.assert_valid();
+
+        // Populate list
+        list.push_back(1);
+        list.assert_valid();
+        list.push_back(2);
+        list.assert_valid();
+        list.push_back(3);
+        list.assert_valid();
+
+        // Check normal removal
+        assert_eq!(list.pop_back(), Some(3));
+        list.assert_valid();
+        assert_eq!(list.pop_back(), Some(2));
+        list.assert_valid();
+
+        // Push some more just to make sure nothing's corrupted
+        list.push_back(4);
+        list.assert_valid();
+        list.push_back(5);
+        list.assert_valid();
+
+        // Check normal removal
+        assert_eq!(list.pop_back(), Some(5));
+        list.assert_valid();
+        assert_eq!(list.pop_back(), Some(4));
+        list.assert_valid();
+
+        // Check exhaustion
+        assert_eq!(list.pop_back(), Some(1));
+        list.assert_valid();
+        assert_eq!(list.pop_back(), None);
+        list.assert_valid();
+    }
+
+    // push_back onto an empty deque used to set the tail twice and never the head,
+    // so the front end couldn't see the element at all.
+    #[test]
+    fn push_back_onto_empty() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.assert_valid();
+        assert_eq!(list.peek_front().map(|front| *front), Some(1));
+        list.push_back(2);
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.pop_front(), None);
+    }
+
+    #[test]
+    fn peek() {
+        let mut list = List::new();
+        assert!(list.peek_front().is_none());
+
+        list.push_front(5);
+        list.assert_valid();
+        list.push_front(3);
+        list.assert_valid();
+        list.push_front(2);
+        list.assert_valid();
+
+        assert_eq!(*list.peek_front().unwrap(), 2);
+        assert_eq!(*list.peek_back().unwrap(), 5);
+    }
+
+    #[test]
+    fn peek_mut() {
+        let mut list = List::new();
+        assert!(list.peek_front().is_none());
+
+        list.push_front(3);
+        list.assert_valid();
+        list.push_front(2);
+        list.assert_valid();
+
+        assert_eq!(*list.peek_front().unwrap(), 2);
+        assert_eq!(*list.peek_back().unwrap(), 3);
+
+        *list.peek_front_mut().unwrap() = 10;
+        *list.peek_back_mut().unwrap() = 5;
+
+        assert_eq!(*list.peek_front().unwrap(), 10);
+        assert_eq!(*list.peek_back().unwrap(), 5);
+    }
+
+    // peek_front_mut only takes &self, so the borrow checker lets this through
+    // (holding a peek across push_front is the compile error, tests/ui/), and
+    // it's the RefCell that says no.
+    #[test]
+    #[should_panic(expected = "already borrowed")]
+    fn peek_mut_while_peeking_panics() {
+        let mut list = List::new();
+        list.push_front(1);
+        let _front = list.peek_front();
+        list.peek_front_mut();
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut list = List::new();
+        assert!(list.peek_front().is_none());
+
+        list.push_front(1);
+        list.assert_valid();
+        list.push_front(2);
+        list.assert_valid();
+        list.push_front(3);
+        list.assert_valid();
+
+        let mut it = list.into_iter();
 
         assert_eq!(it.next(), Some(3));
         assert_eq!(it.next_back(), Some(1));
         assert_eq!(it.next_back(), Some(2));
         assert_eq!(it.next(), None);
     }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn rkyv_round_trip() {
+        use rkyv::{rancor::Error, vec::ArchivedVec, Archived};
+
+        let mut list: List<i32> = List::new();
+        list.extend_back([1, 2, 3]);
+        // archiving only borrows the nodes, so an outstanding peek is fine
+        let front = list.peek_front().unwrap();
+        let bytes = rkyv::to_bytes::<Error>(&list).unwrap();
+        drop(front);
+
+        let archived = rkyv::access::<ArchivedVec<Archived<i32>>, Error>(&bytes).unwrap();
+        assert_eq!(archived.as_slice(), [1, 2, 3]);
+        // and coming back, the prev links are real: it drains from the back too
+        let mut list: List<i32> = rkyv::deserialize::<_, Error>(archived).unwrap();
+        list.assert_valid();
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn to_dot() {
+        use crate::viz::dot_id;
+
+        let mut list = List::new();
+        list.push_back(1);
+        list.assert_valid();
+        list.push_back(2);
+        list.assert_valid();
+        list.push_back(3);
+        list.assert_valid();
+
+        let dot = list.to_dot();
+        let (one, two, three) = (dot_id(&dot, "1"), dot_id(&dot, "2"), dot_id(&dot, "3"));
+        assert!(dot.contains(&format!("head -> {one};")));
+        assert!(dot.contains(&format!("tail -> {three};")));
+        assert!(dot.contains(&format!("{one} -> {two} [label=\"next\"];")));
+        assert!(dot.contains(&format!("{two} -> {one} [label=\"prev\"];")));
+        // head and tail nodes: list + one neighbour; interior: both neighbours
+        assert!(dot.contains("[label=\"1\\nstrong=2\"]"));
+        assert!(dot.contains("[label=\"2\\nstrong=2\"]"));
+        assert!(dot.contains("[label=\"3\\nstrong=2\"]"));
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn to_mermaid() {
+        use crate::viz::mermaid_id;
+
+        let mut list = List::new();
+        list.push_back(1);
+        list.assert_valid();
+        list.push_back(2);
+        list.assert_valid();
+
+        let mermaid = list.to_mermaid();
+        let (one, two) = (mermaid_id(&mermaid, "1"), mermaid_id(&mermaid, "2"));
+        assert!(mermaid.contains(&format!("{one} -->|next| {two}")));
+        assert!(mermaid.contains(&format!("{two} -->|prev| {one}")));
+        assert!(mermaid.contains(&format!("head([head]) --> {one}")));
+        assert!(mermaid.contains(&format!("tail([tail]) --> {two}")));
+    }
+
+    // The links come from the nodes, not from their order: a prev pointer that's
+    // gone wrong shows up pointing where it really points.
+    #[cfg(feature = "viz")]
+    #[test]
+    fn to_json() {
+        use crate::viz::dot_id;
+
+        let mut list = List::new();
+        list.extend_back([1, 2, 3]);
+        let dot = list.to_dot();
+        let (one, three) = (dot_id(&dot, "1"), dot_id(&dot, "3"));
+
+        let tail = list.tail.clone().unwrap();
+        let real_prev = tail.borrow_mut().prev.replace(list.head.clone().unwrap());
+        let json: serde_json::Value = serde_json::from_str(&list.to_json()).unwrap();
+        tail.borrow_mut().prev = real_prev;
+
+        assert_eq!(json["markers"]["head"], one.as_str());
+        assert_eq!(json["markers"]["tail"], three.as_str());
+        assert_eq!(json["nodes"][0]["label"], "1");
+        let links = json["links"].as_array().unwrap();
+        assert!(links.contains(&serde_json::json!({ "from": three, "to": one, "kind": "prev" })));
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn render_ascii() {
+        let mut list = List::new();
+        assert_eq!(list.render_ascii(), "HEAD -> ∅\n   ∅ <- TAIL\n");
+
+        // one node has no prev links to go by, but still gets the prev row
+        list.push_back(1);
+        list.assert_valid();
+        assert_eq!(
+            list.render_ascii(),
+            "HEAD -> [1] -> ∅\n   ∅ <- [1] <- TAIL\n"
+        );
+
+        list.push_back(2);
+        list.assert_valid();
+        assert_eq!(
+            list.render_ascii(),
+            "HEAD -> [1] -> [2] -> ∅\n   ∅ <- [1] <- [2] <- TAIL\n"
+        );
+
+        // the prev row is the real prev pointers: break one and it shows
+        list.push_back(3);
+        let tail = list.tail.clone().unwrap();
+        let real_prev = tail.borrow_mut().prev.replace(list.head.clone().unwrap());
+        let ascii = list.render_ascii();
+        tail.borrow_mut().prev = real_prev;
+        assert_eq!(
+            ascii,
+            "HEAD -> [1] -> [2] -> [3] -> ∅\n   ∅ <- [1] <- [2] ?- [3] <- TAIL\n"
+        );
+    }
+
+    #[test]
+    fn std_conversions() {
+        let std_list: std::collections::LinkedList<i32> = [1, 2, 3].into_iter().collect();
+
+        let list = List::from(std_list);
+        assert_eq!(*list.peek_front().unwrap(), 1);
+        assert_eq!(*list.peek_back().unwrap(), 3);
+
+        let std_list = std::collections::LinkedList::from(list);
+        assert_eq!(std_list.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let empty = List::<i32>::from(std::collections::LinkedList::new());
+        assert!(empty.peek_front().is_none());
+    }
+
+    #[test]
+    fn vec_conversions() {
+        let mut list = List::from(vec![1, 2, 3]);
+        list.assert_valid();
+        assert_eq!(list, vec![1, 2, 3]);
+        assert_eq!(list, &[1, 2, 3][..]);
+        assert_ne!(list, vec![1, 2]);
+        assert_ne!(list, vec![1, 2, 3, 4]);
+        assert_ne!(list, vec![1, 5, 3]);
+        assert_eq!(format!("{list:?}"), "[1, 2, 3]");
+
+        list.push_front(0);
+        assert_eq!(Vec::from(list), [0, 1, 2, 3]);
+
+        let empty = List::<i32>::from(Vec::new());
+        assert_eq!(empty, Vec::<i32>::new());
+        assert_eq!(format!("{empty:?}"), "[]");
+    }
+
+    #[test]
+    fn std_compat() {
+        let mut list = List::new();
+        let mut compat = list.as_std_compat();
+        assert!(compat.is_empty());
+        assert_eq!(compat.len(), 0);
+
+        compat.push_back(2);
+        compat.push_front(1);
+        compat.push_back(3);
+        assert_eq!(compat.len(), 3);
+        assert_eq!(*compat.front().unwrap(), 1);
+        assert_eq!(*compat.back().unwrap(), 3);
+
+        *compat.front_mut().unwrap() = 10;
+        assert_eq!(compat.pop_front(), Some(10));
+        assert_eq!(compat.pop_back(), Some(3));
+
+        compat.clear();
+        assert!(compat.is_empty());
+        assert!(list.peek_front().is_none());
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn trace() {
+        let log = crate::trace::capture(|| {
+            let mut list = List::new();
+            list.push_front(1);
+            list.assert_valid();
+            list.push_front(2);
+            list.assert_valid();
+            list.pop_back();
+            list.assert_valid();
+            list.pop_front();
+            list.assert_valid();
+        });
+        assert_eq!(
+            log,
+            [
+                "set head and tail",
+                "take head",
+                "relink prev",
+                "relink next",
+                "take tail",
+                "take prev",
+                "unlink next",
+                "unwrap old tail",
+                "take head",
+                "take tail",
+                "unwrap old head",
+            ]
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats() {
+        let mut list = List::new();
+        list.push_front(1);
+        list.assert_valid();
+        list.push_back(2);
+        list.assert_valid();
+        list.pop_back();
+        list.assert_valid();
+        list.pop_front();
+        list.assert_valid();
+        list.pop_front();
+        list.assert_valid();
+
+        let stats = list.stats();
+        assert_eq!((stats.pushes, stats.pops, stats.allocations), (2, 2, 2));
+    }
+
+    // The ends are O(1), however long the deque gets: the same handful of
+    // operations take the same zero steps at 16 nodes as at 4096. The walk at the
+    // end is there to show a traversal would get counted.
+    #[cfg(feature = "stats")]
+    #[test]
+    fn ends_never_walk() {
+        for n in [16, 4096] {
+            let mut list = List::new();
+            list.extend_back(0..n);
+            let before = list.stats();
+
+            list.push_front(-1);
+            list.push_back(n);
+            assert_eq!(list.pop_front(), Some(-1));
+            assert_eq!(list.pop_back(), Some(n));
+            list.rotate_forward();
+            list.rotate_backward();
+            assert_eq!(list.peek_front().as_deref(), Some(&0));
+            assert_eq!(list.peek_back().as_deref(), Some(&(n - 1)));
+
+            let after = list.stats();
+            assert_eq!(after.steps, before.steps, "n = {n}");
+            assert_eq!(
+                (after.pushes, after.pops),
+                (before.pushes + 2, before.pops + 2)
+            );
+
+            let back = list.split_off(n as usize / 2);
+            assert_eq!(list.stats().steps, after.steps + n as usize / 2);
+            drop(back);
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn assert_valid_catches_a_broken_prev() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+        let tail = list.tail.clone().unwrap();
+        let real_prev = tail.borrow_mut().prev.replace(list.head.clone().unwrap());
+
+        let result = catch_unwind(AssertUnwindSafe(|| list.assert_valid()));
+        // put it back, or Drop trips over the extra Rc on the head
+        tail.borrow_mut().prev = real_prev;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn add() {
+        let mut list = from(&[]) + from(&[1, 2]);
+        list.assert_valid();
+        list += from(&[3]);
+        list.assert_valid();
+        list += from(&[]);
+        list.assert_valid();
+
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn remove() {
+        let mut list = List::new();
+        for elem in [3, 1, 3, 2, 3, 3] {
+            list.push_back(elem);
+        }
+
+        assert!(list.remove_first(&2));
+        assert!(!list.remove_first(&2));
+        assert!(list.remove_first(&3));
+        list.assert_valid();
+
+        assert_eq!(list.remove_all(&3), 3);
+        list.assert_valid();
+        assert_eq!(*list.peek_front().unwrap(), 1);
+        assert_eq!(*list.peek_back().unwrap(), 1);
+
+        assert_eq!(list.remove_all(&1), 1);
+        list.assert_valid();
+        assert!(list.peek_front().is_none());
+    }
+
+    #[test]
+    fn node_layout() {
+        // No hand-reordering needed: Rust lays fields out as it sees fit, so a
+        // one-byte elem tucks in after the two links and only the unavoidable
+        // tail padding remains.
+        assert_eq!(size_of::<Node<u8>>(), 3 * size_of::<usize>());
+        assert_eq!(size_of::<Node<(u8, u16, u8)>>(), 3 * size_of::<usize>());
+    }
+
+    #[test]
+    fn reverse() {
+        // every node is held by exactly two links: its prev's next (or head) and
+        // its next's prev (or tail)
+        fn strong_counts<T>(list: &List<T>) -> Vec<usize> {
+            let mut counts = Vec::new();
+            let mut current = list.head.clone();
+            while let Some(node) = current {
+                // minus the one we're holding
+                counts.push(Rc::strong_count(&node) - 1);
+                current = node.borrow().next.clone();
+            }
+            counts
+        }
+
+        let mut list = List::new();
+        list.reverse();
+        list.assert_valid();
+        assert!(list.peek_front().is_none());
+
+        list.push_back(1);
+        list.reverse();
+        list.assert_valid();
+        assert_eq!(*list.peek_front().unwrap(), 1);
+
+        for elem in 2..=5 {
+            list.push_back(elem);
+        }
+        list.reverse();
+        list.assert_valid();
+        assert_eq!(strong_counts(&list), [2; 5]);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [5, 4, 3, 2, 1]);
+
+        let mut list: List<_> = (1..=4).collect::<std::collections::LinkedList<_>>().into();
+        list.reverse();
+        list.reverse();
+        list.assert_valid();
+        assert_eq!(strong_counts(&list), [2; 4]);
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), [4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn retain() {
+        let canaries: Vec<_> = (0..8).map(Rc::new).collect();
+        let mut list = List::new();
+        for canary in &canaries {
+            list.push_back(Rc::clone(canary));
+        }
+
+        // drop both ends and a run in the middle
+        list.retain(|elem| ![0, 3, 4, 7].contains(&**elem));
+        list.assert_valid();
+        for (i, canary) in canaries.iter().enumerate() {
+            let expected = if [0, 3, 4, 7].contains(&i) { 1 } else { 2 };
+            assert_eq!(Rc::strong_count(canary), expected);
+        }
+
+        list.retain(|_| true);
+        list.assert_valid();
+        // pop_front try_unwraps each node, which only works if nothing leaked
+        let mut kept = Vec::new();
+        while let Some(elem) = list.pop_front() {
+            kept.push(*elem);
+        }
+        assert_eq!(kept, [1, 2, 5, 6]);
+
+        for canary in &canaries {
+            list.push_back(Rc::clone(canary));
+        }
+        list.retain(|_| false);
+        list.assert_valid();
+        assert!(list.peek_front().is_none());
+        assert!(canaries.iter().all(|canary| Rc::strong_count(canary) == 1));
+    }
+
+    #[test]
+    fn extract_if() {
+        let mut list = List::new();
+        for elem in 1..=8 {
+            list.push_back(elem);
+        }
+
+        let mut evens = list.extract_if(|elem| *elem % 2 == 0);
+        assert_eq!(evens.next(), Some(2));
+        assert_eq!(evens.next(), Some(4));
+        drop(evens);
+        list.assert_valid();
+        // 6 and 8 were never reached
+        assert_eq!(list.pop_back(), Some(8));
+
+        // pred may edit the survivors as it goes
+        let big: Vec<_> = list
+            .extract_if(|elem| {
+                *elem *= 10;
+                *elem > 40
+            })
+            .collect();
+        list.assert_valid();
+        assert_eq!(big, [50, 60, 70]);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [10, 30]);
+    }
+
+    #[test]
+    fn extract_if_dropped_midway() {
+        let canaries: Vec<_> = (0..6).map(Rc::new).collect();
+        {
+            let mut list = List::new();
+            for canary in &canaries {
+                list.push_back(Rc::clone(canary));
+            }
+
+            let mut odds = list.extract_if(|elem| **elem % 2 == 1);
+            let first = odds.next().unwrap();
+            drop(odds);
+            list.assert_valid();
+
+            // the extracted one is ours alone; everything else is just in the list
+            assert_eq!(Rc::strong_count(&first), 2);
+            drop(first);
+            assert!(canaries.iter().all(|canary| Rc::strong_count(canary) <= 2));
+            assert_eq!(Rc::strong_count(&canaries[1]), 1);
+
+            // and the list tears down through try_unwrap without complaint
+            assert_eq!(list.extract_if(|_| true).count(), 5);
+        }
+        assert!(canaries.iter().all(|canary| Rc::strong_count(canary) == 1));
+    }
+
+    #[test]
+    fn rotate() {
+        fn elems(list: &mut List<i32>) -> Vec<i32> {
+            let std_list: std::collections::LinkedList<_> = std::mem::take(list).into();
+            let elems = std_list.iter().copied().collect();
+            *list = std_list.into();
+            elems
+        }
+
+        let mut list = List::new();
+        list.rotate_forward();
+        list.rotate_backward();
+        list.assert_valid();
+
+        list.push_back(1);
+        list.rotate_forward();
+        list.rotate_backward();
+        list.assert_valid();
+        assert_eq!(elems(&mut list), [1]);
+
+        for elem in 2..=5 {
+            list.push_back(elem);
+        }
+        list.rotate_forward();
+        list.assert_valid();
+        assert_eq!(elems(&mut list), [2, 3, 4, 5, 1]);
+        list.rotate_backward();
+        list.rotate_backward();
+        list.assert_valid();
+        assert_eq!(elems(&mut list), [5, 1, 2, 3, 4]);
+        list.rotate_forward();
+
+        // len rotations either way bring us back where we started
+        for _ in 0..5 {
+            list.rotate_forward();
+            list.assert_valid();
+        }
+        assert_eq!(elems(&mut list), [1, 2, 3, 4, 5]);
+        for _ in 0..5 {
+            list.rotate_backward();
+            list.assert_valid();
+        }
+        assert_eq!(elems(&mut list), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn split_off() {
+        fn drain(list: List<i32>) -> Vec<i32> {
+            list.assert_valid();
+            list.into_iter().collect()
+        }
+        fn filled() -> List<i32> {
+            let mut list = List::new();
+            for elem in 1..=5 {
+                list.push_back(elem);
+            }
+            list
+        }
+
+        let mut front = filled();
+        let back = front.split_off(2);
+        front.assert_valid();
+        back.assert_valid();
+        // both halves have working ends
+        front.push_back(10);
+        assert_eq!(drain(front), [1, 2, 10]);
+        assert_eq!(drain(back), [3, 4, 5]);
+
+        let mut front = filled();
+        let back = front.split_off(0);
+        assert_eq!((drain(front), drain(back)), (vec![], vec![1, 2, 3, 4, 5]));
+
+        let mut front = filled();
+        let back = front.split_off(5);
+        assert_eq!((drain(front), drain(back)), (vec![1, 2, 3, 4, 5], vec![]));
+
+        let mut front = filled();
+        let mut back = front.split_off(4);
+        back.push_front(0);
+        assert_eq!((drain(front), drain(back)), (vec![1, 2, 3, 4], vec![0, 5]));
+    }
+
+    #[test]
+    #[should_panic(expected = "at > len (4 > 3)")]
+    fn split_off_past_the_end() {
+        let mut list = List::new();
+        for elem in 0..3 {
+            list.push_back(elem);
+        }
+        list.split_off(4);
+    }
+
+    #[test]
+    fn clone() {
+        let mut original = List::new();
+        for elem in 1..=3 {
+            original.push_back(elem);
+        }
+        let mut copy = original.clone();
+        copy.assert_valid();
+
+        // no node in common: changing one leaves the other alone
+        *copy.peek_front_mut().unwrap() = 10;
+        copy.push_back(4);
+        assert_eq!(*original.peek_front().unwrap(), 1);
+        assert!(!Rc::ptr_eq(
+            original.head.as_ref().unwrap(),
+            copy.head.as_ref().unwrap()
+        ));
+
+        assert_eq!(original.into_iter().collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(copy.into_iter().collect::<Vec<_>>(), [10, 2, 3, 4]);
+        assert!(List::<i32>::new().deep_clone().peek_front().is_none());
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn clone_allocates_every_node() {
+        let mut original = List::new();
+        for elem in 0..5 {
+            original.push_front(elem);
+        }
+        let copy = original.deep_clone();
+        assert_eq!(copy.stats().allocations, 5);
+        // and the original didn't lend any out
+        assert_eq!(original.stats().reuses, 0);
+        assert_eq!(original.stats().allocations, 5);
+    }
+
+    #[test]
+    fn extend() {
+        let mut list = List::new();
+        list.extend_front(Vec::new());
+        list.extend_back(Vec::new());
+        list.assert_valid();
+        assert!(list.peek_front().is_none());
+
+        // iterator order at the front, not reversed
+        list.extend_front([3, 4]);
+        list.assert_valid();
+        list.extend_front([1, 2]);
+        list.assert_valid();
+        list.extend_back([5, 6]);
+        list.assert_valid();
+        list.extend(7..=8);
+        list.assert_valid();
+        list.extend_front(std::iter::once(0));
+        list.assert_valid();
+
+        assert_eq!(*list.peek_back().unwrap(), 8);
+        assert_eq!(
+            list.into_iter().collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8]
+        );
+    }
+
+    #[test]
+    fn swap_nodes() {
+        fn swapped(a: usize, b: usize) -> Vec<i32> {
+            let mut list = List::new();
+            list.extend_back(0..5);
+            let before = list.head.clone().unwrap();
+            list.swap_nodes(a, b);
+            list.assert_valid();
+            // relinked, not copied: the old head node is still the one holding 0
+            assert_eq!(before.borrow().elem, 0);
+            drop(before);
+            list.into_iter().collect()
+        }
+
+        assert_eq!(swapped(0, 4), [4, 1, 2, 3, 0]);
+        assert_eq!(swapped(4, 0), [4, 1, 2, 3, 0]);
+        assert_eq!(swapped(1, 3), [0, 3, 2, 1, 4]);
+        assert_eq!(swapped(1, 2), [0, 2, 1, 3, 4]);
+        assert_eq!(swapped(0, 1), [1, 0, 2, 3, 4]);
+        assert_eq!(swapped(3, 4), [0, 1, 2, 4, 3]);
+        assert_eq!(swapped(2, 2), [0, 1, 2, 3, 4]);
+
+        let mut pair = List::new();
+        pair.extend_back([1, 2]);
+        pair.swap_nodes(1, 0);
+        pair.assert_valid();
+        assert_eq!(pair.into_iter().rev().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds: the len is 3 but the index is 3")]
+    fn swap_nodes_out_of_bounds() {
+        let mut list = List::new();
+        list.extend_back(0..3);
+        list.swap_nodes(0, 3);
+    }
+
+    #[test]
+    fn swap_handles() {
+        use super::Handle;
+
+        let elem = |handle: &Handle<i32>| handle.0.upgrade().unwrap().borrow().elem;
+        let mut list = List::new();
+        list.extend_back(0..5);
+        let zero = list.front_handle().unwrap();
+        let four = list.back_handle().unwrap();
+        let one = zero.next().unwrap();
+        let three = four.prev().unwrap();
+        #[cfg(feature = "stats")]
+        let steps = list.stats().steps;
+
+        list.swap_handles(&one, &three);
+        list.assert_valid();
+        assert_eq!(list, vec![0, 3, 2, 1, 4]);
+        // the handles went with their nodes
+        assert_eq!((elem(&one), elem(&one.prev().unwrap())), (1, 2));
+
+        // neighbours, given back to front, and then the ends
+        list.swap_handles(&one, &one.prev().unwrap());
+        list.assert_valid();
+        assert_eq!(list, vec![0, 3, 1, 2, 4]);
+        list.swap_handles(&four, &zero);
+        list.swap_handles(&three, &three);
+        list.assert_valid();
+        assert_eq!(list, vec![4, 3, 1, 2, 0]);
+        assert_eq!(elem(&list.front_handle().unwrap()), 4);
+        #[cfg(feature = "stats")]
+        assert_eq!(list.stats().steps, steps, "swap_handles walked");
+    }
+
+    #[test]
+    #[should_panic(expected = "handle to a node that's been popped")]
+    fn swap_handles_after_pop() {
+        let mut list = List::new();
+        list.extend_back(0..3);
+        let front = list.front_handle().unwrap();
+        let back = list.back_handle().unwrap();
+        list.pop_front();
+        list.swap_handles(&front, &back);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "handle from another deque")]
+    fn swap_handles_from_another_deque() {
+        let mut list = List::new();
+        list.extend_back(0..4);
+        let front = list.front_handle().unwrap();
+        let mut back = list.split_off(2);
+        back.swap_handles(&front, &back.back_handle().unwrap());
+    }
+
+    #[test]
+    fn move_to_front() {
+        let mut list = List::new();
+        list.extend_back(0..3);
+        let two = list.back_handle().unwrap();
+        let one = two.prev().unwrap();
+        list.move_to_front(&one);
+        list.assert_valid();
+        assert_eq!(list, vec![1, 0, 2]);
+        list.move_to_front(&two);
+        list.move_to_front(&two);
+        list.assert_valid();
+        assert_eq!(list, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn try_next_with_shared_nodes() {
+        use super::SharedNode;
+
+        let mut list = List::from(vec![1, 2, 3, 4]);
+        let mut extract = list.extract_if(|elem| *elem == 3);
+        assert_eq!(extract.next(), Some(3));
+        // it's already holding 4, the next one it'd look at, and forgotten it
+        // never lets go
+        std::mem::forget(extract);
+
+        let mut iter = list.into_iter();
+        assert_eq!(iter.try_next_back(), Some(Err(SharedNode)));
+        // a failed try leaves everything in place
+        iter.0.assert_valid();
+        assert_eq!(iter.0, vec![1, 2, 4]);
+
+        assert_eq!(iter.try_next(), Some(Ok(1)));
+        assert_eq!(iter.try_next(), Some(Ok(2)));
+        // 4 is both ends now, and still held
+        assert_eq!(iter.try_next(), Some(Err(SharedNode)));
+        assert_eq!(iter.try_next_back(), Some(Err(SharedNode)));
+        // and dropping the rest cuts it loose rather than panicking
+        drop(iter);
+    }
+
+    #[test]
+    fn pop_if() {
+        let mut list = List::new();
+        assert_eq!(list.pop_front_if(|_| true), None);
+        assert_eq!(list.pop_back_if(|_| true), None);
+        list.extend_back([1, 2, 3]);
+
+        assert_eq!(list.pop_front_if(|&elem| elem > 1), None);
+        assert_eq!(list.pop_back_if(|&elem| elem > 1), Some(3));
+        assert_eq!(list.pop_front_if(|&elem| elem == 1), Some(1));
+        assert_eq!(list.pop_back_if(|&elem| elem == 1), None);
+        assert_eq!(list.pop_back_if(|&elem| elem == 2), Some(2));
+        list.assert_valid();
+        assert!(list.peek_front().is_none());
+    }
+
+    #[test]
+    fn cycle_detection() {
+        let mut list = List::new();
+        assert_eq!((list.has_cycle(), list.cycle_start()), (false, None));
+        list.extend_back(0..5);
+        assert!(!list.has_cycle());
+
+        for to in 0..5 {
+            list.make_cycle_for_test(to);
+            assert_eq!((list.has_cycle(), list.cycle_start()), (true, Some(to)));
+            list.tail.as_ref().unwrap().borrow_mut().next = None;
+        }
+        list.assert_valid();
+    }
+
+    #[test]
+    fn long_clear() {
+        let mut list = List::new();
+        for i in 0..1_000_000 {
+            list.push_back(i);
+        }
+        list.clear();
+        list.assert_valid();
+        assert!(list.peek_front().is_none() && list.peek_back().is_none());
+
+        list.push_back(1);
+        let mut taken = std::mem::take(&mut list);
+        assert_eq!((taken.pop_front(), list.pop_front()), (Some(1), None));
+    }
 }
@@ -0,0 +1,349 @@
+// second.rs and third.rs are the same singly-linked list twice over: one with Box
+// links, one with Rc. The walking, peeking and dropping barely change between
+// them — only the pointer does. So here the pointer is a type parameter, and
+// `second::List` and `third::List` are this list with Box and Rc links. Each of
+// those modules adds what only its pointer can do: only a Box list can pop or
+// hand out `&mut` (an Rc node might belong to other lists too), and only
+// Rc/Arc lists can share tails with `prepend`/`tail`.
+//
+// `LinkPtr` is a family of pointers rather than a pointer: `P::Ptr<N>` is "P
+// pointing at an N", the generic associated type doing what a higher-kinded
+// `P<N>` would if Rust had those. It's sealed, since `clear` relies on
+// `try_unwrap` being honest about sole ownership.
+//
+// The one price: a list's T is only reachable through a projection, so the list
+// is invariant in T where a plain Box list would be covariant.
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use core::ops::Deref;
+
+use crate::trace::trace;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A kind of owning pointer for the links: [`BoxPtr`], [`RcPtr`] or [`ArcPtr`].
+pub trait LinkPtr: sealed::Sealed {
+    type Ptr<N>: Deref<Target = N>;
+
+    fn new<N>(node: N) -> Self::Ptr<N>;
+
+    /// Hands the node back if nobody else points at it.
+    fn try_unwrap<N>(ptr: Self::Ptr<N>) -> Result<N, Self::Ptr<N>>;
+}
+
+/// Link pointers that can be cloned to share a node between lists.
+pub trait SharedPtr: LinkPtr {
+    fn share<N>(ptr: &Self::Ptr<N>) -> Self::Ptr<N>;
+
+    fn strong_count<N>(ptr: &Self::Ptr<N>) -> usize;
+}
+
+pub enum BoxPtr {}
+pub enum RcPtr {}
+#[cfg(target_has_atomic = "ptr")]
+pub enum ArcPtr {}
+
+impl sealed::Sealed for BoxPtr {}
+impl sealed::Sealed for RcPtr {}
+#[cfg(target_has_atomic = "ptr")]
+impl sealed::Sealed for ArcPtr {}
+
+impl LinkPtr for BoxPtr {
+    type Ptr<N> = Box<N>;
+
+    fn new<N>(node: N) -> Box<N> {
+        Box::new(node)
+    }
+
+    // a Box is always the only owner
+    fn try_unwrap<N>(ptr: Box<N>) -> Result<N, Box<N>> {
+        Ok(*ptr)
+    }
+}
+
+impl LinkPtr for RcPtr {
+    type Ptr<N> = Rc<N>;
+
+    fn new<N>(node: N) -> Rc<N> {
+        Rc::new(node)
+    }
+
+    fn try_unwrap<N>(ptr: Rc<N>) -> Result<N, Rc<N>> {
+        Rc::try_unwrap(ptr)
+    }
+}
+
+impl SharedPtr for RcPtr {
+    fn share<N>(ptr: &Rc<N>) -> Rc<N> {
+        Rc::clone(ptr)
+    }
+
+    fn strong_count<N>(ptr: &Rc<N>) -> usize {
+        Rc::strong_count(ptr)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl LinkPtr for ArcPtr {
+    type Ptr<N> = Arc<N>;
+
+    fn new<N>(node: N) -> Arc<N> {
+        Arc::new(node)
+    }
+
+    fn try_unwrap<N>(ptr: Arc<N>) -> Result<N, Arc<N>> {
+        Arc::try_unwrap(ptr)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl SharedPtr for ArcPtr {
+    fn share<N>(ptr: &Arc<N>) -> Arc<N> {
+        Arc::clone(ptr)
+    }
+
+    fn strong_count<N>(ptr: &Arc<N>) -> usize {
+        Arc::strong_count(ptr)
+    }
+}
+
+/// A persistent list that can be shared across threads: third.rs's, with Arc
+/// links.
+#[cfg(target_has_atomic = "ptr")]
+pub type SyncPersistent<T> = GenericList<T, ArcPtr>;
+
+pub struct GenericList<T, P: LinkPtr> {
+    pub(crate) head: Link<T, P>,
+    // so len() and the iterators' size_hints don't have to walk the chain
+    pub(crate) len: usize,
+    // Persistent lists inherit the counters of the list they were made from.
+    #[cfg(feature = "stats")]
+    pub(crate) stats: crate::stats::Counters,
+}
+
+pub struct Node<T, P: LinkPtr> {
+    pub(crate) value: T,
+    pub(crate) next: Link<T, P>,
+}
+
+pub(crate) type Link<T, P> = Option<<P as LinkPtr>::Ptr<Node<T, P>>>;
+
+impl<T, P: LinkPtr> GenericList<T, P> {
+    #[must_use] // linter error if invoked without binding return value
+    pub const fn new() -> Self {
+        Self {
+            head: None,
+            len: 0,
+            #[cfg(feature = "stats")]
+            stats: crate::stats::Counters::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            next: self.head.as_deref(),
+            remaining: self.len,
+            #[cfg(feature = "stats")]
+            stats: &self.stats,
+        }
+    }
+
+    /// Lets go of our nodes. Ones other lists still share survive, of course.
+    pub fn clear(&mut self) {
+        // Default Drop isn't tail recursive: a link drops its node, which drops
+        // the next link, and so on, a stack frame each. So unlink as we go,
+        // stopping at the first node another list still owns.
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            head = P::try_unwrap(node)
+                .ok()
+                .and_then(|mut node| node.next.take());
+        }
+        self.len = 0;
+    }
+}
+
+// Counting from the back, where from_end(0) is the last element. Without a tail,
+// the classic trick is a runner: send one pointer n nodes ahead, then walk both
+// in step. When the lead hits the last node, the trailer is n behind it.
+impl<T, P: LinkPtr> GenericList<T, P> {
+    #[must_use]
+    pub fn from_end(&self, n: usize) -> Option<&T> {
+        let mut lead = self.head.as_deref();
+        for _ in 0..n {
+            lead = lead?.next.as_deref();
+        }
+        let mut lead = lead?;
+        let mut trail = self.head.as_deref()?;
+        while let Some(next) = lead.next.as_deref() {
+            lead = next;
+            trail = trail.next.as_deref()?;
+        }
+        Some(&trail.value)
+    }
+
+    /// The middle element: for an even length, the last one of the first half.
+    /// The same runner trick, except the lead moves two nodes for every one the
+    /// trailer does, so the trailer's halfway when the lead runs out.
+    #[must_use]
+    pub fn middle(&self) -> Option<&T> {
+        self.midpoint().map(|(_, node)| &node.value)
+    }
+
+    /// Where `middle()` is, counting from the front.
+    #[must_use]
+    pub fn middle_index(&self) -> Option<usize> {
+        self.midpoint().map(|(index, _)| index)
+    }
+
+    fn midpoint(&self) -> Option<(usize, &Node<T, P>)> {
+        let mut trail = self.head.as_deref()?;
+        let mut lead = trail;
+        let mut index = 0;
+        while let Some(two_on) = lead.next.as_deref().and_then(|next| next.next.as_deref()) {
+            lead = two_on;
+            trail = trail.next.as_deref()?;
+            index += 1;
+        }
+        Some((index, trail))
+    }
+}
+
+// The persistent half: new lists made from old ones, sharing their nodes.
+impl<T, P: SharedPtr> GenericList<T, P> {
+    /// Prepends an element to the existing list.
+    /// I think this is synonymous with a `push_front`.
+    #[must_use]
+    pub fn prepend(&self, elem: T) -> Self {
+        let next = self.head.as_ref().map(P::share);
+        trace!(strong = ?next.as_ref().map(P::strong_count), "clone head as next");
+        #[cfg(feature = "stats")]
+        let stats = crate::stats::Counters::from_stats(self.stats.snapshot());
+        #[cfg(feature = "stats")]
+        {
+            stats.push();
+            if next.is_some() {
+                stats.reuse();
+            }
+        }
+        Self {
+            head: Some(P::new(Node { value: elem, next })),
+            len: self.len + 1,
+            #[cfg(feature = "stats")]
+            stats,
+        }
+    }
+
+    #[must_use]
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|n| &n.value)
+    }
+
+    #[must_use]
+    pub fn tail(&self) -> Self {
+        let head = self
+            .head
+            .as_ref()
+            .and_then(|node| node.next.as_ref().map(P::share));
+        trace!(strong = ?head.as_ref().map(P::strong_count), "clone next as head");
+        #[cfg(feature = "stats")]
+        let stats = crate::stats::Counters::from_stats(self.stats.snapshot());
+        #[cfg(feature = "stats")]
+        {
+            stats.pop();
+            if head.is_some() {
+                stats.reuse();
+            }
+        }
+        Self {
+            head,
+            len: self.len.saturating_sub(1),
+            #[cfg(feature = "stats")]
+            stats,
+        }
+    }
+}
+
+impl<T, P: LinkPtr> Default for GenericList<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "stats")]
+impl<T, P: LinkPtr> GenericList<T, P> {
+    #[must_use]
+    pub fn stats(&self) -> crate::stats::Stats {
+        self.stats.snapshot()
+    }
+}
+
+impl<T, P: LinkPtr> Drop for GenericList<T, P> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+pub struct Iter<'a, T, P: LinkPtr> {
+    next: Option<&'a Node<T, P>>,
+    remaining: usize,
+    #[cfg(feature = "stats")]
+    stats: &'a crate::stats::Counters,
+}
+
+impl<'a, T, P: LinkPtr> Iterator for Iter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            self.remaining -= 1;
+            #[cfg(feature = "stats")]
+            self.stats.step();
+            &node.value
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, P: LinkPtr> ExactSizeIterator for Iter<'_, T, P> {}
+
+#[cfg(all(test, target_has_atomic = "ptr"))]
+mod tests {
+    use super::SyncPersistent;
+    use std::thread;
+
+    // the same persistent list as third.rs's, but its nodes can cross threads
+    #[test]
+    fn sync_persistent_shares_across_threads() {
+        let base = SyncPersistent::new().prepend(3).prepend(2);
+        let handles: Vec<_> = (0..2)
+            .map(|i| {
+                let base = base.tail().prepend(i);
+                thread::spawn(move || base.iter().copied().collect::<Vec<_>>())
+            })
+            .collect();
+        let lists: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(lists, [[0, 3], [1, 3]]);
+        assert_eq!(base.iter().copied().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!((base.len(), base.tail().tail().len()), (2, 0));
+    }
+}
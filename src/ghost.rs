@@ -0,0 +1,324 @@
+// A doubly-linked deque with no RefCell and no unsafe in sight (well, outside of GhostCell).
+//
+// fourth.rs wraps every node in a RefCell, so each access pays for a runtime borrow check, and
+// getting it wrong is a panic. GhostCell moves that check to compile time: instead of each cell
+// tracking its own borrows, ALL the cells share one "token", and you need &token to read any of
+// them or &mut token to write any of them. The usual &/&mut rules on the token then guarantee
+// nobody writes a node while someone else is reading one.
+//
+// The trick that makes this sound is "branding": every token gets a unique, made-up lifetime
+// 'id, and cells are stamped with the same 'id, so a token can only unlock its own cells. The
+// only way to get a token is GhostToken::new, which hands one to your closure with a brand new
+// 'id that can't be unified with any other.
+//
+// Based on the GhostCell paper (Yanovski et al., ICFP 2021).
+
+use alloc::rc::{Rc, Weak};
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+
+// fn(&'id ()) -> &'id () makes 'id invariant, so the compiler can't stretch or shrink one brand
+// into another.
+type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+pub struct GhostToken<'id> {
+    _brand: Brand<'id>,
+}
+
+impl GhostToken<'_> {
+    /// Runs `f` with a freshly branded token.
+    // Named after the paper. It can't return Self: the token must not outlive f.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<R>(f: impl for<'new> FnOnce(GhostToken<'new>) -> R) -> R {
+        f(GhostToken {
+            _brand: PhantomData,
+        })
+    }
+}
+
+pub struct GhostCell<'id, T: ?Sized> {
+    _brand: Brand<'id>,
+    value: UnsafeCell<T>,
+}
+
+impl<'id, T> GhostCell<'id, T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            _brand: PhantomData,
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    // Owning the cell is proof enough; no token needed.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<'id, T: ?Sized> GhostCell<'id, T> {
+    pub fn borrow<'a>(&'a self, _token: &'a GhostToken<'id>) -> &'a T {
+        // SAFETY: we hold a shared borrow of the one token with our brand for 'a, so nobody
+        // can be holding the &mut token needed to write any cell with our brand.
+        unsafe { &*self.value.get() }
+    }
+
+    pub fn borrow_mut<'a>(&'a self, _token: &'a mut GhostToken<'id>) -> &'a mut T {
+        // SAFETY: we hold the one token with our brand mutably for 'a, so nobody else can be
+        // reading or writing any cell with our brand.
+        unsafe { &mut *self.value.get() }
+    }
+}
+
+// Next links own the next node; prev links are Weak so the chain has no Rc cycles. That way
+// dropping the list never needs the token (see Drop below).
+type Link<'id, T> = Option<Rc<GhostCell<'id, Node<'id, T>>>>;
+type WeakLink<'id, T> = Option<Weak<GhostCell<'id, Node<'id, T>>>>;
+
+// GhostCell is a bare UnsafeCell, and Weak is non-null (its dangling sentinel
+// isn't null either), so both kinds of link stay one pointer wide.
+const _: () = assert!(size_of::<Link<'static, u64>>() == size_of::<usize>());
+const _: () = assert!(size_of::<WeakLink<'static, u64>>() == size_of::<usize>());
+const _: () = assert!(size_of::<Node<'static, u64>>() == size_of::<(u64, usize, usize)>());
+
+pub struct List<'id, T> {
+    head: Link<'id, T>,
+    tail: Link<'id, T>,
+}
+
+struct Node<'id, T> {
+    elem: T,
+    next: Link<'id, T>,
+    prev: WeakLink<'id, T>,
+}
+
+impl<'id, T> Node<'id, T> {
+    fn new(elem: T) -> Rc<GhostCell<'id, Self>> {
+        Rc::new(GhostCell::new(Self {
+            elem,
+            next: None,
+            prev: None,
+        }))
+    }
+}
+
+impl<'id, T> List<'id, T> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+        }
+    }
+
+    pub fn push_front(&mut self, elem: T, token: &mut GhostToken<'id>) {
+        let new_head = Node::new(elem);
+        if let Some(old_head) = self.head.take() {
+            old_head.borrow_mut(token).prev = Some(Rc::downgrade(&new_head));
+            new_head.borrow_mut(token).next = Some(old_head);
+        } else {
+            self.tail = Some(new_head.clone());
+        }
+        self.head = Some(new_head);
+    }
+
+    pub fn push_back(&mut self, elem: T, token: &mut GhostToken<'id>) {
+        let new_tail = Node::new(elem);
+        if let Some(old_tail) = self.tail.take() {
+            new_tail.borrow_mut(token).prev = Some(Rc::downgrade(&old_tail));
+            old_tail.borrow_mut(token).next = Some(new_tail.clone());
+        } else {
+            self.head = Some(new_tail.clone());
+        }
+        self.tail = Some(new_tail);
+    }
+
+    pub fn pop_front(&mut self, token: &mut GhostToken<'id>) -> Option<T> {
+        self.head.take().map(|old_head| {
+            match old_head.borrow_mut(token).next.take() {
+                Some(new_head) => {
+                    new_head.borrow_mut(token).prev = None;
+                    self.head = Some(new_head);
+                }
+                None => {
+                    // list is emptied after this pop
+                    self.tail = None;
+                }
+            }
+            // next owned nothing but us, head and tail have let go: we're the last owner
+            Rc::try_unwrap(old_head).ok().unwrap().into_inner().elem
+        })
+    }
+
+    pub fn pop_back(&mut self, token: &mut GhostToken<'id>) -> Option<T> {
+        self.tail.take().map(|old_tail| {
+            let prev = old_tail.borrow_mut(token).prev.take();
+            match prev.and_then(|prev| prev.upgrade()) {
+                Some(new_tail) => {
+                    // this drops the previous node's strong hold on us
+                    new_tail.borrow_mut(token).next = None;
+                    self.tail = Some(new_tail);
+                }
+                None => {
+                    // list is emptied after this pop
+                    self.head = None;
+                }
+            }
+            Rc::try_unwrap(old_tail).ok().unwrap().into_inner().elem
+        })
+    }
+
+    // Plain references, no Ref guards! The token borrow keeps everything honest.
+    pub fn peek_front<'a>(&'a self, token: &'a GhostToken<'id>) -> Option<&'a T> {
+        self.head.as_ref().map(|node| &node.borrow(token).elem)
+    }
+
+    pub fn peek_back<'a>(&'a self, token: &'a GhostToken<'id>) -> Option<&'a T> {
+        self.tail.as_ref().map(|node| &node.borrow(token).elem)
+    }
+
+    pub fn peek_front_mut<'a>(&'a self, token: &'a mut GhostToken<'id>) -> Option<&'a mut T> {
+        self.head
+            .as_ref()
+            .map(|node| &mut node.borrow_mut(token).elem)
+    }
+
+    pub fn peek_back_mut<'a>(&'a self, token: &'a mut GhostToken<'id>) -> Option<&'a mut T> {
+        self.tail
+            .as_ref()
+            .map(|node| &mut node.borrow_mut(token).elem)
+    }
+
+    // Something fourth.rs couldn't give us: a borrowing iterator. It just needs the token.
+    pub fn iter<'a>(&'a self, token: &'a GhostToken<'id>) -> Iter<'a, 'id, T> {
+        Iter {
+            next: self.head.as_ref().map(|node| node.borrow(token)),
+            token,
+        }
+    }
+}
+
+impl<T> Default for List<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// We can't touch the nodes' insides without a token, but we don't need to: once we're the only
+// owner of a node, GhostCell::into_inner hands it over by value. Same iterative teardown as
+// second.rs, so long lists don't recurse.
+impl<T> Drop for List<'_, T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> List<'_, T> {
+    pub fn clear(&mut self) {
+        self.tail.take();
+        let mut current = self.head.take();
+        while let Some(node) = current {
+            current = match Rc::try_unwrap(node) {
+                Ok(node) => node.into_inner().next,
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+pub struct Iter<'a, 'id, T> {
+    next: Option<&'a Node<'id, T>>,
+    token: &'a GhostToken<'id>,
+}
+
+impl<'a, T> Iterator for Iter<'a, '_, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_ref().map(|next| next.borrow(self.token));
+            &node.elem
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GhostToken, List};
+
+    #[test]
+    fn basics() {
+        GhostToken::new(|mut token| {
+            let mut list = List::new();
+            assert_eq!(list.pop_front(&mut token), None);
+            assert_eq!(list.pop_back(&mut token), None);
+
+            list.push_front(2, &mut token);
+            list.push_front(1, &mut token);
+            list.push_back(3, &mut token);
+
+            assert_eq!(list.peek_front(&token), Some(&1));
+            assert_eq!(list.peek_back(&token), Some(&3));
+
+            assert_eq!(list.pop_back(&mut token), Some(3));
+            assert_eq!(list.pop_front(&mut token), Some(1));
+            assert_eq!(list.pop_back(&mut token), Some(2));
+            assert_eq!(list.pop_front(&mut token), None);
+
+            // refill after emptying, from the other ends
+            list.push_back(4, &mut token);
+            list.push_front(5, &mut token);
+            assert_eq!(list.pop_front(&mut token), Some(5));
+            assert_eq!(list.pop_front(&mut token), Some(4));
+            assert_eq!(list.pop_back(&mut token), None);
+        });
+    }
+
+    #[test]
+    fn peek_mut() {
+        GhostToken::new(|mut token| {
+            let mut list = List::new();
+            list.push_back(1, &mut token);
+            list.push_back(2, &mut token);
+
+            *list.peek_front_mut(&mut token).unwrap() *= 10;
+            *list.peek_back_mut(&mut token).unwrap() *= 10;
+
+            assert_eq!(list.iter(&token).copied().collect::<Vec<_>>(), vec![10, 20]);
+        });
+    }
+
+    #[test]
+    fn iter() {
+        GhostToken::new(|mut token| {
+            let mut list = List::new();
+            for i in 1..=3 {
+                list.push_back(i, &mut token);
+            }
+
+            // two iterators at once are fine — they only share the token
+            let mut a = list.iter(&token);
+            let mut b = list.iter(&token);
+            assert_eq!(a.next(), Some(&1));
+            assert_eq!(b.next(), Some(&1));
+            assert_eq!(a.next(), Some(&2));
+            assert_eq!(a.next(), Some(&3));
+            assert_eq!(a.next(), None);
+            assert_eq!(b.next(), Some(&2));
+        });
+    }
+
+    #[test]
+    fn drop_without_token() {
+        use std::rc::Rc;
+
+        let canary = Rc::new(());
+        GhostToken::new(|mut token| {
+            let mut list = List::new();
+            for _ in 0..100_000 {
+                list.push_back(Rc::clone(&canary), &mut token);
+            }
+            // list goes out of scope here, no token involved
+        });
+        assert_eq!(Rc::strong_count(&canary), 1);
+    }
+}
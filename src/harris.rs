@@ -0,0 +1,415 @@
+// Harris's lock-free ordered list: a sorted set that any number of threads can
+// insert into, search and remove from at once, with no locks at all. Every
+// change is one compare-and-swap on a `next` pointer.
+//
+// Insertion is the easy half: find the two nodes the new one goes between, point
+// it at the second, and CAS the first's `next` from the second to it. If someone
+// changed that link in the meantime the CAS fails and we look again.
+//
+// Removal is where it gets interesting. Unlinking a node with one CAS on its
+// predecessor isn't enough: a thread inserting right after the node could CAS
+// the node's own `next` at the same moment, and its new node would be unlinked
+// along with it. So removal takes two steps. First the node is marked as deleted
+// by setting the low bit of its own `next` (the "mark"), which makes every later
+// CAS on that link fail: nobody can insert after a deleted node. That's the
+// moment it leaves the set. Then it's unlinked from its predecessor, by the
+// remover or by whoever walks past it next, whichever gets there first.
+//
+// Once a node is unlinked, other threads may still be standing on it, so it
+// can't be freed straight away. crossbeam-epoch keeps track of that: every
+// operation runs pinned to an epoch, and an unlinked node is only freed once
+// every thread that might have seen it has moved on.
+//
+// A plain walk down the list can see one change and miss an earlier one, giving
+// a set that never existed at any single moment. `snapshot_iter` counts writes
+// instead, seqlock style: every insert and remove bumps `started` before it
+// touches the list and `finished` after. A walk that starts with the two equal
+// (no write in flight) and ends with `started` unchanged saw the list standing
+// still; anything else, and it goes round again.
+//
+// That has two costs. Writers pay two SeqCst read-modify-writes each on counters
+// that every writer shares, snapshot or no snapshot. That's one cache line all of
+// them fight over, which is exactly what a lock-free list is meant to avoid.
+// benches/harris.rs measures it: on the single-core machine it was written on,
+// taking the counters out made no difference beyond the noise (about 150ns an
+// insert or remove either way), but one core can't show cache-line contention,
+// so run it on a real multicore box before leaning on these numbers. And
+// `snapshot_iter` itself isn't lock-free: it only finishes once it finds a gap
+// between writes long enough to walk the list in, so writers that never let up
+// starve it forever.
+
+use core::cmp::Ordering as Cmp;
+use core::hint;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned, Shared};
+
+use crate::second;
+
+struct Node<T> {
+    value: T,
+    // tagged 1 once this node is deleted
+    next: Atomic<Node<T>>,
+}
+
+/// Removed nodes are freed whenever the epoch gets round to it, by whichever
+/// thread happens to collect the garbage, and possibly after the list itself is
+/// gone. So the elements have to be `Send + 'static`, even in a list that never
+/// leaves its thread:
+///
+/// ```compile_fail
+/// use std::rc::Rc;
+/// use too_many_linked_lists::harris::List;
+///
+/// let list = List::new();
+/// list.insert(Rc::new(1));
+/// ```
+pub struct List<T> {
+    head: Atomic<Node<T>>,
+    // writes begun and writes done, for snapshot_iter
+    started: AtomicUsize,
+    finished: AtomicUsize,
+}
+
+// Bumps `finished` when the write's over, however it ends.
+struct Writing<'a>(&'a AtomicUsize);
+
+impl Drop for Writing<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl<T> List<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            head: Atomic::null(),
+            started: AtomicUsize::new(0),
+            finished: AtomicUsize::new(0),
+        }
+    }
+
+    fn writing(&self) -> Writing<'_> {
+        self.started.fetch_add(1, Ordering::SeqCst);
+        Writing(&self.finished)
+    }
+}
+
+// Send + 'static: see List
+impl<T: Ord + Send + 'static> List<T> {
+    // Finds the first live node not less than `value`, and the link pointing at
+    // it. Deleted nodes on the way get unlinked; if someone else changes a link
+    // under us while we do that, we start over from the head.
+    fn find<'g>(
+        &'g self,
+        value: &T,
+        guard: &'g Guard,
+    ) -> (&'g Atomic<Node<T>>, Shared<'g, Node<T>>) {
+        'retry: loop {
+            let mut prev = &self.head;
+            let mut curr = prev.load(Ordering::Acquire, guard);
+            loop {
+                // Safety: we're pinned, so nothing we reach from the head is freed
+                let Some(node) = (unsafe { curr.as_ref() }) else {
+                    return (prev, curr);
+                };
+                let next = node.next.load(Ordering::Acquire, guard);
+                if next.tag() == 1 {
+                    // `prev` may itself be deleted by now, in which case its tag
+                    // makes this fail and we start over
+                    if prev
+                        .compare_exchange(
+                            curr,
+                            next.with_tag(0),
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                            guard,
+                        )
+                        .is_err()
+                    {
+                        continue 'retry;
+                    }
+                    // Safety: we unlinked it, so only threads already pinned can
+                    // still reach it
+                    unsafe { guard.defer_destroy(curr) };
+                    curr = next.with_tag(0);
+                    continue;
+                }
+                if node.value >= *value {
+                    return (prev, curr);
+                }
+                prev = &node.next;
+                curr = next;
+            }
+        }
+    }
+
+    /// Adds `value`, unless it's already there. Returns whether it was added.
+    pub fn insert(&self, value: T) -> bool {
+        let _writing = self.writing();
+        let guard = &epoch::pin();
+        let mut node = Owned::new(Node {
+            value,
+            next: Atomic::null(),
+        });
+        loop {
+            let (prev, curr) = self.find(&node.value, guard);
+            if unsafe { curr.as_ref() }.is_some_and(|curr| curr.value == node.value) {
+                return false;
+            }
+            node.next.store(curr, Ordering::Relaxed);
+            // Release: whoever finds the node sees its value written
+            match prev.compare_exchange(curr, node, Ordering::Release, Ordering::Relaxed, guard) {
+                Ok(_) => return true,
+                Err(lost) => node = lost.new,
+            }
+        }
+    }
+
+    /// Doesn't write anything, and never retries: it walks straight past
+    /// deleted nodes instead of unlinking them.
+    #[must_use]
+    pub fn contains(&self, value: &T) -> bool {
+        let guard = &epoch::pin();
+        let mut curr = self.head.load(Ordering::Acquire, guard);
+        while let Some(node) = unsafe { curr.as_ref() } {
+            let next = node.next.load(Ordering::Acquire, guard);
+            match node.value.cmp(value) {
+                Cmp::Less => curr = next.with_tag(0),
+                Cmp::Equal => return next.tag() == 0,
+                Cmp::Greater => return false,
+            }
+        }
+        false
+    }
+
+    /// Takes `value` out. Returns whether it was there. Only one of several
+    /// threads removing the same value at once gets true.
+    pub fn remove(&self, value: &T) -> bool {
+        let _writing = self.writing();
+        let guard = &epoch::pin();
+        loop {
+            let (prev, curr) = self.find(value, guard);
+            let Some(node) = (unsafe { curr.as_ref() }) else {
+                return false;
+            };
+            if node.value != *value {
+                return false;
+            }
+            let next = node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 1 {
+                // another remover got here first; the next find will unlink it
+                continue;
+            }
+            // the mark: from here on, the value is gone
+            if node
+                .next
+                .compare_exchange(
+                    next,
+                    next.with_tag(1),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                    guard,
+                )
+                .is_err()
+            {
+                // an insert after it, or another remover: look again
+                continue;
+            }
+            match prev.compare_exchange(curr, next, Ordering::AcqRel, Ordering::Acquire, guard) {
+                // Safety: unlinked, as in find
+                Ok(_) => unsafe { guard.defer_destroy(curr) },
+                // the link moved under us; a find walks past and unlinks it
+                Err(_) => {
+                    self.find(value, guard);
+                }
+            }
+            return true;
+        }
+    }
+}
+
+impl<T: Clone> List<T> {
+    /// Copies out the whole set as it stood at one moment, in order. It waits
+    /// out writes in progress and starts over if any land mid-copy, so it needs
+    /// a quiet moment as long as the walk: while writers keep overlapping each
+    /// other, or keep landing mid-copy, it never returns.
+    #[must_use]
+    pub fn snapshot_iter(&self) -> second::IntoIter<T> {
+        loop {
+            let finished = self.finished.load(Ordering::SeqCst);
+            let started = self.started.load(Ordering::SeqCst);
+            if started != finished {
+                hint::spin_loop();
+                continue;
+            }
+
+            let mut copy = second::List::new();
+            let mut end = copy.cursor_front_mut();
+            let guard = &epoch::pin();
+            let mut curr = self.head.load(Ordering::Acquire, guard);
+            while let Some(node) = unsafe { curr.as_ref() } {
+                let next = node.next.load(Ordering::Acquire, guard);
+                if next.tag() == 0 {
+                    end.insert_after(node.value.clone());
+                }
+                curr = next.with_tag(0);
+            }
+
+            // any write whose change we saw bumped `started` before making it
+            if self.started.load(Ordering::SeqCst) == started {
+                return copy.into_iter();
+            }
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        // Safety: we have the only handle, so no other thread is in the list.
+        // The nodes still on the chain, deleted or not, are ours to free; the
+        // unlinked ones were handed to the epoch already.
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut curr = self.head.load(Ordering::Relaxed, guard);
+            while !curr.is_null() {
+                let next = curr.deref().next.load(Ordering::Relaxed, guard);
+                drop(curr.into_owned());
+                curr = next.with_tag(0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::List;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let list = List::new();
+        assert!(!list.contains(&1));
+        assert!(!list.remove(&1));
+
+        assert!(list.insert(3));
+        assert!(list.insert(1));
+        assert!(list.insert(2));
+        assert!(!list.insert(2));
+        assert!(list.contains(&1) && list.contains(&2) && list.contains(&3));
+
+        assert!(list.remove(&2));
+        assert!(!list.remove(&2));
+        assert!(!list.contains(&2));
+        assert!(list.contains(&1) && list.contains(&3));
+
+        assert!(list.insert(2));
+        assert!(list.contains(&2));
+    }
+
+    #[test]
+    fn drops_what_is_left() {
+        let value = Arc::new(());
+        let list = List::new();
+        for i in 0..10 {
+            list.insert((i, Arc::clone(&value)));
+        }
+        drop(list);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+
+    // Every thread tries to insert every value; each one goes in exactly once.
+    #[test]
+    fn one_insert_wins() {
+        let list = List::new();
+        let inserted = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for i in 0..1000 {
+                        if list.insert(i) {
+                            inserted.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(inserted.into_inner(), 1000);
+        assert!((0..1000).all(|i| list.contains(&i)));
+    }
+
+    // The writer works both ends at once: step k inserts -k at the front, then
+    // removes M + k - 1 from the back. So at any moment, a set with n negatives
+    // starts its back at M + n - 1 or M + n. A walk that saw the front early and
+    // the back late, with writes landing in between, would find it further on.
+    #[test]
+    fn snapshots_see_whole_moments() {
+        const M: i64 = 2000;
+        const STEPS: i64 = 2000;
+        let list = List::new();
+        for i in 0..M + STEPS {
+            list.insert(i);
+        }
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    let seen: Vec<_> = list.snapshot_iter().collect();
+                    let negatives = seen.iter().filter(|&&i| i < 0).count() as i64;
+                    let back = seen.iter().find(|&&i| i >= M).copied();
+                    let back = back.unwrap_or(M + STEPS);
+                    assert!(back == M + negatives - 1 || back == M + negatives);
+                }
+            });
+            for k in 1..=STEPS {
+                list.insert(-k);
+                list.remove(&(M + k - 1));
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(list.snapshot_iter().count() as i64, M + STEPS);
+    }
+
+    // Inserters and removers working on neighbouring values, which is exactly
+    // the case the mark is there for.
+    #[test]
+    fn stress() {
+        const N: usize = 2000;
+        let list = List::new();
+        for i in (0..N).filter(|i| i % 2 == 0) {
+            list.insert(i);
+        }
+        let removed = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for i in (0..N).filter(|i| i % 2 == 1) {
+                        list.insert(i);
+                    }
+                });
+                scope.spawn(|| {
+                    for i in (0..N).filter(|i| i % 2 == 0) {
+                        if list.remove(&i) {
+                            removed.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(removed.into_inner(), N / 2);
+        for i in 0..N {
+            assert_eq!(list.contains(&i), i % 2 == 1, "{i}");
+        }
+    }
+}
@@ -0,0 +1,512 @@
+// A stack that never touches the heap: all N nodes live inside the list itself,
+// and links are indices into that array instead of pointers. No allocator needed,
+// so it's fair game on the smallest no_std targets.
+//
+// Slots start out uninitialized. `used` is a high-water mark: everything past it
+// has never held a node. Popped slots go on a free list, threaded through their
+// (still perfectly good) `next` field, and get handed out again before we touch
+// any fresh slot.
+
+use core::cell::{Cell, UnsafeCell};
+use core::mem::MaybeUninit;
+use core::ptr;
+
+type Link = Option<usize>;
+
+struct Node<T> {
+    elem: T,
+    next: Link,
+}
+
+pub struct List<T, const N: usize> {
+    nodes: [MaybeUninit<Node<T>>; N],
+    head: Link,
+    free: Link,
+    used: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> List<T, N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            nodes: [const { MaybeUninit::uninit() }; N],
+            head: None,
+            free: None,
+            used: 0,
+            len: 0,
+        }
+    }
+
+    /// Pushes onto the front, or hands `elem` back if all N slots are taken.
+    pub fn try_push(&mut self, elem: T) -> Result<(), T> {
+        let index = match self.free {
+            Some(index) => {
+                // free slots only have their `next` initialized, so read just that
+                self.free = unsafe { ptr::addr_of!((*self.nodes[index].as_ptr()).next).read() };
+                index
+            }
+            None if self.used < N => {
+                self.used += 1;
+                self.used - 1
+            }
+            None => return Err(elem),
+        };
+
+        self.nodes[index].write(Node {
+            elem,
+            next: self.head,
+        });
+        self.head = Some(index);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let index = self.head?;
+        // Moves the node out; the slot is logically uninitialized again...
+        let Node { elem, next } = unsafe { self.nodes[index].assume_init_read() };
+        self.head = next;
+        // ...except for the `next` we write back to join the free list.
+        unsafe { ptr::addr_of_mut!((*self.nodes[index].as_mut_ptr()).next).write(self.free) };
+        self.free = Some(index);
+        self.len -= 1;
+        Some(elem)
+    }
+
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.head.map(|index| &self.node(index).elem)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head
+            .map(|index| unsafe { &mut self.nodes[index].assume_init_mut().elem })
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drops every element. The slots stay, on the free list.
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Moves the live nodes into slots `0..len`, in list order, so iterating walks
+    /// the array front to back and the free list is empty again. O(n) and no extra
+    /// space — handy after lots of churn has scattered the nodes about.
+    pub fn compact(&mut self) {
+        // Pass 1: every live node's `next` becomes its rank (its slot-to-be), and
+        // every free slot's becomes None, so we can tell the two apart below.
+        let mut free = self.free;
+        while let Some(index) = free {
+            let next = self.next_mut(index);
+            free = *next;
+            *next = None;
+        }
+        let mut link = self.head;
+        for rank in 0..self.len {
+            let index = link.expect("len and the links disagree");
+            let next = self.next_mut(index);
+            link = *next;
+            *next = Some(rank);
+        }
+
+        // Pass 2: cycle each slot's occupant to where it belongs. Every swap puts
+        // at least one live node in its final slot, so this is O(used) swaps.
+        for index in 0..self.used {
+            while let Some(rank) = *self.next_mut(index) {
+                if rank == index {
+                    break;
+                }
+                self.nodes.swap(index, rank);
+            }
+        }
+
+        // Pass 3: relink 0 -> 1 -> ... -> len - 1.
+        for index in 0..self.len {
+            *self.next_mut(index) = (index + 1 < self.len).then_some(index + 1);
+        }
+        self.head = (self.len > 0).then_some(0);
+        self.free = None;
+        self.used = self.len;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            list: self,
+            next: self.head,
+        }
+    }
+
+    // Only ever called with indices reachable from head, which are all initialized.
+    fn node(&self, index: usize) -> &Node<T> {
+        unsafe { self.nodes[index].assume_init_ref() }
+    }
+
+    // Every slot below `used` has at least its `next` initialized, live or free.
+    fn next_mut(&mut self, index: usize) -> &mut Link {
+        debug_assert!(index < self.used);
+        unsafe { &mut *ptr::addr_of_mut!((*self.nodes[index].as_mut_ptr()).next) }
+    }
+}
+
+impl<T, const N: usize> Default for List<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// MaybeUninit never drops what's inside it, so we have to.
+impl<T, const N: usize> Drop for List<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+pub struct Iter<'a, T, const N: usize> {
+    list: &'a List<T, N>,
+    next: Link,
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|index| {
+            let node = self.list.node(index);
+            self.next = node.next;
+            &node.elem
+        })
+    }
+}
+
+// The same slots-and-free-list scheme, pulled out of the list so several lists can
+// share one array: a device with a handful of queues that are never all full at
+// once can size one pool for the total, rather than every list for its worst case.
+//
+// The lists borrow the pool, so it needs interior mutability: the free list and
+// counters are Cells, and each slot is an UnsafeCell. A slot only ever belongs to
+// one list (or the free list) at a time, so no two lists touch the same one.
+pub struct StaticPool<T, const N: usize> {
+    nodes: [UnsafeCell<MaybeUninit<Node<T>>>; N],
+    free: Cell<Link>,
+    used: Cell<usize>,
+    in_use: Cell<usize>,
+}
+
+impl<T, const N: usize> StaticPool<T, N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            nodes: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            free: Cell::new(None),
+            used: Cell::new(0),
+            in_use: Cell::new(0),
+        }
+    }
+
+    /// A new, empty list drawing its nodes from this pool.
+    pub const fn list(&self) -> PoolList<'_, T, N> {
+        PoolList {
+            pool: self,
+            head: None,
+            len: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// How many more nodes the lists can take between them.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        N - self.in_use.get()
+    }
+
+    // Same as List::try_push, minus the linking.
+    fn alloc(&self, elem: T, next: Link) -> Result<usize, T> {
+        let index = match self.free.get() {
+            Some(index) => {
+                let slot = self.nodes[index].get().cast::<Node<T>>();
+                self.free.set(unsafe { ptr::addr_of!((*slot).next).read() });
+                index
+            }
+            None if self.used.get() < N => {
+                self.used.set(self.used.get() + 1);
+                self.used.get() - 1
+            }
+            None => return Err(elem),
+        };
+        unsafe { (*self.nodes[index].get()).write(Node { elem, next }) };
+        self.in_use.set(self.in_use.get() + 1);
+        Ok(index)
+    }
+
+    // `index` has to be a live node, which its list gives up here.
+    unsafe fn release(&self, index: usize) -> Node<T> {
+        let slot = self.nodes[index].get();
+        let node = (*slot).assume_init_read();
+        ptr::addr_of_mut!((*slot.cast::<Node<T>>()).next).write(self.free.get());
+        self.free.set(Some(index));
+        self.in_use.set(self.in_use.get() - 1);
+        node
+    }
+
+    // Only live nodes are safe to go through. Shared or mutable is up to the list
+    // that owns it, through &self or &mut self.
+    fn node(&self, index: usize) -> *mut Node<T> {
+        self.nodes[index].get().cast()
+    }
+}
+
+impl<T, const N: usize> Default for StaticPool<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A stack like [`List`], but with its nodes in a [`StaticPool`].
+pub struct PoolList<'p, T, const N: usize> {
+    pool: &'p StaticPool<T, N>,
+    head: Link,
+    len: usize,
+}
+
+impl<T, const N: usize> PoolList<'_, T, N> {
+    /// Pushes onto the front, or hands `elem` back if the pool has run dry.
+    pub fn try_push(&mut self, elem: T) -> Result<(), T> {
+        self.head = Some(self.pool.alloc(elem, self.head)?);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let index = self.head?;
+        let Node { elem, next } = unsafe { self.pool.release(index) };
+        self.head = next;
+        self.len -= 1;
+        Some(elem)
+    }
+
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.head
+            .map(|index| unsafe { &(*self.pool.node(index)).elem })
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head
+            .map(|index| unsafe { &mut (*self.pool.node(index)).elem })
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drops every element, handing the nodes back to the pool.
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    pub fn iter(&self) -> PoolIter<'_, T, N> {
+        PoolIter {
+            pool: self.pool,
+            next: self.head,
+        }
+    }
+}
+
+// Hands every node back to the pool.
+impl<T, const N: usize> Drop for PoolList<'_, T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+pub struct PoolIter<'a, T, const N: usize> {
+    pool: &'a StaticPool<T, N>,
+    next: Link,
+}
+
+impl<'a, T, const N: usize> Iterator for PoolIter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|index| {
+            let node = unsafe { &*self.pool.node(index) };
+            self.next = node.next;
+            &node.elem
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{List, StaticPool};
+    use std::rc::Rc;
+
+    #[test]
+    fn in_a_static() {
+        use std::sync::Mutex;
+
+        static STACK: Mutex<List<u32, 4>> = Mutex::new(List::new());
+        std::thread::spawn(|| assert_eq!(STACK.lock().unwrap().try_push(5), Ok(())))
+            .join()
+            .unwrap();
+        assert_eq!(STACK.lock().unwrap().pop(), Some(5));
+    }
+
+    #[test]
+    fn basics() {
+        let mut list: List<i32, 3> = List::new();
+        assert_eq!(list.pop(), None);
+
+        assert_eq!(list.try_push(1), Ok(()));
+        assert_eq!(list.try_push(2), Ok(()));
+        assert_eq!(list.try_push(3), Ok(()));
+        // full: the value comes back
+        assert_eq!(list.try_push(4), Err(4));
+        assert_eq!(list.len(), list.capacity());
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [3, 2, 1]);
+        assert_eq!(list.pop(), Some(3));
+        *list.peek_mut().unwrap() = 20;
+        assert_eq!(list.peek(), Some(&20));
+    }
+
+    #[test]
+    fn reuses_freed_slots() {
+        let mut list: List<i32, 2> = List::new();
+        for round in 0..10 {
+            assert_eq!(list.try_push(round), Ok(()));
+            assert_eq!(list.try_push(-round), Ok(()));
+            assert_eq!(list.try_push(99), Err(99));
+            assert_eq!(list.pop(), Some(-round));
+            assert_eq!(list.pop(), Some(round));
+            assert!(list.is_empty());
+        }
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut list: List<i32, 0> = List::new();
+        assert_eq!(list.try_push(1), Err(1));
+        assert_eq!(list.pop(), None);
+    }
+
+    #[test]
+    fn drops_what_it_holds() {
+        let canary = Rc::new(());
+        {
+            let mut list: List<Rc<()>, 4> = List::new();
+            for _ in 0..4 {
+                list.try_push(Rc::clone(&canary)).unwrap();
+            }
+            drop(list.pop());
+            assert_eq!(Rc::strong_count(&canary), 4);
+        }
+        assert_eq!(Rc::strong_count(&canary), 1);
+    }
+
+    #[test]
+    fn compact() {
+        let mut list: List<i32, 8> = List::new();
+        for elem in 0..8 {
+            list.try_push(elem).unwrap();
+        }
+        // free some slots in the middle of the array and reuse a couple of them
+        for _ in 0..5 {
+            list.pop();
+        }
+        list.try_push(10).unwrap();
+        list.try_push(11).unwrap();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [11, 10, 2, 1, 0]);
+
+        list.compact();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [11, 10, 2, 1, 0]);
+        assert_eq!((list.head, list.free, list.used), (Some(0), None, 5));
+
+        // and it's still a working list afterwards
+        for elem in 20..23 {
+            list.try_push(elem).unwrap();
+        }
+        assert_eq!(list.try_push(99), Err(99));
+        assert_eq!(list.pop(), Some(22));
+
+        let mut empty: List<i32, 4> = List::new();
+        empty.try_push(1).unwrap();
+        empty.pop();
+        empty.compact();
+        assert_eq!((empty.head, empty.free, empty.used), (None, None, 0));
+    }
+
+    #[test]
+    fn shared_pool() {
+        let pool = StaticPool::<i32, 4>::new();
+        let mut evens = pool.list();
+        let mut odds = pool.list();
+
+        assert_eq!(evens.try_push(2), Ok(()));
+        assert_eq!(evens.try_push(4), Ok(()));
+        assert_eq!(odds.try_push(1), Ok(()));
+        assert_eq!(odds.try_push(3), Ok(()));
+        // four nodes, all taken, whichever list asks
+        assert_eq!(pool.available(), 0);
+        assert_eq!(evens.try_push(6), Err(6));
+        assert_eq!(odds.try_push(5), Err(5));
+
+        // a pop in one list frees a node for the other
+        assert_eq!(evens.pop(), Some(4));
+        assert_eq!(odds.try_push(5), Ok(()));
+        assert_eq!(odds.iter().copied().collect::<Vec<_>>(), [5, 3, 1]);
+        *evens.peek_mut().unwrap() *= 10;
+        assert_eq!(evens.peek(), Some(&20));
+
+        // and dropping a list hands all of its nodes back
+        drop(odds);
+        assert_eq!(pool.available(), 3);
+        let mut more = pool.list();
+        for elem in 0..3 {
+            assert_eq!(more.try_push(elem), Ok(()));
+        }
+        assert_eq!(more.len(), 3);
+        assert_eq!(more.try_push(3), Err(3));
+    }
+
+    #[test]
+    fn pooled_elems_are_dropped() {
+        let canary = Rc::new(());
+        let pool = StaticPool::<Rc<()>, 8>::new();
+        {
+            let mut list = pool.list();
+            for _ in 0..5 {
+                assert!(list.try_push(Rc::clone(&canary)).is_ok());
+            }
+            drop(list.pop());
+            assert_eq!(Rc::strong_count(&canary), 5);
+        }
+        assert_eq!(Rc::strong_count(&canary), 1);
+        assert_eq!(pool.available(), 8);
+    }
+}
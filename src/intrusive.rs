@@ -0,0 +1,229 @@
+// An intrusive list: the links live inside the nodes, and the list only points at
+// nodes someone else owns. Here that someone is the program itself — nodes are
+// `static`s, so there's no heap anywhere, not even alloc. That's the shape
+// bare-metal code wants: a timer wheel or a wait queue whose entries are all
+// known at compile time.
+//
+//     static READY: List<Task> = List::new();
+//     static BLINK: Node<Task> = Node::new(Task::Blink);
+//
+//     critical_section::with(|cs| READY.push_front(&BLINK, cs));
+//
+// Every operation that reads or writes a link takes a `CriticalSection` token.
+// On a single-core microcontroller holding one means interrupts are off, so an
+// ISR can't come in halfway through a relink; on a host it's a global lock. The
+// token is what makes sharing sound: the links are plain Cells, and the only way
+// to touch them is while nobody else can. That's also why a node can be linked
+// into at most one list at a time — the `linked` flag is checked on every push.
+
+use core::cell::Cell;
+use core::marker::PhantomData;
+
+use critical_section::CriticalSection;
+
+pub struct Node<T: 'static> {
+    value: T,
+    next: Cell<Option<&'static Node<T>>>,
+    linked: Cell<bool>,
+}
+
+// The Cells are only ever touched with a CriticalSection in hand, and the value is
+// only ever handed out shared.
+unsafe impl<T: Sync> Sync for Node<T> {}
+
+impl<T> Node<T> {
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            next: Cell::new(None),
+            linked: Cell::new(false),
+        }
+    }
+
+    #[must_use]
+    pub const fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Whether the node is in a list right now.
+    #[must_use]
+    pub fn is_linked(&self, _cs: CriticalSection<'_>) -> bool {
+        self.linked.get()
+    }
+}
+
+pub struct List<T: 'static> {
+    head: Cell<Option<&'static Node<T>>>,
+}
+
+// Same deal as Node: no link changes without a CriticalSection.
+unsafe impl<T: Sync> Sync for List<T> {}
+
+impl<T> List<T> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            head: Cell::new(None),
+        }
+    }
+
+    /// Links `node` in at the front. If it's already in a list (this one or any
+    /// other), it's handed back untouched.
+    pub fn push_front(
+        &self,
+        node: &'static Node<T>,
+        _cs: CriticalSection<'_>,
+    ) -> Result<(), &'static Node<T>> {
+        if node.linked.get() {
+            return Err(node);
+        }
+        node.next.set(self.head.get());
+        node.linked.set(true);
+        self.head.set(Some(node));
+        Ok(())
+    }
+
+    pub fn pop_front(&self, _cs: CriticalSection<'_>) -> Option<&'static Node<T>> {
+        let node = self.head.get()?;
+        self.head.set(node.next.take());
+        node.linked.set(false);
+        Some(node)
+    }
+
+    #[must_use]
+    pub fn front(&self, _cs: CriticalSection<'_>) -> Option<&'static Node<T>> {
+        self.head.get()
+    }
+
+    /// Unlinks `node` from wherever it is in this list. O(n): there's no prev
+    /// link to jump straight there with. Returns whether it was here.
+    pub fn remove(&self, node: &'static Node<T>, _cs: CriticalSection<'_>) -> bool {
+        let mut link = &self.head;
+        while let Some(current) = link.get() {
+            if core::ptr::eq(current, node) {
+                link.set(node.next.take());
+                node.linked.set(false);
+                return true;
+            }
+            link = &current.next;
+        }
+        false
+    }
+
+    #[must_use]
+    pub fn is_empty(&self, _cs: CriticalSection<'_>) -> bool {
+        self.head.get().is_none()
+    }
+
+    /// Walks the list. The iterator can't outlive the critical section, so
+    /// nothing can relink the nodes out from under it.
+    pub fn iter<'cs>(&self, _cs: CriticalSection<'cs>) -> Iter<'cs, T> {
+        Iter {
+            next: self.head.get(),
+            _cs: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'cs, T: 'static> {
+    next: Option<&'static Node<T>>,
+    _cs: PhantomData<CriticalSection<'cs>>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = &'static Node<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next?;
+        self.next = node.next.get();
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{List, Node};
+
+    fn values(list: &List<u32>) -> Vec<u32> {
+        critical_section::with(|cs| list.iter(cs).map(|node| *node.value()).collect())
+    }
+
+    #[test]
+    fn basics() {
+        static LIST: List<u32> = List::new();
+        static A: Node<u32> = Node::new(1);
+        static B: Node<u32> = Node::new(2);
+        static C: Node<u32> = Node::new(3);
+
+        critical_section::with(|cs| {
+            assert!(LIST.is_empty(cs));
+            for node in [&A, &B, &C] {
+                assert!(LIST.push_front(node, cs).is_ok());
+            }
+            assert_eq!(LIST.front(cs).map(Node::value), Some(&3));
+        });
+        assert_eq!(values(&LIST), [3, 2, 1]);
+
+        critical_section::with(|cs| {
+            assert_eq!(LIST.pop_front(cs).map(Node::value), Some(&3));
+            assert!(!C.is_linked(cs));
+            assert!(B.is_linked(cs));
+        });
+        assert_eq!(values(&LIST), [2, 1]);
+    }
+
+    #[test]
+    fn one_list_at_a_time() {
+        static ONE: List<u32> = List::new();
+        static OTHER: List<u32> = List::new();
+        static NODE: Node<u32> = Node::new(7);
+
+        critical_section::with(|cs| {
+            assert!(ONE.push_front(&NODE, cs).is_ok());
+            // linking it twice would tie the list in a knot
+            assert!(ONE.push_front(&NODE, cs).is_err());
+            assert!(OTHER.push_front(&NODE, cs).is_err());
+
+            assert!(!OTHER.remove(&NODE, cs));
+            assert!(ONE.remove(&NODE, cs));
+            assert!(OTHER.push_front(&NODE, cs).is_ok());
+        });
+        assert!(values(&ONE).is_empty());
+        assert_eq!(values(&OTHER), [7]);
+    }
+
+    #[test]
+    fn remove_from_the_middle() {
+        static LIST: List<u32> = List::new();
+        static NODES: [Node<u32>; 4] = [Node::new(0), Node::new(1), Node::new(2), Node::new(3)];
+
+        critical_section::with(|cs| {
+            for node in &NODES {
+                assert!(LIST.push_front(node, cs).is_ok());
+            }
+            assert!(LIST.remove(&NODES[1], cs));
+            assert!(LIST.remove(&NODES[3], cs));
+        });
+        assert_eq!(values(&LIST), [2, 0]);
+    }
+
+    #[test]
+    fn from_another_thread() {
+        static LIST: List<u32> = List::new();
+        static NODE: Node<u32> = Node::new(9);
+
+        std::thread::spawn(|| {
+            critical_section::with(|cs| assert!(LIST.push_front(&NODE, cs).is_ok()))
+        })
+        .join()
+        .unwrap();
+        critical_section::with(|cs| assert_eq!(LIST.pop_front(cs).map(Node::value), Some(&9)));
+    }
+}
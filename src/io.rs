@@ -0,0 +1,185 @@
+// Saving and loading lists too big to hold twice. Serializing a whole list
+// through serde already streams (serialize_seq takes the elements one at a
+// time), but reading a JSON array back means the format has to get to the `]`
+// before anything's usable, and a half-written file is no file at all.
+//
+// JSON Lines avoids both: one element per line, written as it's reached and
+// read back as it arrives, so memory stays at one element however long the list
+// is. A file cut short loses its last line, not everything.
+//
+//     io::write_jsonl(queue.iter(), BufWriter::new(File::create(path)?))?;
+//     let queue = io::read_jsonl_into_queue(BufReader::new(File::open(path)?))?;
+//
+// `SerializeIter` is the same idea for any other serde format: it serializes an
+// iterator as a sequence without collecting it first, so a list can be drained
+// straight into the output, freeing each node as its element is written.
+
+use core::cell::Cell;
+use std::io::{Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer};
+use serde_json::de::IoRead;
+use serde_json::{Deserializer, Error, StreamDeserializer};
+
+use crate::fifth;
+
+/// Writes each element as a line of JSON, in order. Doesn't buffer anything
+/// itself: give it a `BufWriter` for files.
+pub fn write_jsonl<T: Serialize>(
+    elems: impl IntoIterator<Item = T>,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    for elem in elems {
+        serde_json::to_writer(&mut writer, &elem)?;
+        writer.write_all(b"\n").map_err(Error::io)?;
+    }
+    writer.flush().map_err(Error::io)
+}
+
+/// Reads JSON Lines back an element at a time, as an iterator. Stops at the
+/// first error, after handing it over.
+pub fn read_jsonl<T: DeserializeOwned, R: Read>(reader: R) -> ReadJsonl<R, T> {
+    ReadJsonl {
+        elems: Deserializer::from_reader(reader).into_iter(),
+        failed: false,
+    }
+}
+
+pub struct ReadJsonl<R: Read, T> {
+    elems: StreamDeserializer<'static, IoRead<R>, T>,
+    failed: bool,
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for ReadJsonl<R, T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let elem = self.elems.next()?;
+        // the stream can't find its footing after a bad line, so don't let it try
+        self.failed = elem.is_err();
+        Some(elem)
+    }
+}
+
+/// Loads JSON Lines into a queue, pushing each element as it's read.
+pub fn read_jsonl_into_queue<T: DeserializeOwned>(
+    reader: impl Read,
+) -> Result<fifth::List<T>, Error> {
+    let mut queue = fifth::List::new();
+    for elem in read_jsonl(reader) {
+        queue.push(elem?);
+    }
+    Ok(queue)
+}
+
+/// Serializes an iterator as a sequence, pulling one element at a time. It can
+/// only be serialized once: serde's `serialize` takes `&self`, so the iterator
+/// is taken out on the first go, and a second one is an error. That includes
+/// formats that go over their input twice, like `bincode::serialize`, which
+/// sizes it up first; write straight out with `bincode::serialize_into`.
+///
+/// The sequence's length is given up front if the iterator knows it exactly,
+/// which formats like bincode need.
+pub struct SerializeIter<I>(Cell<Option<I>>);
+
+impl<I> SerializeIter<I> {
+    #[must_use]
+    pub const fn new(iter: I) -> Self {
+        Self(Cell::new(Some(iter)))
+    }
+}
+
+impl<I> Serialize for SerializeIter<I>
+where
+    I: Iterator,
+    I::Item: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let iter = self
+            .0
+            .take()
+            .ok_or_else(|| serde::ser::Error::custom("SerializeIter serialized twice"))?;
+        serializer.collect_seq(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_jsonl, read_jsonl_into_queue, write_jsonl, SerializeIter};
+    use crate::fifth::List;
+    use std::io::{self, Read};
+
+    fn filled(n: u32) -> List<u32> {
+        let mut list = List::new();
+        for i in 0..n {
+            list.push(i);
+        }
+        list
+    }
+
+    #[test]
+    fn jsonl_round_trip() {
+        let list = filled(1000);
+        let mut file = Vec::new();
+        write_jsonl(list.iter(), &mut file).unwrap();
+        assert!(file.starts_with(b"0\n1\n2\n"));
+
+        let back: List<u32> = read_jsonl_into_queue(&file[..]).unwrap();
+        assert!(back.iter().eq(list.iter()));
+    }
+
+    // A reader that hands over a byte at a time: nothing needs the whole file
+    // in hand to make progress.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((&first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn reads_as_it_goes() {
+        let file = b"[1,\"a\"]\n[2,\"b\"]\n[3,\"c\"]\n";
+        let mut reader = Trickle(file);
+        let mut elems = read_jsonl::<(u32, String), _>(&mut reader);
+        assert_eq!(elems.next().unwrap().unwrap(), (1, "a".to_string()));
+        // the first element came out without reading past its own line
+        drop(elems);
+        assert!(reader.0.starts_with(b"\n[2,"));
+    }
+
+    #[test]
+    fn stops_at_a_bad_line() {
+        let file = b"1\n2\nthree\n4\n";
+        let elems: Vec<_> = read_jsonl::<u32, _>(&file[..]).collect();
+        assert_eq!(elems.len(), 3);
+        assert!(elems[..2].iter().all(Result::is_ok));
+        assert!(elems[2].is_err());
+        assert!(read_jsonl_into_queue::<u32>(&file[..]).is_err());
+    }
+
+    #[test]
+    fn serialize_iter() {
+        // drained straight into the output, no Vec in between
+        let json = serde_json::to_string(&SerializeIter::new(filled(4).into_iter())).unwrap();
+        assert_eq!(json, "[0,1,2,3]");
+
+        let mut bytes = Vec::new();
+        bincode::serialize_into(&mut bytes, &SerializeIter::new(filled(3).into_iter())).unwrap();
+        assert_eq!(bincode::deserialize::<Vec<u32>>(&bytes).unwrap(), [0, 1, 2]);
+
+        let once = SerializeIter::new(0..2);
+        assert!(serde_json::to_string(&once).is_ok());
+        assert!(serde_json::to_string(&once).is_err());
+    }
+}
@@ -1,5 +1,63 @@
-mod first;
-mod second;
-mod third;
-mod fourth;
-mod fifth;
+// The lists only need a heap, not an OS: with the default `std` feature off, the
+// crate is no_std + alloc. Tests always get std.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod fifth;
+pub mod first;
+pub mod fourth;
+pub mod generic;
+#[cfg(feature = "std")]
+pub mod lru;
+#[cfg(target_has_atomic = "ptr")]
+pub mod mpsc;
+pub mod second;
+pub mod segmented;
+pub mod sentinel;
+#[cfg(feature = "std")]
+pub mod shared;
+pub mod silly1;
+pub mod silly2;
+pub mod small;
+#[cfg(feature = "stats")]
+pub mod stats;
+pub mod third;
+mod trace;
+#[cfg(target_has_atomic = "ptr")]
+pub mod work_stealing;
+
+#[cfg(feature = "arena")]
+pub mod arena;
+#[cfg(feature = "async")]
+pub mod async_queue;
+#[cfg(feature = "bumpalo")]
+pub mod bump;
+#[cfg(feature = "checkpoint")]
+pub mod checkpoint;
+#[cfg(feature = "embedded")]
+pub mod embedded;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod ghost;
+#[cfg(feature = "epoch")]
+pub mod harris;
+pub mod inline;
+#[cfg(feature = "intrusive")]
+pub mod intrusive;
+#[cfg(feature = "jsonl")]
+pub mod io;
+#[cfg(feature = "pedagogy")]
+pub mod pedagogy;
+#[cfg(feature = "pyo3")]
+pub mod python;
+#[cfg(feature = "epoch")]
+pub mod read_mostly;
+#[cfg(feature = "async")]
+pub mod reorder;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+#[cfg(feature = "parking_lot")]
+pub mod two_lock;
+#[cfg(feature = "viz")]
+mod viz;
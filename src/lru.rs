@@ -0,0 +1,126 @@
+// A least-recently-used cache: the textbook job for a doubly-linked list. The
+// keys sit in fourth.rs's deque, most recently used at the front, and the map
+// keeps a handle on each key's node, so a hit can relink it to the front in
+// O(1) — no walking the deque to find it — and eviction is a pop off the back.
+//
+// The values live in the map rather than the deque: fourth's nodes are behind
+// RefCells, so `get` couldn't hand out a plain `&V` from one.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::fourth::{Handle, List};
+
+pub struct LruCache<K, V> {
+    entries: HashMap<K, (V, Handle<K>)>,
+    recency: List<K>,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// # Panics
+    ///
+    /// If `capacity` is 0: a cache that can hold nothing would evict everything
+    /// as it went in.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "an LRU cache needs room for at least one entry"
+        );
+        Self {
+            entries: HashMap::with_capacity(capacity),
+            recency: List::new(),
+            capacity,
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Inserts or updates `key`, making it the most recently used. If that
+    /// takes the cache over capacity, the least recently used entry is evicted
+    /// and handed back.
+    pub fn push(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Some((old, handle)) = self.entries.get_mut(&key) {
+            *old = value;
+            self.recency.move_to_front(handle);
+            return None;
+        }
+        self.recency.push_front(key.clone());
+        let handle = self.recency.front_handle().expect("just pushed");
+        self.entries.insert(key, (value, handle));
+        if self.entries.len() > self.capacity {
+            self.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Looks `key` up, and counts it as a use.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let (value, handle) = self.entries.get(key)?;
+        self.recency.move_to_front(handle);
+        Some(value)
+    }
+
+    /// Looks `key` up without counting it as a use.
+    #[must_use]
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    /// Removes the least recently used entry.
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        let key = self.recency.pop_back()?;
+        let (value, _) = self.entries.remove(&key).expect("every key is in both");
+        Some((key, value))
+    }
+
+    /// The keys, most recently used first. A copy, since there's no borrowing
+    /// iterator through the deque's RefCells: O(n).
+    #[must_use]
+    pub fn keys(&self) -> crate::fourth::IntoIter<K> {
+        self.recency.clone().into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LruCache;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        assert_eq!(cache.push("a", 1), None);
+        assert_eq!(cache.push("b", 2), None);
+        // touching a leaves b the oldest
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.push("c", 3), Some(("b", 2)));
+        assert_eq!(cache.keys().collect::<Vec<_>>(), ["c", "a"]);
+
+        // updating counts as a use, and evicts nothing
+        assert_eq!(cache.push("a", 10), None);
+        assert_eq!(cache.peek(&"c"), Some(&3));
+        assert_eq!(cache.push("d", 4), Some(("c", 3)));
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.len(), 2);
+
+        assert_eq!(cache.pop(), Some(("a", 10)));
+        assert_eq!(cache.pop(), Some(("d", 4)));
+        assert_eq!(cache.pop(), None);
+        assert!(cache.is_empty());
+    }
+}
@@ -1 +1,333 @@
-fn main() {}
+// A little REPL for poking at the lists by hand. Pick an implementation, push and
+// pop things, and watch the ASCII drawing change after every step.
+//
+//     $ cargo run --features viz
+//     stack> push 5
+//     HEAD -> [5] -> ∅
+//
+// Or replay a file of commands, one every half second, with a diff of the
+// drawing after each one — handy for demoing push_front/pop_back live:
+//
+//     $ cargo run --features viz -- --script demo.txt --delay 1000
+
+use std::collections::LinkedList;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::mem;
+use std::thread;
+use std::time::Duration;
+
+use too_many_linked_lists::{fifth, fourth, second, third};
+
+const HELP: &str = "\
+lists:    use stack | persistent | deque | unsafe
+commands: push N, pop (the deque also has push_back N, pop_back)
+          iter, viz, help, quit";
+
+enum Demo {
+    Stack(second::List<i32>),
+    Persistent(third::List<i32>),
+    Deque(fourth::List<i32>),
+    Unsafe(fifth::List<i32>),
+}
+
+impl Demo {
+    fn new(name: &str) -> Option<Self> {
+        match name {
+            "stack" => Some(Self::Stack(second::List::new())),
+            "persistent" => Some(Self::Persistent(third::List::new())),
+            "deque" => Some(Self::Deque(fourth::List::new())),
+            "unsafe" => Some(Self::Unsafe(fifth::List::new())),
+            _ => None,
+        }
+    }
+
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Stack(_) => "stack",
+            Self::Persistent(_) => "persistent",
+            Self::Deque(_) => "deque",
+            Self::Unsafe(_) => "unsafe",
+        }
+    }
+
+    fn push(&mut self, elem: i32) {
+        match self {
+            Self::Stack(list) => list.push_front(elem),
+            // persistent lists don't change; we just move on to the new one
+            Self::Persistent(list) => *list = list.prepend(elem),
+            Self::Deque(list) => list.push_front(elem),
+            Self::Unsafe(list) => list.push(elem),
+        }
+    }
+
+    fn pop(&mut self) -> Option<i32> {
+        match self {
+            Self::Stack(list) => list.pop_front(),
+            Self::Persistent(list) => {
+                let head = list.head().copied();
+                *list = list.tail();
+                head
+            }
+            Self::Deque(list) => list.pop_front(),
+            Self::Unsafe(list) => list.pop(),
+        }
+    }
+
+    fn elems(&mut self) -> Vec<i32> {
+        match self {
+            Self::Stack(list) => list.iter().copied().collect(),
+            Self::Persistent(list) => {
+                let mut elems = Vec::new();
+                let mut rest = list.tail();
+                elems.extend(list.head());
+                while let Some(&elem) = rest.head() {
+                    elems.push(elem);
+                    rest = rest.tail();
+                }
+                elems
+            }
+            // the deque can't lend out plain references, so take a detour through std
+            Self::Deque(list) => {
+                let std_list: LinkedList<i32> = mem::take(list).into();
+                let elems = std_list.iter().copied().collect();
+                *list = std_list.into();
+                elems
+            }
+            Self::Unsafe(list) => list.iter().copied().collect(),
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Self::Stack(list) => list.render_ascii(),
+            Self::Persistent(list) => list.render_ascii(),
+            Self::Deque(list) => list.render_ascii(),
+            Self::Unsafe(list) => list.render_ascii(),
+        }
+    }
+
+    // Runs one command, returning what to print (without the drawing).
+    fn run(&mut self, command: &str) -> Result<String, String> {
+        let mut words = command.split_whitespace();
+        let (Some(op), arg) = (words.next(), words.next()) else {
+            return Ok(String::new());
+        };
+        let elem = || -> Result<i32, String> {
+            arg.ok_or_else(|| format!("{op} needs a number"))?
+                .parse()
+                .map_err(|_| format!("{op} needs a number"))
+        };
+        let popped = |elem: Option<i32>| elem.map_or_else(|| "empty".into(), |e| e.to_string());
+
+        match (op, self) {
+            ("push", demo) => demo.push(elem()?),
+            ("pop", demo) => return Ok(popped(demo.pop())),
+            ("push_back", Self::Deque(list)) => list.push_back(elem()?),
+            ("pop_back", Self::Deque(list)) => return Ok(popped(list.pop_back())),
+            ("iter", demo) => return Ok(format!("{:?}", demo.elems())),
+            ("viz", _) => {}
+            (op, demo) => return Err(format!("{} doesn't know `{op}`", demo.name())),
+        }
+        Ok(String::new())
+    }
+}
+
+// `use` swaps out the whole demo, so it lives out here rather than in Demo::run.
+fn execute(demo: &mut Demo, line: &str) -> Result<String, String> {
+    let mut words = line.split_whitespace();
+    if words.next() == Some("use") {
+        *demo = words
+            .next()
+            .and_then(Demo::new)
+            .ok_or("pick one of: stack, persistent, deque, unsafe")?;
+        return Ok(String::new());
+    }
+    demo.run(line)
+}
+
+fn repl(input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+    let mut demo = Demo::Stack(second::List::new());
+    writeln!(out, "{HELP}")?;
+    write!(out, "{}> ", demo.name())?;
+    out.flush()?;
+
+    for line in input.lines() {
+        let line = line?;
+        match line.split_whitespace().next() {
+            Some("quit" | "exit") => break,
+            Some("help") => writeln!(out, "{HELP}")?,
+            _ => match execute(&mut demo, &line) {
+                Ok(message) => {
+                    if !message.is_empty() {
+                        writeln!(out, "{message}")?;
+                    }
+                    write!(out, "{}", demo.render())?;
+                }
+                Err(message) => writeln!(out, "{message}")?,
+            },
+        }
+        write!(out, "{}> ", demo.name())?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+// Replays a script, pausing `delay` between commands. Instead of redrawing the
+// whole list, each step prints a line diff: `-` for rows that went away, `+` for
+// rows that appeared. Blank lines and `#` comments are skipped.
+fn script(input: impl BufRead, mut out: impl Write, delay: Duration) -> io::Result<()> {
+    let mut demo = Demo::Stack(second::List::new());
+
+    for line in input.lines() {
+        let line = line?;
+        let command = line.trim();
+        if command.is_empty() || command.starts_with('#') {
+            continue;
+        }
+
+        writeln!(out, "{}> {command}", demo.name())?;
+        let before = demo.render();
+        match execute(&mut demo, command) {
+            Ok(message) => {
+                if !message.is_empty() {
+                    writeln!(out, "{message}")?;
+                }
+                write!(out, "{}", diff(&before, &demo.render()))?;
+            }
+            Err(message) => writeln!(out, "{message}")?,
+        }
+        out.flush()?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+// Our drawings are a row or two, so a row-by-row comparison is all the diff we need.
+fn diff(before: &str, after: &str) -> String {
+    use std::fmt::Write as _;
+
+    let mut diff = String::new();
+    for row in before
+        .lines()
+        .filter(|row| !after.lines().any(|r| r == *row))
+    {
+        let _ = writeln!(diff, "- {row}");
+    }
+    for row in after.lines() {
+        let sign = if before.lines().any(|r| r == row) {
+            ' '
+        } else {
+            '+'
+        };
+        let _ = writeln!(diff, "{sign} {row}");
+    }
+    diff
+}
+
+fn main() -> io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let mut script_path = None;
+    let mut delay = Duration::from_millis(500);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--script", Some(path)) => script_path = Some(path),
+            ("--delay", Some(ms)) => {
+                let ms = ms.parse().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "--delay takes milliseconds")
+                })?;
+                delay = Duration::from_millis(ms);
+            }
+            _ => {
+                eprintln!("usage: too-many-linked-lists [--script FILE [--delay MS]]");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    match script_path {
+        Some(path) => script(
+            BufReader::new(File::open(path)?),
+            io::stdout().lock(),
+            delay,
+        ),
+        None => repl(io::stdin().lock(), io::stdout().lock()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{repl, script};
+    use std::time::Duration;
+
+    fn session(input: &str) -> String {
+        let mut out = Vec::new();
+        repl(input.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn stack() {
+        let out = session("push 1\npush 2\npop\niter\nquit\n");
+        assert!(out.contains("HEAD -> [2] -> [1] -> ∅\n"));
+        assert!(out.contains("2\nHEAD -> [1] -> ∅\n"));
+        assert!(out.contains("[1]\n"));
+    }
+
+    #[test]
+    fn switching_lists() {
+        let out = session("use deque\npush 1\npush_back 2\niter\npop_back\nuse unsafe\npush 3\n");
+        assert!(out.contains("deque> "));
+        assert!(out.contains("[1, 2]\n"));
+        assert!(out.contains("unsafe> "));
+        assert!(out.contains("HEAD -> [3] -> ∅\n"));
+    }
+
+    #[test]
+    fn persistent() {
+        let out = session("use persistent\npush 1\npush 2\niter\npop\n");
+        assert!(out.contains("[2, 1]\n"));
+        assert!(out.contains("2\nHEAD -> [1]"));
+    }
+
+    #[test]
+    fn mistakes() {
+        let out = session("push\npush_back 1\nuse queue\n");
+        assert!(out.contains("push needs a number"));
+        assert!(out.contains("stack doesn't know `push_back`"));
+        assert!(out.contains("pick one of"));
+    }
+
+    #[test]
+    fn script_diffs() {
+        let input = "# a comment\nuse deque\n\npush 1\npush_back 2\npop\n";
+        let mut out = Vec::new();
+        script(input.as_bytes(), &mut out, Duration::ZERO).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert_eq!(
+            out,
+            "\
+stack> use deque
+  HEAD -> ∅
++    ∅ <- TAIL
+deque> push 1
+- HEAD -> ∅
+-    ∅ <- TAIL
++ HEAD -> [1] -> ∅
++    ∅ <- [1] <- TAIL
+deque> push_back 2
+- HEAD -> [1] -> ∅
+-    ∅ <- [1] <- TAIL
++ HEAD -> [1] -> [2] -> ∅
++    ∅ <- [1] <- [2] <- TAIL
+deque> pop
+1
+- HEAD -> [1] -> [2] -> ∅
+-    ∅ <- [1] <- [2] <- TAIL
++ HEAD -> [2] -> ∅
++    ∅ <- [2] <- TAIL
+"
+        );
+    }
+}
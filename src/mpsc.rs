@@ -0,0 +1,420 @@
+// Dmitry Vyukov's intrusive multi-producer, single-consumer queue: the shape
+// async executors use for their run queues. Any number of threads push, one pops,
+// and nobody ever loops on a compare-and-swap.
+//
+// Producers push with a single atomic swap on `head`: "I'm the newest now, who
+// was before me?" Then they link that predecessor's `next` to themselves. The
+// consumer pops from `tail`, only ever walking forward along `next`s. There's a
+// catch hiding between those two steps of a push: once the swap's done, the node
+// is the newest, but it isn't reachable from the consumer's end until the link
+// is stored. A pop that lands in that gap sees a chain that stops short of
+// `head`, and says so (`Pop::Inconsistent`) rather than waiting: try again in a
+// moment, it's about to be there.
+//
+// The stub is a node with no value, always somewhere in the queue, so there's
+// never a truly empty chain to special-case. When the consumer would take the
+// last real node, it pushes the stub back in behind it first. The stub is boxed,
+// so the queue can move without leaving pointers into its old self behind. It's
+// boxed the first time the queue is split, not in `new`, which keeps `new` a
+// `const fn`: a queue can sit in a `static` (behind a Mutex, to get at `split`).
+//
+// Intrusive means the queue never allocates a node: you own them, it links them
+// (that's the `&'a Node<T>` everywhere). A node can only be in one queue at a
+// time; `queued` is how push notices.
+
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::ptr;
+
+#[cfg(not(loom))]
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+struct Link {
+    next: AtomicPtr<Link>,
+}
+
+impl Link {
+    fn new() -> Self {
+        Self {
+            next: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+}
+
+// `link` has to come first: the queue only sees Links, and turns the non-stub
+// ones back into Nodes with a cast.
+#[repr(C)]
+pub struct Node<T> {
+    link: Link,
+    queued: AtomicBool,
+    value: T,
+}
+
+impl<T> Node<T> {
+    #[cfg(not(loom))]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            link: Link {
+                next: AtomicPtr::new(ptr::null_mut()),
+            },
+            queued: AtomicBool::new(false),
+            value,
+        }
+    }
+
+    // loom's atomics can't be made in a const context
+    #[cfg(loom)]
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self {
+            link: Link::new(),
+            queued: AtomicBool::new(false),
+            value,
+        }
+    }
+
+    #[must_use]
+    pub const fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Whether the node's in a queue right now.
+    #[must_use]
+    pub fn is_queued(&self) -> bool {
+        self.queued.load(Ordering::Acquire)
+    }
+}
+
+pub struct Queue<'a, T> {
+    // the newest link; producers swap themselves in here
+    head: AtomicPtr<Link>,
+    // the oldest link, which only the consumer touches
+    tail: UnsafeCell<*mut Link>,
+    // boxed by the first split(), and freed by Drop; null until then, and so are
+    // head and tail
+    stub: *mut Link,
+    _nodes: core::marker::PhantomData<&'a Node<T>>,
+}
+
+// Producers on any thread only go through `head` and the nodes' atomics. `tail`
+// belongs to the single Consumer, which split() makes sure there's only one of.
+unsafe impl<T: Sync> Sync for Queue<'_, T> {}
+unsafe impl<T: Sync> Send for Queue<'_, T> {}
+
+/// What a pop found.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Pop<'a, T> {
+    Data(&'a T),
+    Empty,
+    /// A push is halfway done. Try again shortly.
+    Inconsistent,
+}
+
+impl<'a, T> Queue<'a, T> {
+    /// Doesn't allocate: the stub waits for the first `split`.
+    #[cfg(not(loom))]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+            tail: UnsafeCell::new(ptr::null_mut()),
+            stub: ptr::null_mut(),
+            _nodes: core::marker::PhantomData,
+        }
+    }
+
+    #[cfg(loom)]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+            tail: UnsafeCell::new(ptr::null_mut()),
+            stub: ptr::null_mut(),
+            _nodes: core::marker::PhantomData,
+        }
+    }
+
+    /// Hands out the two ends. Producers are `Copy` and can go to as many threads
+    /// as you like; there's exactly one Consumer, since this borrows the queue
+    /// mutably.
+    pub fn split(&mut self) -> (Producer<'_, 'a, T>, Consumer<'_, 'a, T>) {
+        if self.stub.is_null() {
+            // `&mut self`, so nobody's pushing yet
+            let stub = Box::into_raw(Box::new(Link::new()));
+            self.stub = stub;
+            self.head = AtomicPtr::new(stub);
+            self.tail = UnsafeCell::new(stub);
+        }
+        (Producer(self), Consumer(self))
+    }
+
+    fn push_link(&self, link: *mut Link) {
+        unsafe {
+            (*link).next.store(ptr::null_mut(), Ordering::Relaxed);
+            let prev = self.head.swap(link, Ordering::AcqRel);
+            // the gap: `link` is the newest, but not reachable from tail until this
+            (*prev).next.store(link, Ordering::Release);
+        }
+    }
+
+    // Only ever called by the one Consumer (or by Drop, with nobody else left).
+    unsafe fn pop(&self) -> Pop<'a, T> {
+        let tail_slot = self.tail.get();
+        let mut tail = *tail_slot;
+        let mut next = (*tail).next.load(Ordering::Acquire);
+
+        // the stub's at the front: skip over it, if there's anything behind it
+        if tail == self.stub {
+            if next.is_null() {
+                return Pop::Empty;
+            }
+            *tail_slot = next;
+            tail = next;
+            next = (*next).next.load(Ordering::Acquire);
+        }
+
+        if !next.is_null() {
+            *tail_slot = next;
+            return Pop::Data(self.take(tail));
+        }
+
+        // `tail` is the last linked node. If it's not the newest, someone's in
+        // the gap, with a node we can't reach yet.
+        if tail != self.head.load(Ordering::Acquire) {
+            return Pop::Inconsistent;
+        }
+        // It's the only node. Put the stub behind it, so taking it doesn't leave
+        // the chain with nothing in it.
+        self.push_link(self.stub);
+        next = (*tail).next.load(Ordering::Acquire);
+        if next.is_null() {
+            // a producer got in between our head check and our stub push
+            return Pop::Inconsistent;
+        }
+        *tail_slot = next;
+        Pop::Data(self.take(tail))
+    }
+
+    // `link` has just left the queue, and is a Node (the stub never gets here).
+    unsafe fn take(&self, link: *mut Link) -> &'a T {
+        let node = &*link.cast::<Node<T>>();
+        node.queued.store(false, Ordering::Release);
+        &node.value
+    }
+}
+
+impl<T> Default for Queue<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Pops everything that's left, so the nodes aren't stuck thinking they're
+// queued somewhere that no longer exists.
+impl<T> Drop for Queue<'_, T> {
+    fn drop(&mut self) {
+        if self.stub.is_null() {
+            // never split, so nothing was ever pushed
+            return;
+        }
+        // `&mut self`: no producers left, so no pushes can be halfway done
+        while let Pop::Data(_) = unsafe { self.pop() } {}
+        unsafe { drop(Box::from_raw(self.stub)) };
+    }
+}
+
+pub struct Producer<'q, 'a, T>(&'q Queue<'a, T>);
+
+impl<T> Clone for Producer<'_, '_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Producer<'_, '_, T> {}
+
+impl<'a, T> Producer<'_, 'a, T> {
+    /// Queues `node`: one atomic swap and a store, whatever the other producers
+    /// are up to. A node that's already queued (here or anywhere) is handed back.
+    pub fn push(&self, node: &'a Node<T>) -> Result<(), &'a Node<T>> {
+        if node.queued.swap(true, Ordering::AcqRel) {
+            return Err(node);
+        }
+        self.0.push_link(ptr::from_ref(&node.link).cast_mut());
+        Ok(())
+    }
+}
+
+pub struct Consumer<'q, 'a, T>(&'q Queue<'a, T>);
+
+impl<'a, T> Consumer<'_, 'a, T> {
+    /// Takes the oldest value, if there is one and its push has finished.
+    pub fn pop(&mut self) -> Pop<'a, T> {
+        unsafe { self.0.pop() }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::{Node, Pop, Queue};
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let nodes = [Node::new(1), Node::new(2), Node::new(3)];
+        let mut queue = Queue::new();
+        let (producer, mut consumer) = queue.split();
+        assert_eq!(consumer.pop(), Pop::Empty);
+
+        for node in &nodes {
+            assert!(producer.push(node).is_ok());
+        }
+        assert!(nodes[0].is_queued());
+        // already in there
+        assert!(producer.push(&nodes[0]).is_err());
+
+        assert_eq!(consumer.pop(), Pop::Data(&1));
+        assert!(!nodes[0].is_queued());
+        assert_eq!(consumer.pop(), Pop::Data(&2));
+        // and back in, behind 3
+        assert!(producer.push(&nodes[0]).is_ok());
+        assert_eq!(consumer.pop(), Pop::Data(&3));
+        assert_eq!(consumer.pop(), Pop::Data(&1));
+        assert_eq!(consumer.pop(), Pop::Empty);
+    }
+
+    #[test]
+    fn in_a_static() {
+        use std::sync::Mutex;
+
+        static NODES: [Node<u32>; 2] = [Node::new(1), Node::new(2)];
+        static QUEUE: Mutex<Queue<'static, u32>> = Mutex::new(Queue::new());
+
+        let mut queue = QUEUE.lock().unwrap();
+        let (producer, mut consumer) = queue.split();
+        for node in &NODES {
+            assert!(producer.push(node).is_ok());
+        }
+        assert_eq!(consumer.pop(), Pop::Data(&1));
+        assert_eq!(consumer.pop(), Pop::Data(&2));
+        // split again: same stub, same queue
+        let (producer, mut consumer) = queue.split();
+        assert!(producer.push(&NODES[0]).is_ok());
+        assert_eq!(consumer.pop(), Pop::Data(&1));
+        assert_eq!(consumer.pop(), Pop::Empty);
+    }
+
+    #[test]
+    fn drop_unqueues_the_leftovers() {
+        let nodes = [Node::new(1), Node::new(2)];
+        {
+            let mut queue = Queue::new();
+            let (producer, _) = queue.split();
+            for node in &nodes {
+                assert!(producer.push(node).is_ok());
+            }
+        }
+        assert!(nodes.iter().all(|node| !node.is_queued()));
+    }
+
+    #[test]
+    fn many_producers() {
+        const PER_THREAD: usize = 1000;
+        let nodes: Vec<Vec<Node<(usize, usize)>>> = (0..4)
+            .map(|thread| (0..PER_THREAD).map(|i| Node::new((thread, i))).collect())
+            .collect();
+        let mut queue = Queue::new();
+        let (producer, mut consumer) = queue.split();
+
+        thread::scope(|scope| {
+            for mine in &nodes {
+                scope.spawn(move || {
+                    for node in mine {
+                        assert!(producer.push(node).is_ok());
+                    }
+                });
+            }
+
+            // each producer's values come out in the order it pushed them
+            let mut next_expected = [0; 4];
+            let mut popped = 0;
+            while popped < 4 * PER_THREAD {
+                match consumer.pop() {
+                    Pop::Data(&(thread, i)) => {
+                        assert_eq!(i, next_expected[thread]);
+                        next_expected[thread] += 1;
+                        popped += 1;
+                    }
+                    Pop::Empty | Pop::Inconsistent => thread::yield_now(),
+                }
+            }
+            assert_eq!(next_expected, [PER_THREAD; 4]);
+        });
+        assert_eq!(consumer.pop(), Pop::Empty);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::{Node, Pop, Queue};
+    use loom::thread;
+
+    // Loom threads have to be 'static, so the nodes and queue are leaked for the
+    // length of each model run.
+    fn leak<T>(value: T) -> &'static mut T {
+        Box::leak(Box::new(value))
+    }
+
+    #[test]
+    fn two_producers() {
+        loom::model(|| {
+            let a = leak(Node::new(1));
+            let b = leak(Node::new(2));
+            let (producer, mut consumer) = leak(Queue::new()).split();
+
+            let handles =
+                [&*a, &*b].map(|node| thread::spawn(move || assert!(producer.push(node).is_ok())));
+
+            let mut seen = Vec::new();
+            while seen.len() < 2 {
+                match consumer.pop() {
+                    Pop::Data(&value) => seen.push(value),
+                    Pop::Empty | Pop::Inconsistent => thread::yield_now(),
+                }
+            }
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            seen.sort_unstable();
+            assert_eq!(seen, [1, 2]);
+            assert_eq!(consumer.pop(), Pop::Empty);
+        });
+    }
+
+    #[test]
+    fn pop_races_the_stub_push() {
+        // one node in already, so the consumer goes for the "take the last
+        // node, push the stub back" path while a producer pushes
+        loom::model(|| {
+            let first = leak(Node::new(1));
+            let second = leak(Node::new(2));
+            let (producer, mut consumer) = leak(Queue::new()).split();
+            assert!(producer.push(first).is_ok());
+
+            let handle = thread::spawn(move || assert!(producer.push(second).is_ok()));
+
+            let mut seen = Vec::new();
+            while seen.len() < 2 {
+                match consumer.pop() {
+                    Pop::Data(&value) => seen.push(value),
+                    Pop::Empty | Pop::Inconsistent => thread::yield_now(),
+                }
+            }
+            handle.join().unwrap();
+            assert_eq!(seen, [1, 2]);
+        });
+    }
+}
@@ -0,0 +1,297 @@
+// Python bindings, so the lists can be poked at from a REPL:
+//
+//     >>> from too_many_linked_lists import Deque
+//     >>> d = Deque()
+//     >>> d.push(1); d.push_front(0)
+//     >>> list(d), len(d)
+//     ([0, 1], 2)
+//
+// Build the extension with something like maturin (`maturin develop --features pyo3`).
+//
+// Python hands us arbitrary objects, so every list stores PyObjects, and `len` is whatever the
+// list's own is: O(1) for the queue, a walk for the deque, which doesn't keep count.
+
+use std::hash::{Hash, Hasher};
+
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+
+use crate::lru::LruCache;
+use crate::{fifth, fourth};
+
+/// A double-ended queue backed by the Rc<RefCell> list from fourth.rs.
+///
+/// `push`/`pop` treat it as a queue (push to the back, pop from the front).
+// Rc isn't Send, so Python must only ever touch this from the thread that made it.
+#[pyclass(unsendable)]
+#[derive(Default)]
+pub struct Deque {
+    list: fourth::List<PyObject>,
+}
+
+#[pymethods]
+impl Deque {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&mut self, elem: PyObject) {
+        self.push_back(elem);
+    }
+
+    fn pop(&mut self) -> Option<PyObject> {
+        self.pop_front()
+    }
+
+    fn push_front(&mut self, elem: PyObject) {
+        self.list.push_front(elem);
+    }
+
+    fn push_back(&mut self, elem: PyObject) {
+        self.list.push_back(elem);
+    }
+
+    fn pop_front(&mut self) -> Option<PyObject> {
+        self.list.pop_front()
+    }
+
+    fn pop_back(&mut self) -> Option<PyObject> {
+        self.list.pop_back()
+    }
+
+    fn __len__(&mut self) -> usize {
+        self.list.as_std_compat().len()
+    }
+
+    fn __iter__<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        // There's no borrowing iterator through all those RefCells, so we cycle every element
+        // out the front and back in the back, grabbing a new reference to each on the way.
+        let len = self.__len__();
+        let mut elems = Vec::with_capacity(len);
+        for _ in 0..len {
+            if let Some(elem) = self.list.pop_front() {
+                elems.push(elem.clone_ref(py));
+                self.list.push_back(elem);
+            }
+        }
+
+        // Iterating a snapshot means mutating the deque mid-loop can't pull the rug out.
+        Ok(PyList::new_bound(py, elems).as_any().iter()?.into_any())
+    }
+}
+
+/// A FIFO queue backed by the unsafe list from fifth.rs.
+#[pyclass(unsendable)]
+#[derive(Default)]
+pub struct Queue {
+    list: fifth::List<PyObject>,
+}
+
+#[pymethods]
+impl Queue {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&mut self, elem: PyObject) {
+        self.list.push(elem);
+    }
+
+    fn pop(&mut self) -> Option<PyObject> {
+        self.list.pop()
+    }
+
+    fn __len__(&self) -> usize {
+        self.list.len()
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let elems: Vec<_> = self.list.iter().map(|elem| elem.clone_ref(py)).collect();
+        Ok(PyList::new_bound(py, elems).as_any().iter()?.into_any())
+    }
+}
+
+// A Python object as a map key. Its hash is taken once, up front, where an unhashable key can
+// be turned into a TypeError; equality asks Python's `==`, and counts an exception as "not equal".
+struct Key {
+    hash: isize,
+    obj: PyObject,
+}
+
+impl FromPyObject<'_> for Key {
+    fn extract_bound(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(Self {
+            hash: obj.hash()?,
+            obj: obj.clone().unbind(),
+        })
+    }
+}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        Python::with_gil(|py| self.obj.bind(py).eq(other.obj.bind(py)).unwrap_or(false))
+    }
+}
+
+impl Eq for Key {}
+
+// Py's own Clone is behind pyo3's `py-clone` feature, since it can't check for the GIL
+impl Clone for Key {
+    fn clone(&self) -> Self {
+        Python::with_gil(|py| Self {
+            hash: self.hash,
+            obj: self.obj.clone_ref(py),
+        })
+    }
+}
+
+/// An LRU cache backed by the deque from fourth.rs (see `lru.rs`).
+///
+/// `push(key, value)` inserts or updates, and hands back the `(key, value)` it evicted, if any;
+/// `pop()` evicts the least recently used entry. Iterating yields the keys, most recently used
+/// first.
+#[pyclass(unsendable)]
+pub struct Lru {
+    cache: LruCache<Key, PyObject>,
+}
+
+#[pymethods]
+impl Lru {
+    #[new]
+    fn new(capacity: usize) -> PyResult<Self> {
+        if capacity == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "an LRU cache needs room for at least one entry",
+            ));
+        }
+        Ok(Self {
+            cache: LruCache::new(capacity),
+        })
+    }
+
+    fn push(&mut self, py: Python<'_>, key: Key, value: PyObject) -> Option<PyObject> {
+        let (key, value) = self.cache.push(key, value)?;
+        Some((key.obj, value).into_py(py))
+    }
+
+    fn pop(&mut self, py: Python<'_>) -> Option<PyObject> {
+        let (key, value) = self.cache.pop()?;
+        Some((key.obj, value).into_py(py))
+    }
+
+    /// The value for `key`, or None; either way it counts as a use.
+    fn get(&mut self, py: Python<'_>, key: Key) -> Option<PyObject> {
+        self.cache.get(&key).map(|value| value.clone_ref(py))
+    }
+
+    fn __len__(&self) -> usize {
+        self.cache.len()
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let keys: Vec<_> = self.cache.keys().map(|key| key.obj).collect();
+        Ok(PyList::new_bound(py, keys).as_any().iter()?.into_any())
+    }
+}
+
+#[pymodule]
+fn too_many_linked_lists(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Deque>()?;
+    m.add_class::<Queue>()?;
+    m.add_class::<Lru>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+
+    use super::{Deque, Lru, Queue};
+
+    fn run(script: &str) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let locals = pyo3::types::PyDict::new_bound(py);
+            locals
+                .set_item("Deque", py.get_type_bound::<Deque>())
+                .unwrap();
+            locals
+                .set_item("Queue", py.get_type_bound::<Queue>())
+                .unwrap();
+            locals.set_item("Lru", py.get_type_bound::<Lru>()).unwrap();
+            py.run_bound(script, None, Some(&locals)).unwrap();
+        });
+    }
+
+    #[test]
+    fn deque() {
+        run(r#"
+d = Deque()
+assert len(d) == 0
+assert d.pop() is None
+
+d.push(2)
+d.push(3)
+d.push_front(1)
+assert list(d) == [1, 2, 3]
+assert len(d) == 3
+
+# iterating must leave the deque as it was
+assert list(d) == [1, 2, 3]
+
+assert d.pop_back() == 3
+assert d.pop() == 1
+assert d.pop_front() == 2
+assert d.pop_back() is None
+assert len(d) == 0
+"#);
+    }
+
+    #[test]
+    fn queue() {
+        run(r#"
+q = Queue()
+q.push("a")
+q.push(["b"])
+assert len(q) == 2
+assert [x for x in q] == ["a", ["b"]]
+assert q.pop() == "a"
+assert q.pop() == ["b"]
+assert q.pop() is None
+assert len(q) == 0
+"#);
+    }
+
+    #[test]
+    fn lru() {
+        run(r#"
+c = Lru(2)
+assert c.push("a", 1) is None
+assert c.push((1, 2), [2]) is None
+assert c.get("a") == 1
+assert c.push("c", 3) == ((1, 2), [2])
+assert list(c) == ["c", "a"]
+assert len(c) == 2
+assert c.get((1, 2)) is None
+
+try:
+    c.push([], 0)
+except TypeError:
+    pass
+else:
+    raise AssertionError("a list isn't hashable")
+
+assert c.pop() == ("a", 1)
+assert c.pop() == ("c", 3)
+assert c.pop() is None
+"#);
+    }
+}
@@ -0,0 +1,288 @@
+// A list for data that's read all the time and changed once in a blue moon:
+// routing tables, config, the set of live backends. Read-copy-update, in the
+// style of the kernel's RCU. Readers never wait, not even on each other, and
+// never write to anything shared: `read` pins the current epoch and hands back
+// the list as it stands. Writers copy the whole list, change the copy, and swap
+// it in. Readers already in the middle of the old one carry on undisturbed, and
+// crossbeam-epoch frees it once the last of them is done.
+//
+// The price is all on the write side: every update is O(n) for the copy, and
+// writers queue up on a mutex so that two updates can't both start from the same
+// list and lose one of them. SharedList (shared.rs) is the other way round.
+
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::sync::atomic::Ordering;
+use std::sync::{Mutex, PoisonError};
+
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned};
+
+use crate::second::{Iter, List};
+
+/// Old versions are freed by whichever thread next collects the epoch's
+/// garbage, maybe after the list itself is gone, so `update` needs the elements
+/// to be `Send + 'static`, even in a list that never leaves its thread:
+///
+/// ```compile_fail
+/// use std::rc::Rc;
+/// use too_many_linked_lists::read_mostly::ReadMostlyList;
+///
+/// let list = ReadMostlyList::new();
+/// list.update(|list| list.push_front(Rc::new(1)));
+/// ```
+///
+/// Readers on other threads share each version, so sharing the list between
+/// threads takes `Sync` elements as well:
+///
+/// ```compile_fail
+/// use std::cell::Cell;
+/// use std::thread;
+/// use too_many_linked_lists::read_mostly::ReadMostlyList;
+///
+/// let list = ReadMostlyList::new();
+/// list.update(|list| list.push_front(Cell::new(1)));
+/// thread::scope(|scope| {
+///     scope.spawn(|| list.read().peek().map(Cell::get));
+/// });
+/// ```
+pub struct ReadMostlyList<T> {
+    current: Atomic<List<T>>,
+    // guards nothing but the right to swap `current`
+    writer: Mutex<()>,
+}
+
+/// The list as it stood when `read` was called, however many updates have
+/// happened since. Holding one keeps that version alive, so don't hang on to it
+/// for long.
+pub struct ReadGuard<'a, T> {
+    _guard: Guard,
+    list: *const List<T>,
+    _marker: PhantomData<&'a List<T>>,
+}
+
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = List<T>;
+
+    fn deref(&self) -> &List<T> {
+        // Safety: we're pinned, so the version we loaded isn't freed under us
+        unsafe { &*self.list }
+    }
+}
+
+/// Walks one version of the list, handing out clones, and keeps that version
+/// alive until it's dropped.
+pub struct SnapshotIter<'a, T> {
+    iter: Iter<'a, T>,
+    _read: ReadGuard<'a, T>,
+}
+
+impl<T: Clone> Iterator for SnapshotIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.iter.next().cloned()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for SnapshotIter<'_, T> {}
+
+impl<T> ReadMostlyList<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from(List::new())
+    }
+
+    /// Wait-free.
+    #[must_use]
+    pub fn read(&self) -> ReadGuard<'_, T> {
+        let guard = epoch::pin();
+        let list = self.current.load(Ordering::Acquire, &guard).as_raw();
+        ReadGuard {
+            _guard: guard,
+            list,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone> ReadMostlyList<T> {
+    /// Like `read`, but as an owned iterator: no copy up front, each element is
+    /// cloned as it's reached. Updates made meanwhile aren't seen.
+    #[must_use]
+    pub fn snapshot_iter(&self) -> SnapshotIter<'_, T> {
+        let read = self.read();
+        // Safety: the borrows never leave the iterator, which only hands out
+        // clones, and the iterator holds `read`'s pin for as long as it lives
+        let list = unsafe { &*read.list };
+        SnapshotIter {
+            iter: list.iter(),
+            _read: read,
+        }
+    }
+}
+
+// Send + 'static: see ReadMostlyList
+impl<T: Clone + Send + 'static> ReadMostlyList<T> {
+    /// Copies the list, runs `f` on the copy, and publishes it. Readers see the
+    /// old version or the new one, never anything in between. If `f` panics,
+    /// nothing is published.
+    pub fn update<R>(&self, f: impl FnOnce(&mut List<T>) -> R) -> R {
+        // nothing's published until the very end, so a panicked writer left
+        // nothing half done
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let guard = &epoch::pin();
+        let old = self.current.load(Ordering::Acquire, guard);
+        // Safety: never null, and we're pinned
+        let mut copy = List::new();
+        copy.restore(unsafe { old.deref() }.snapshot());
+        let result = f(&mut copy);
+        // Release: readers who load the new list see it fully built
+        self.current.store(Owned::new(copy), Ordering::Release);
+        // Safety: unreachable from `current` now, so only readers already
+        // pinned can still be looking at it
+        unsafe { guard.defer_destroy(old) };
+        result
+    }
+}
+
+impl<T> From<List<T>> for ReadMostlyList<T> {
+    fn from(list: List<T>) -> Self {
+        Self {
+            current: Atomic::new(list),
+            writer: Mutex::new(()),
+        }
+    }
+}
+
+impl<T> Default for ReadMostlyList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for ReadMostlyList<T> {
+    fn drop(&mut self) {
+        // Safety: no reader outlives its borrow of us, so the current version is
+        // ours alone; the older ones were handed to the epoch already
+        unsafe {
+            let current = self.current.load(Ordering::Relaxed, epoch::unprotected());
+            drop(current.into_owned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadMostlyList;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let list = ReadMostlyList::new();
+        assert!(list.read().is_empty());
+
+        list.update(|list| {
+            list.push_front(2);
+            list.push_front(1);
+        });
+        assert_eq!(list.update(|list| list.pop_front()), Some(1));
+        assert!(list.read().iter().eq(&[2]));
+    }
+
+    #[test]
+    fn readers_keep_their_version() {
+        let list = ReadMostlyList::new();
+        list.update(|list| list.push_front(1));
+
+        let before = list.read();
+        list.update(|list| list.push_front(2));
+        list.update(|list| list.clear());
+        assert!(before.iter().eq(&[1]));
+        assert!(list.read().is_empty());
+    }
+
+    #[test]
+    fn a_panicking_update_publishes_nothing() {
+        let list = ReadMostlyList::new();
+        list.update(|list| list.push_front(1));
+
+        let panicked = std::panic::catch_unwind(|| {
+            list.update(|list| {
+                list.push_front(2);
+                panic!("halfway through");
+            })
+        });
+        assert!(panicked.is_err());
+        assert!(list.read().iter().eq(&[1]));
+        // and the next writer isn't locked out
+        list.update(|list| list.push_front(3));
+        assert!(list.read().iter().eq(&[3, 1]));
+    }
+
+    #[test]
+    fn drops_every_version() {
+        let value = Arc::new(());
+        let list = ReadMostlyList::new();
+        for _ in 0..10 {
+            list.update(|list| list.push_front(Arc::clone(&value)));
+        }
+        drop(list);
+        // the old versions go whenever the epoch gets round to them
+        for _ in 0..1000 {
+            if Arc::strong_count(&value) == 1 {
+                return;
+            }
+            crossbeam_epoch::pin().flush();
+            thread::yield_now();
+        }
+        panic!("{} references left", Arc::strong_count(&value) - 1);
+    }
+
+    // Every version a reader sees was published whole: the writer only ever
+    // publishes lists of the form n, n-1, ..., 1.
+    #[test]
+    fn readers_never_see_half_an_update() {
+        let list = ReadMostlyList::new();
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        let version = list.read();
+                        assert!(version.iter().copied().eq((1..=version.len()).rev()));
+                    }
+                });
+            }
+            for n in 1..=200 {
+                list.update(|list| list.push_front(n));
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(list.read().len(), 200);
+    }
+
+    #[test]
+    fn snapshot_iters_see_one_version() {
+        let list = ReadMostlyList::new();
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    let version = list.snapshot_iter();
+                    let len = version.len();
+                    assert!(version.eq((1..=len).rev()));
+                }
+            });
+            for n in 1..=200 {
+                list.update(|list| list.push_front(n));
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+    }
+}
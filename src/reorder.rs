@@ -0,0 +1,241 @@
+// A Stream combinator that puts a stream of numbered items back in order. Work
+// fanned out to a pool comes back in whatever order it finished; tag each piece
+// with a sequence number on the way out, and `reorder` hands the results on
+// 0, 1, 2, ... no matter how they arrive.
+//
+//     let in_order = reorder(results_tagged_with_their_index, 1024);
+//
+// The waiting items live in fourth.rs's deque, one slot per sequence number
+// from the next one due: slot 0 is `next`, slot 1 is `next + 1`, and so on, with
+// None for the ones that haven't shown up yet. An item that's due pops straight
+// off the front. One that's early gets its slot by splitting the deque there and
+// splicing it back together: the split walks out to the slot, O(offset), and
+// the splice is O(1).
+//
+// Every sequence number between `next` and an early item costs a slot, so one
+// item from far in the future would cost a slot per number it skipped. The
+// window caps that: an item more than `window` places ahead comes back as a
+// TooFarAhead error instead, which also keeps each walk under `window` nodes.
+//
+// The deque is Rc-based, so a Reorder is !Send: it's for single-threaded
+// executors, or for the last stage of a pipeline on one thread.
+
+use core::fmt;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::fourth::List;
+
+/// Reorders `stream`'s `(sequence number, item)` pairs, starting from 0. Late
+/// or repeated sequence numbers are dropped. If the stream ends with gaps, the
+/// items after them are still handed on, in order.
+///
+/// At most `window` slots are held open at once: an item whose sequence number
+/// is `window` or more past the next one due is handed back in a
+/// [`TooFarAhead`], and the stream carries on.
+///
+/// # Panics
+///
+/// If `window` is 0.
+pub fn reorder<S, T>(stream: S, window: usize) -> Reorder<S, T>
+where
+    S: Stream<Item = (u64, T)> + Unpin,
+{
+    assert!(window != 0, "window must be non-zero");
+    Reorder {
+        stream,
+        next: 0,
+        slots: List::new(),
+        len: 0,
+        window,
+        done: false,
+    }
+}
+
+pub struct Reorder<S, T> {
+    stream: S,
+    // the sequence number slot 0 stands for
+    next: u64,
+    slots: List<Option<T>>,
+    // the deque doesn't count its nodes
+    len: usize,
+    window: usize,
+    done: bool,
+}
+
+/// An item too far ahead of the next one due to be held, handed back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TooFarAhead<T> {
+    pub seq: u64,
+    /// The next sequence number due when it arrived.
+    pub next: u64,
+    pub item: T,
+}
+
+impl<T> fmt::Display for TooFarAhead<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sequence number {} is too far ahead of {} to reorder",
+            self.seq, self.next
+        )
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for TooFarAhead<T> {}
+
+impl<S, T> Reorder<S, T> {
+    /// How many slots are held open: items waiting on an earlier one, plus the
+    /// gaps in front of them.
+    #[must_use]
+    pub fn held_back(&self) -> usize {
+        self.len
+    }
+
+    fn pop_slot(&mut self) -> Option<Option<T>> {
+        let slot = self.slots.pop_front()?;
+        self.len -= 1;
+        self.next += 1;
+        Some(slot)
+    }
+
+    // Puts `item` in the slot for `seq`, growing the deque out to it first.
+    fn fill(&mut self, seq: u64, item: T) -> Result<(), TooFarAhead<T>> {
+        let Some(offset) = seq.checked_sub(self.next) else {
+            // already handed on (or skipped): too late
+            return Ok(());
+        };
+        let offset = match usize::try_from(offset) {
+            Ok(offset) if offset < self.window => offset,
+            _ => {
+                let next = self.next;
+                return Err(TooFarAhead { seq, next, item });
+            }
+        };
+        while self.len <= offset {
+            self.slots.push_back(None);
+            self.len += 1;
+        }
+        let rest = self.slots.split_off(offset);
+        {
+            let mut slot = rest.peek_front_mut().expect("the deque reaches offset");
+            if slot.is_none() {
+                *slot = Some(item);
+            }
+        }
+        self.slots += rest;
+        Ok(())
+    }
+}
+
+// Nothing in here is ever pinned: the stream has to be Unpin, and the slots are
+// behind Rcs anyway.
+impl<S, T> Unpin for Reorder<S, T> {}
+
+impl<S, T> Stream for Reorder<S, T>
+where
+    S: Stream<Item = (u64, T)> + Unpin,
+{
+    type Item = Result<T, TooFarAhead<T>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.slots.peek_front().is_some_and(|slot| slot.is_some()) {
+                return Poll::Ready(this.pop_slot().flatten().map(Ok));
+            }
+            if this.done {
+                // nothing more is coming, so the gaps never will be filled
+                while let Some(slot) = this.pop_slot() {
+                    if slot.is_some() {
+                        return Poll::Ready(slot.map(Ok));
+                    }
+                }
+                return Poll::Ready(None);
+            }
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some((seq, item))) => {
+                    if let Err(too_far) = this.fill(seq, item) {
+                        return Poll::Ready(Some(Err(too_far)));
+                    }
+                }
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reorder, TooFarAhead};
+    use futures::channel::mpsc;
+    use futures::executor::block_on;
+    use futures::{stream, StreamExt};
+
+    #[test]
+    fn puts_them_back_in_order() {
+        let shuffled = [(3, 'd'), (0, 'a'), (2, 'c'), (5, 'f'), (1, 'b'), (4, 'e')];
+        let in_order = block_on(reorder(stream::iter(shuffled), 8).collect::<Vec<_>>());
+        let in_order: String = in_order.into_iter().map(Result::unwrap).collect();
+        assert_eq!(in_order, "abcdef");
+    }
+
+    #[test]
+    fn hands_on_what_it_can_as_soon_as_it_can() {
+        let (tx, rx) = mpsc::unbounded();
+        let mut in_order = reorder(rx, 8);
+
+        block_on(async {
+            tx.unbounded_send((1, "b")).unwrap();
+            tx.unbounded_send((2, "c")).unwrap();
+            tx.unbounded_send((0, "a")).unwrap();
+            assert_eq!(in_order.next().await, Some(Ok("a")));
+            assert_eq!(in_order.next().await, Some(Ok("b")));
+            assert_eq!(in_order.next().await, Some(Ok("c")));
+            assert_eq!(in_order.held_back(), 0);
+
+            tx.unbounded_send((4, "e")).unwrap();
+            // 3 isn't here yet, so 4 has to wait
+            assert_eq!(futures::poll!(in_order.next()), std::task::Poll::Pending);
+            assert_eq!(in_order.held_back(), 2);
+        });
+    }
+
+    #[test]
+    fn late_repeats_and_gaps() {
+        let items = [(1, 1), (0, 0), (0, 100), (1, 100), (4, 4), (4, 100), (6, 6)];
+        let in_order: Vec<_> = block_on(reorder(stream::iter(items), 8).collect());
+        let in_order: Vec<_> = in_order.into_iter().map(Result::unwrap).collect();
+        // the repeats are dropped; 2, 3 and 5 never came, so the rest are
+        // flushed once the stream ends
+        assert_eq!(in_order, [0, 1, 4, 6]);
+    }
+
+    #[test]
+    fn far_ahead_is_an_error_not_a_slot_per_gap() {
+        let items = [(1, 1), (u64::MAX, 100), (3, 100), (0, 0), (2, 2), (4, 4)];
+        let mut in_order = reorder(stream::iter(items), 3);
+        block_on(async {
+            let too_far = |seq, next| {
+                Some(Err(TooFarAhead {
+                    seq,
+                    next,
+                    item: 100,
+                }))
+            };
+            assert_eq!(in_order.next().await, too_far(u64::MAX, 0));
+            // the window is 3 slots, 0 to 2, and nothing past it was held
+            assert_eq!(in_order.held_back(), 2);
+            assert_eq!(in_order.next().await, too_far(3, 0));
+            assert_eq!(in_order.next().await, Some(Ok(0)));
+            assert_eq!(in_order.next().await, Some(Ok(1)));
+            assert_eq!(in_order.next().await, Some(Ok(2)));
+            // the window moves along with what's been handed on
+            assert_eq!(in_order.next().await, Some(Ok(4)));
+            assert_eq!(in_order.next().await, None);
+        });
+    }
+}
@@ -1,23 +1,30 @@
-// struct w single field -> zero cost abstraction!
-#[derive(Debug)]
-pub struct List<T> {
-    head: Link<T>,
-}
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::mem;
+use core::ops::{Index, IndexMut};
+use core::ptr;
 
-#[derive(Debug)]
-struct Node<T> {
-    value: T,
-    next: Link<T>,
-}
+use crate::generic::{self, BoxPtr, GenericList};
+use crate::trace::trace;
 
-type Link<T> = Option<Box<Node<T>>>;
+// The stack is generic.rs's list with Box links: new, len, iter, the runners and
+// Drop live there, shared with third.rs. What's here needs a sole owner for
+// every node.
+pub type List<T> = GenericList<T, BoxPtr>;
+type Node<T> = generic::Node<T, BoxPtr>;
+type Link<T> = generic::Link<T, BoxPtr>;
 
-impl<T> List<T> {
-    #[must_use] // linter error if invoked without binding return value
-    pub const fn new() -> Self {
-        Self { head: Link::None }
-    }
+pub type Iter<'a, T> = generic::Iter<'a, T, BoxPtr>;
 
+// Same null pointer optimization as first.rs's hand-rolled enum, for free: None is
+// the null pointer.
+const _: () = assert!(size_of::<Link<u64>>() == size_of::<usize>());
+const _: () = assert!(size_of::<Node<u64>>() == size_of::<(u64, usize)>());
+const _: () = assert!(align_of::<Node<u64>>() == align_of::<(u64, usize)>());
+
+impl<T> List<T> {
     pub fn push_front(&mut self, new_value: T) {
         let new_node = Node {
             value: new_value,
@@ -28,8 +35,13 @@ impl<T> List<T> {
             // below.
             next: self.head.take(),
         };
+        trace!("take head");
 
         self.head = Link::Some(Box::new(new_node));
+        trace!("box new node as head");
+        self.len += 1;
+        #[cfg(feature = "stats")]
+        self.stats.push();
     }
 
     pub fn pop_front(&mut self) -> Option<T> {
@@ -42,7 +54,12 @@ impl<T> List<T> {
         // We CAN do another cheeky Option::take() to acquire self.head by value without invalidating
         // self.head as a ptr!
         self.head.take().map(|boxed_node| {
+            trace!("take head");
             self.head = boxed_node.next;
+            trace!("relink head to next");
+            self.len -= 1;
+            #[cfg(feature = "stats")]
+            self.stats.pop();
             boxed_node.value
         })
     }
@@ -54,23 +71,42 @@ impl<T> List<T> {
         // little confused about this.
         self.head.as_ref().map(|boxed_node| &boxed_node.value)
     }
+
+    /// Pops the top only if `pred` says so: check-and-pop in one call, with no
+    /// window between a peek and a pop for things to change.
+    pub fn pop_front_if<F: FnOnce(&T) -> bool>(&mut self, pred: F) -> Option<T> {
+        if self.peek().is_some_and(pred) {
+            self.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// The bottom of the stack: the first element pushed. There's no tail pointer,
+    /// so this walks the whole list, O(n).
+    #[must_use]
+    pub fn back(&self) -> Option<&T> {
+        self.iter().last()
+    }
 }
 
-impl<T> Default for List<T> {
-    fn default() -> Self {
-        Self::new()
+// What #[derive(Debug)] would print, if it could see through the link pointer.
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_struct("List");
+        list.field("head", &self.head).field("len", &self.len);
+        #[cfg(feature = "stats")]
+        list.field("stats", &self.stats);
+        list.finish()
     }
 }
 
-// Default Drop isn't fully tail recursive! Namely, Box<Node> must drop its Node before
-// deallocating itself.
-// To fix this, we change all links in the list to None to avoid recursive drops.
-impl<T> Drop for List<T> {
-    fn drop(&mut self) {
-        let mut current_link = self.head.take();
-        while let Link::Some(mut boxed_node) = current_link {
-            current_link = boxed_node.next.take();
-        }
+impl<T: fmt::Debug> fmt::Debug for Node<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("value", &self.value)
+            .field("next", &self.next)
+            .finish()
     }
 }
 
@@ -79,8 +115,11 @@ impl<T> Drop for List<T> {
 // an IntoIter instance), then "iterate" over its elements by consuming each one.
 pub struct IntoIter<T>(List<T>);
 
-impl<T> List<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
         IntoIter(self)
     }
 }
@@ -91,19 +130,43 @@ impl<T> Iterator for IntoIter<T> {
     fn next(&mut self) -> Option<Self::Item> {
         self.0.pop_front()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
 }
 
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
 pub struct IterMut<'a, T> {
     next: Option<&'a mut Node<T>>,
+    remaining: usize,
+    #[cfg(feature = "stats")]
+    stats: &'a crate::stats::Counters,
 }
 
 impl<T> List<T> {
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         // still unsure as to what as_deref is doing here.
         IterMut {
             next: self.head.as_deref_mut(),
+            remaining: self.len,
+            #[cfg(feature = "stats")]
+            stats: &self.stats,
         }
     }
+
+    /// Gives up the list for good, like `Vec::leak`: the nodes are never freed,
+    /// so every element can be borrowed for `'static`. For setup-time data that
+    /// lives as long as the program anyway.
+    pub fn leak(self) -> IterMut<'static, T>
+    where
+        T: 'static,
+    {
+        // the list itself goes too, so the iterator has something 'static to
+        // borrow from
+        Box::leak(Box::new(self)).iter_mut()
+    }
 }
 
 impl<'a, T> Iterator for IterMut<'a, T> {
@@ -115,42 +178,799 @@ impl<'a, T> Iterator for IterMut<'a, T> {
         self.next.take().map(|node| {
             // still unsure as to what as_deref is doing here.
             self.next = node.next.as_deref_mut();
+            self.remaining -= 1;
+            #[cfg(feature = "stats")]
+            self.stats.step();
             &mut node.value
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+// Cursors: a position in the list you can move forward and, with CursorMut, edit
+// at. Past the last element sits the "ghost" position, same as std's cursors —
+// except with only next pointers, there's no wrapping round from the ghost to the
+// front. Once you're there, you're done moving.
+//
+// CursorMut points at the *link* holding the current node, not the node itself,
+// the same link-cursor walk insert_sorted does. Standing on a link means
+// inserting and removing are just the take/replace dance on that one link: no raw
+// pointers, nothing unsafe.
+pub struct Cursor<'a, T> {
+    current: Option<&'a Node<T>>,
+    index: usize,
 }
 
-pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>,
+// (T: 'a is implied for a plain Box link, but not through generic.rs's projection.)
+pub struct CursorMut<'a, T: 'a> {
+    // Always Some, except for the moment move_next has it out: the &'a mut has to
+    // be moved out of self to be stepped forward, and Option::take is the way out.
+    link: Option<&'a mut Link<T>>,
+    len: &'a mut usize,
+    index: usize,
 }
 
 impl<T> List<T> {
-    pub fn iter(&self) -> Iter<T> {
-        // still unsure as to what as_deref is doing here.
-        Iter {
-            next: self.head.as_deref(),
+    /// A cursor on the top of the stack (or at the ghost, if it's empty).
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.head.as_deref(),
+            index: 0,
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            link: Some(&mut self.head),
+            len: &mut self.len,
+            index: 0,
+        }
+    }
+
+    /// A cursor on the first element matching `pred`, or at the ghost if none
+    /// do. Find-then-edit in one walk.
+    pub fn find_cursor<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Cursor<'_, T> {
+        let mut cursor = self.cursor_front();
+        while cursor.current().is_some_and(|elem| !pred(elem)) {
+            cursor.move_next();
+        }
+        cursor
+    }
+
+    pub fn find_cursor_mut<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> CursorMut<'_, T> {
+        let mut cursor = self.cursor_front_mut();
+        while cursor.current().is_some_and(|elem| !pred(elem)) {
+            cursor.move_next();
+        }
+        cursor
+    }
+}
+
+impl<'a, T> Cursor<'a, T> {
+    /// Where we are, counting from the front. None at the ghost.
+    #[must_use]
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    #[must_use]
+    pub fn current(&self) -> Option<&'a T> {
+        self.current.map(|node| &node.value)
+    }
+
+    #[must_use]
+    pub fn peek_next(&self) -> Option<&'a T> {
+        self.current?.next.as_deref().map(|node| &node.value)
+    }
+
+    /// Steps forward. From the last element that's the ghost; from the ghost,
+    /// nowhere.
+    pub fn move_next(&mut self) {
+        if let Some(node) = self.current {
+            self.current = node.next.as_deref();
+            self.index += 1;
+        }
+    }
+}
+
+impl<T> CursorMut<'_, T> {
+    fn link(&mut self) -> &mut Link<T> {
+        self.link.as_mut().unwrap()
+    }
+
+    #[must_use]
+    pub fn index(&self) -> Option<usize> {
+        self.link.as_ref().unwrap().as_ref().map(|_| self.index)
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.link().as_mut().map(|node| &mut node.value)
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let node = self.link().as_mut()?;
+        node.next.as_mut().map(|next| &mut next.value)
+    }
+
+    pub fn move_next(&mut self) {
+        let link = self.link.take().unwrap();
+        self.link = Some(if link.is_some() {
+            self.index += 1;
+            &mut link.as_mut().unwrap().next
+        } else {
+            link
+        });
+    }
+
+    /// Puts `elem` right after the current element, without moving the cursor.
+    /// At the ghost, `elem` goes on the end and the cursor stays on the ghost.
+    pub fn insert_after(&mut self, elem: T) {
+        let ghost = self.link().is_none();
+        let after = match self.link() {
+            Some(node) => &mut node.next,
+            None => self.link(),
+        };
+        let next = after.take();
+        *after = Some(Box::new(Node { value: elem, next }));
+        trace!("splice new node into link");
+        *self.len += 1;
+        if ghost {
+            // still past the last element, which is now the one we just added
+            self.move_next();
         }
     }
+
+    /// Unlinks the current element and hands it back. The cursor ends up on the
+    /// element that came after it (or the ghost). Nothing to remove at the ghost.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let link = self.link();
+        let node = link.take()?;
+        *link = node.next;
+        trace!("relink over removed node");
+        *self.len -= 1;
+        Some(node.value)
+    }
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
-    type Item = &'a T;
+// Random access, linked-list style: walk there one node at a time. O(n), so
+// `list[i]` in a loop is quietly O(n²) — iter() is almost always what you want.
+impl<T> List<T> {
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.iter_mut().nth(index)
+    }
+}
+
+// Batches of up to `size` elements, as owned lists. Each batch is cut straight
+// out of the chain, so nothing gets copied or reallocated.
+pub struct Chunks<T> {
+    rest: List<T>,
+    size: usize,
+}
+
+impl<T> List<T> {
+    /// # Panics
+    ///
+    /// If `size` is 0, like `slice::chunks`.
+    pub fn chunks(self, size: usize) -> Chunks<T> {
+        assert!(size != 0, "chunk size must be non-zero");
+        Chunks { rest: self, size }
+    }
+}
+
+impl<T> Iterator for Chunks<T> {
+    type Item = List<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.next.map(|node| {
-            // still unsure as to what as_deref is doing here.
-            self.next = node.next.as_deref();
-            &node.value
-        })
+        self.rest.head.as_ref()?;
+        let taken = self.size.min(self.rest.len);
+        let mut cursor = &mut self.rest.head;
+        for _ in 0..taken {
+            cursor = &mut cursor.as_mut().unwrap().next;
+        }
+        let after = cursor.take();
+
+        let mut chunk = List::new();
+        chunk.head = self.rest.head.take();
+        chunk.len = taken;
+        self.rest.head = after;
+        self.rest.len -= taken;
+        Some(chunk)
+    }
+}
+
+// from_end's runner (see generic.rs), handing out the element mutably.
+impl<T> List<T> {
+    pub fn from_end_mut(&mut self, n: usize) -> Option<&mut T> {
+        self.runner_mut(n).map(|node| &mut node.value)
+    }
+
+    // The same walk, but the trailer has to come out mutable while the lead reads
+    // ahead of it through the same links, which references can't say. So both walk
+    // as raw pointers, and only the trailer is made a reference, once the lead's
+    // done. addr_of_mut! gets from a link to its node without a reference to the
+    // node along the way, so neither pointer invalidates the other.
+    fn runner_mut(&mut self, n: usize) -> Option<&mut Node<T>> {
+        let node = |link: &mut Link<T>| link.as_mut().map(|node| ptr::addr_of_mut!(**node));
+        let next = |node_ptr: *mut Node<T>| node(unsafe { &mut (*node_ptr).next });
+        let mut lead = node(&mut self.head);
+        for _ in 0..n {
+            lead = next(lead?);
+        }
+        let mut lead = lead?;
+        let mut trail = node(&mut self.head)?;
+        while let Some(ahead) = next(lead) {
+            lead = ahead;
+            // n nodes behind the lead, so never past it
+            trail = next(trail)?;
+        }
+        // SAFETY: trail came from the &mut self this borrows from, and the lead,
+        // the only other pointer, is done with.
+        Some(unsafe { &mut *trail })
+    }
+}
+
+// Same deal as Vec: out of range is a panic. Use get() if that's a possibility.
+impl<T> Index<usize> for List<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        // counting as we go means the panic message gets the length for free
+        let mut len = 0;
+        for elem in self.iter() {
+            if len == index {
+                return elem;
+            }
+            len += 1;
+        }
+        panic!("index out of bounds: the len is {len} but the index is {index}")
+    }
+}
+
+impl<T> IndexMut<usize> for List<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let mut len = 0;
+        for elem in self.iter_mut() {
+            if len == index {
+                return elem;
+            }
+            len += 1;
+        }
+        panic!("index out of bounds: the len is {len} but the index is {index}")
+    }
+}
+
+impl<T> List<T> {
+    /// Splits the list into (matching, non-matching), keeping the original order
+    /// in both. Nodes are relinked, never rebuilt: no element moves, no allocations.
+    pub fn partition<F: FnMut(&T) -> bool>(mut self, mut pred: F) -> (Self, Self) {
+        let (mut matching, mut rest) = (Self::new(), Self::new());
+        // Cursors on the last link of each output, so appending is O(1). Option::insert
+        // hands back a &mut to the node it just stored, and we hop onto its `next`.
+        let mut matching_end = &mut matching.head;
+        let mut rest_end = &mut rest.head;
+
+        let mut remaining = self.head.take();
+        while let Some(mut node) = remaining {
+            remaining = node.next.take();
+            if pred(&node.value) {
+                matching_end = &mut matching_end.insert(node).next;
+                matching.len += 1;
+            } else {
+                rest_end = &mut rest_end.insert(node).next;
+                rest.len += 1;
+            }
+        }
+
+        (matching, rest)
+    }
+
+    /// Splits into the first `mid` elements and everything after, in one walk.
+    ///
+    /// # Panics
+    ///
+    /// If `mid` is past the end of the list, same as slices.
+    pub fn split_at(mut self, mid: usize) -> (Self, Self) {
+        // walk a cursor to the link after the first `mid` nodes, then cut it
+        let mut cursor = &mut self.head;
+        for walked in 0..mid {
+            match cursor {
+                Some(node) => cursor = &mut node.next,
+                None => panic!("mid > len ({mid} > {walked})"),
+            }
+        }
+        let mut back = Self::new();
+        back.head = cursor.take();
+        back.len = self.len - mid;
+        self.len = mid;
+        (self, back)
+    }
+
+    /// Splices `lists` together in order. We have no tail pointer, so this has to walk
+    /// over every node to find each list's end: O(total nodes), but zero allocations.
+    pub fn concat<I: IntoIterator<Item = Self>>(lists: I) -> Self {
+        let mut joined = Self::new();
+        let mut end = &mut joined.head;
+        for mut list in lists {
+            *end = list.head.take();
+            joined.len += list.len;
+            while let Some(node) = end {
+                end = &mut node.next;
+            }
+        }
+        joined
+    }
+
+    /// Zips `other`'s nodes in between ours: a, x, b, y, ... Whichever list is longer
+    /// has its leftovers tacked on the end. Pure relinking.
+    pub fn interleave(&mut self, mut other: Self) {
+        self.len += other.len;
+        let mut cursor = &mut self.head;
+        let mut theirs = other.head.take();
+        while let Some(mut their_node) = theirs {
+            match cursor {
+                Some(our_node) => {
+                    // slot their node in right behind ours, then step over both
+                    theirs = their_node.next.take();
+                    their_node.next = our_node.next.take();
+                    cursor = &mut our_node.next.insert(their_node).next;
+                }
+                None => {
+                    // we ran out first: their node still holds the rest of their list
+                    *cursor = Some(their_node);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl<T: PartialEq> List<T> {
+    /// Whether the list reads the same both ways. O(n) time and no extra space:
+    /// cut the list after the middle, reverse the back half in place, walk the
+    /// two halves side by side, then reverse it back and reattach it. That's why
+    /// a read-only question takes `&mut self`. The reattaching is done by a guard,
+    /// so a panicking `==` still leaves the list whole.
+    pub fn is_palindrome(&mut self) -> bool {
+        // Owns the reversed back half until it's put back, on whatever way out.
+        struct Reattach<'a, T> {
+            list: &'a mut List<T>,
+            middle: usize,
+            back: Link<T>,
+        }
+
+        impl<T> Drop for Reattach<'_, T> {
+            fn drop(&mut self) {
+                let back = List::reverse_chain(self.back.take());
+                *self.list.link_after(self.middle) = back;
+            }
+        }
+
+        let Some(middle) = self.middle_index() else {
+            return true;
+        };
+        let back = Self::reverse_chain(self.link_after(middle).take());
+        let halves = Reattach {
+            list: self,
+            middle,
+            back,
+        };
+
+        let back = core::iter::successors(halves.back.as_deref(), |node| node.next.as_deref());
+        halves
+            .list
+            .iter()
+            .zip(back)
+            .all(|(front, back)| *front == back.value)
+    }
+}
+
+impl<T> List<T> {
+    // the `next` of the node at `index`, which has to exist
+    fn link_after(&mut self, index: usize) -> &mut Link<T> {
+        let mut cursor = &mut self.head;
+        for _ in 0..index {
+            cursor = &mut cursor.as_mut().unwrap().next;
+        }
+        &mut cursor.as_mut().unwrap().next
+    }
+
+    // Flips a detached chain around, one node at a time, by relinking each onto
+    // the front of the reversed part. No allocations.
+    fn reverse_chain(mut remaining: Link<T>) -> Link<T> {
+        let mut reversed = None;
+        while let Some(mut node) = remaining {
+            remaining = mem::replace(&mut node.next, reversed);
+            reversed = Some(node);
+        }
+        reversed
+    }
+
+    /// Reverses each run of `k` nodes in place: with k = 3, 1 2 3 4 5 6 7 becomes
+    /// 3 2 1 6 5 4 7. A last group shorter than `k` stays as it is, unless
+    /// `reverse_rest` says to flip it too. Only relinks.
+    ///
+    /// # Panics
+    ///
+    /// If `k` is 0.
+    pub fn reverse_in_groups(&mut self, k: usize, reverse_rest: bool) {
+        assert!(k != 0, "group size must be non-zero");
+        let mut remaining = self.head.take();
+        let mut end = &mut self.head;
+        while remaining.is_some() {
+            // cut the next group off the front of what's left
+            let mut cut = &mut remaining;
+            let mut taken = 0;
+            while taken < k {
+                match cut {
+                    Some(node) => cut = &mut node.next,
+                    None => break,
+                }
+                taken += 1;
+            }
+            let rest = cut.take();
+            let mut group = mem::replace(&mut remaining, rest);
+
+            if taken == k || reverse_rest {
+                group = Self::reverse_chain(group);
+            }
+            *end = group;
+            while let Some(node) = end {
+                end = &mut node.next;
+            }
+        }
+    }
+}
+
+impl<T: PartialEq> List<T> {
+    /// Unlinks the first node holding `value`. Returns whether there was one.
+    pub fn remove_first(&mut self, value: &T) -> bool {
+        // same link-cursor walk as insert_sorted
+        let mut cursor = &mut self.head;
+        while cursor.as_ref().is_some_and(|node| node.value != *value) {
+            cursor = &mut cursor.as_mut().unwrap().next;
+        }
+        match cursor.take() {
+            Some(node) => {
+                *cursor = node.next;
+                self.len -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Unlinks every node holding `value`, in one pass. Returns how many went.
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        // Take the whole chain and relink the keepers back on, one by one.
+        let mut removed = 0;
+        let mut remaining = self.head.take();
+        let mut end = &mut self.head;
+        while let Some(mut node) = remaining {
+            remaining = node.next.take();
+            if node.value == *value {
+                removed += 1;
+            } else {
+                end = &mut end.insert(node).next;
+            }
+        }
+        self.len -= removed;
+        removed
+    }
+}
+
+// Checkpoints, for when the list is an interpreter's stack and a failed step has to
+// be undone. A snapshot is a plain deep copy: sharing nodes copy-on-write would
+// take Rc links, and then this wouldn't be the Box stack any more.
+pub struct Snapshot<T>(List<T>);
+
+impl<T: Clone> List<T> {
+    /// Copies the whole list, to hand back to `restore` later. O(n).
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot<T> {
+        let mut copy = Self::new();
+        // a cursor parked at the ghost appends, so the copy keeps our order
+        let mut end = copy.cursor_front_mut();
+        for elem in self.iter() {
+            end.insert_after(elem.clone());
+        }
+        Snapshot(copy)
+    }
+}
+
+impl<T> List<T> {
+    /// Rolls the list back to `snapshot`, dropping whatever it holds now. The
+    /// snapshot's nodes move straight in, so this is O(1) plus the drop.
+    pub fn restore(&mut self, snapshot: Snapshot<T>) {
+        // Swap the nodes over rather than assigning the whole list, so our stats
+        // (if any) carry on. The old nodes go down with the snapshot's list.
+        let mut old = snapshot.0;
+        mem::swap(&mut self.head, &mut old.head);
+        mem::swap(&mut self.len, &mut old.len);
     }
 }
 
-fn main() {}
+impl<T> Snapshot<T> {
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.0.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+}
+
+// Sorted means ascending from the front. Keep a list sorted by only ever using
+// insert_sorted and you've got a (slow, O(n) insert) priority queue: the smallest
+// element is always one pop_front away.
+impl<T: Ord> List<T> {
+    #[must_use]
+    pub fn is_sorted(&self) -> bool {
+        self.iter().is_sorted()
+    }
+
+    /// Inserts `elem` after every element that is <= it, so equal elements keep
+    /// their insertion order. Assumes the list is already sorted.
+    pub fn insert_sorted(&mut self, elem: T) {
+        // Walk a cursor over the *links*, not the nodes: stopping on a link means
+        // we can splice right there, even when that link is the head.
+        let mut cursor = &mut self.head;
+        // `while let Some(node) = cursor` would hold the borrow too long for the
+        // borrow checker, so peek first and only then move the cursor along.
+        while cursor.as_ref().is_some_and(|node| node.value <= elem) {
+            cursor = &mut cursor.as_mut().unwrap().next;
+        }
+        let next = cursor.take();
+        *cursor = Some(Box::new(Node { value: elem, next }));
+        trace!("splice new node into link");
+        self.len += 1;
+        #[cfg(feature = "stats")]
+        self.stats.push();
+    }
+}
+
+impl<T: Ord> List<T> {
+    /// Splits the list three ways around `pivot`: (less, equal, greater), each in
+    /// its original order. The Dutch national flag problem, done by relinking —
+    /// quicksort for lists is this plus recursing on the outer two.
+    pub fn partition3(mut self, pivot: &T) -> (Self, Self, Self) {
+        let (mut less, mut equal, mut greater) = (Self::new(), Self::new(), Self::new());
+        // same end-of-list cursors as partition, one per part
+        let mut less_end = &mut less.head;
+        let mut equal_end = &mut equal.head;
+        let mut greater_end = &mut greater.head;
+
+        let mut remaining = self.head.take();
+        while let Some(mut node) = remaining {
+            remaining = node.next.take();
+            match node.value.cmp(pivot) {
+                Ordering::Less => {
+                    less_end = &mut less_end.insert(node).next;
+                    less.len += 1;
+                }
+                Ordering::Equal => {
+                    equal_end = &mut equal_end.insert(node).next;
+                    equal.len += 1;
+                }
+                Ordering::Greater => {
+                    greater_end = &mut greater_end.insert(node).next;
+                    greater.len += 1;
+                }
+            }
+        }
+
+        (less, equal, greater)
+    }
+
+    /// Merges two sorted lists into one, by relinking their nodes. On ties, ours
+    /// come first, so merging is stable.
+    #[must_use]
+    pub fn merge(mut self, mut other: Self) -> Self {
+        let mut merged = Self::new();
+        merged.len = self.len + other.len;
+        let mut end = &mut merged.head;
+        let (mut ours, mut theirs) = (self.head.take(), other.head.take());
+        while let (Some(our_node), Some(their_node)) = (&ours, &theirs) {
+            let from = if their_node.value < our_node.value {
+                &mut theirs
+            } else {
+                &mut ours
+            };
+            let mut node = from.take().unwrap();
+            *from = node.next.take();
+            end = &mut end.insert(node).next;
+        }
+        // whichever side's left over is still sorted, and goes on as is
+        *end = ours.or(theirs);
+        merged
+    }
+
+    /// Merges any number of sorted lists, pairing them off and merging each pair,
+    /// round after round: O(n log k) for n elements over k lists, versus O(nk)
+    /// folding them into one list at a time. Only relinks. Ties keep list order.
+    #[must_use]
+    pub fn merge_k(mut lists: Vec<Self>) -> Self {
+        while lists.len() > 1 {
+            let mut merged = Vec::with_capacity(lists.len().div_ceil(2));
+            let mut pairs = lists.into_iter();
+            while let Some(first) = pairs.next() {
+                merged.push(match pairs.next() {
+                    Some(second) => first.merge(second),
+                    None => first,
+                });
+            }
+            lists = merged;
+        }
+        lists.pop().unwrap_or_default()
+    }
+}
+
+// Lists serialize as plain sequences, front to back. Deserializing appends through a cursor
+// parked at the ghost, so the elements go straight into nodes in the order they arrive.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        // Some formats (bincode) insist on knowing the length up front.
+        let mut seq = serializer.serialize_seq(Some(self.len))?;
+        for elem in self.iter() {
+            seq.serialize_element(elem)?;
+        }
+        seq.end()
+    }
+}
+
+/// Elements that can borrow from the input do, when the format allows it: a
+/// `List<&str>` read from JSON points into the JSON text, with no copies. Which
+/// means the list can't outlive the text:
+///
+/// ```compile_fail
+/// use too_many_linked_lists::second::List;
+///
+/// let list: List<&str> = {
+///     let json = String::from(r#"["a", "b"]"#);
+///     serde_json::from_str(&json).unwrap()
+/// }; // json dropped here while `list` still points into it
+/// ```
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ListVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for ListVisitor<T> {
+            type Value = List<T>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a sequence")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut list = List::new();
+                let mut end = list.cursor_front_mut();
+                while let Some(elem) = seq.next_element()? {
+                    end.insert_after(elem);
+                }
+                Ok(list)
+            }
+        }
+
+        deserializer.deserialize_seq(ListVisitor(std::marker::PhantomData))
+    }
+}
+
+// With rkyv, a list archives to a flat ArchivedVec — readable as a slice straight out of the
+// byte buffer, no nodes (or deserialization) required.
+#[cfg(feature = "rkyv")]
+impl<T: rkyv::Archive> rkyv::Archive for List<T> {
+    type Archived = rkyv::vec::ArchivedVec<T::Archived>;
+    type Resolver = rkyv::vec::VecResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: rkyv::Place<Self::Archived>) {
+        rkyv::vec::ArchivedVec::resolve_from_len(self.len, resolver, out);
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, S> rkyv::Serialize<S> for List<T>
+where
+    T: rkyv::Serialize<S>,
+    S: rkyv::rancor::Fallible + rkyv::ser::Allocator + rkyv::ser::Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        // The archiver wants an ExactSizeIterator it can clone, so gather up refs first.
+        let elems: Vec<&T> = self.iter().collect();
+        rkyv::vec::ArchivedVec::serialize_from_iter::<T, _, _>(elems.iter().copied(), serializer)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, D> rkyv::Deserialize<List<T>, D> for rkyv::vec::ArchivedVec<T::Archived>
+where
+    T: rkyv::Archive,
+    [T::Archived]: rkyv::DeserializeUnsized<[T], D>,
+    D: rkyv::rancor::Fallible + ?Sized,
+    D::Error: rkyv::rancor::Source,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<List<T>, D::Error> {
+        let elems: Vec<T> = self.deserialize(deserializer)?;
+        let mut list = List::new();
+        for elem in elems.into_iter().rev() {
+            list.push_front(elem);
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(feature = "viz")]
+impl<T: std::fmt::Debug> List<T> {
+    fn graph(&self) -> crate::viz::Graph {
+        let mut graph = crate::viz::Graph::new();
+        for node in std::iter::successors(self.head.as_deref(), |node| node.next.as_deref()) {
+            graph.node(node, &node.value);
+            if let Some(next) = node.next.as_deref() {
+                graph.edge(node, next, "next");
+            }
+        }
+        if let Some(head) = self.head.as_deref() {
+            graph.marker("head", head);
+        }
+        graph
+    }
+
+    /// Dumps the list's structure as a Graphviz DOT graph.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        self.graph().to_dot()
+    }
+
+    /// Same as [`List::to_dot`], but as a Mermaid `graph LR` diagram, ready to paste into
+    /// markdown.
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        self.graph().to_mermaid()
+    }
+
+    /// Draws the list as ASCII art, e.g. `HEAD -> [2] -> [5] -> ∅`.
+    #[must_use]
+    pub fn render_ascii(&self) -> String {
+        self.graph().to_ascii()
+    }
+
+    /// Same as [`List::to_dot`], but as JSON, for drawing somewhere other than a
+    /// terminal (examples/wasm-viz draws it in a browser).
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        self.graph().to_json()
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::List;
 
+    fn from(elems: &[i32]) -> List<i32> {
+        let mut list = List::new();
+        for &elem in elems.iter().rev() {
+            list.push_front(elem);
+        }
+        list
+    }
+
     #[test]
     fn pushing_pulling() {
         let mut list: List<i32> = List::new();
@@ -192,6 +1012,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::while_let_on_iterator, clippy::assign_op_pattern)] // spelled out on purpose
     fn mut_itering() {
         let mut list: List<i32> = List::new();
         list.push_front(5);
@@ -209,4 +1030,677 @@ mod tests {
         assert_eq!(lil_iter.next(), Some(50));
         assert_eq!(lil_iter.next(), None);
     }
+
+    #[test]
+    fn leak() {
+        let mut list = List::new();
+        list.push_front(String::from("b"));
+        list.push_front(String::from("a"));
+
+        let leaked: Vec<&'static mut String> = list.leak().collect();
+        let [a, b] = <[_; 2]>::try_from(leaked).unwrap();
+        b.push('!');
+        assert_eq!((a.as_str(), b.as_str()), ("a", "b!"));
+    }
+
+    // Both the str and the bytes come straight out of the input, not copies.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_borrows() {
+        let json = r#"["a","bc"]"#;
+        let list: List<&str> = serde_json::from_str(json).unwrap();
+        assert!(list.iter().eq(&["a", "bc"]));
+        assert!(list
+            .iter()
+            .all(|s| json.as_bytes().as_ptr_range().contains(&s.as_ptr())));
+
+        let bytes = bincode::serialize(&vec![&b"ab"[..], b"c"]).unwrap();
+        let list: List<&[u8]> = bincode::deserialize(&bytes).unwrap();
+        assert!(list.iter().eq(&[&b"ab"[..], b"c"]));
+        assert!(list
+            .iter()
+            .all(|b| bytes.as_ptr_range().contains(&b.as_ptr())));
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn rkyv_round_trip() {
+        use rkyv::{rancor::Error, vec::ArchivedVec, Archived};
+
+        for elems in [vec![1, 2, 3], vec![3, 2, 1], vec![42], vec![]] {
+            let mut list: List<i32> = List::new();
+            for &elem in elems.iter().rev() {
+                list.push_front(elem);
+            }
+
+            let bytes = rkyv::to_bytes::<Error>(&list).unwrap();
+
+            // no deserialization needed to look at the archive
+            let archived = rkyv::access::<ArchivedVec<Archived<i32>>, Error>(&bytes).unwrap();
+            assert_eq!(archived.as_slice(), elems.as_slice());
+
+            let list: List<i32> = rkyv::deserialize::<_, Error>(archived).unwrap();
+            assert_eq!(list.into_iter().collect::<Vec<_>>(), elems);
+        }
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn to_dot() {
+        use crate::viz::dot_id;
+
+        assert!(!List::<i32>::new().to_dot().contains("->"));
+
+        let mut list = List::new();
+        list.push_front(5);
+        list.push_front(2);
+
+        let dot = list.to_dot();
+        let (two, five) = (dot_id(&dot, "2"), dot_id(&dot, "5"));
+        assert!(dot.contains(&format!("head -> {two};")));
+        assert!(dot.contains(&format!("{two} -> {five} [label=\"next\"];")));
+        assert_eq!(dot.matches("->").count(), 2);
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn to_mermaid() {
+        use crate::viz::mermaid_id;
+
+        let mut list = List::new();
+        list.push_front("five");
+        list.push_front("two");
+
+        let mermaid = list.to_mermaid();
+        assert!(mermaid.starts_with("graph LR\n"));
+        let (two, five) = (
+            mermaid_id(&mermaid, "#34;two#34;"),
+            mermaid_id(&mermaid, "#34;five#34;"),
+        );
+        assert!(mermaid.contains(&format!("head([head]) --> {two}")));
+        assert!(mermaid.contains(&format!("{two} -->|next| {five}")));
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn render_ascii() {
+        let mut list = List::new();
+        assert_eq!(list.render_ascii(), "HEAD -> ∅\n");
+
+        list.push_front(5);
+        list.push_front(2);
+        assert_eq!(list.render_ascii(), "HEAD -> [2] -> [5] -> ∅\n");
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn trace() {
+        let log = crate::trace::capture(|| {
+            let mut list = List::new();
+            list.push_front(1);
+            list.pop_front();
+            list.pop_front();
+        });
+        // popping an empty list touches nothing worth narrating
+        assert_eq!(
+            log,
+            [
+                "take head",
+                "box new node as head",
+                "take head",
+                "relink head to next",
+            ]
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats() {
+        let mut list = List::new();
+        list.push_front(1);
+        list.push_front(2);
+        list.push_front(3);
+        list.pop_front();
+        assert_eq!(list.iter().count(), 2);
+        list.iter_mut().for_each(|elem| *elem += 1);
+
+        let stats = list.stats();
+        assert_eq!((stats.pushes, stats.pops, stats.allocations), (3, 1, 3));
+        // one step per node, per pass
+        assert_eq!(stats.steps, 4);
+        assert_eq!(stats.reuses, 0);
+    }
+
+    #[test]
+    fn index() {
+        let mut list = List::new();
+        list.push_front(3);
+        list.push_front(2);
+        list.push_front(1);
+
+        assert_eq!(list.get(0), Some(&1));
+        assert_eq!(list.get(3), None);
+        assert_eq!(list[2], 3);
+
+        list[1] = 20;
+        *list.get_mut(2).unwrap() = 30;
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 20, 30]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds: the len is 3 but the index is 3")]
+    fn index_out_of_bounds() {
+        let mut list = List::new();
+        list.push_front(1);
+        list.push_front(2);
+        list.push_front(3);
+        let _ = list[3];
+    }
+
+    #[test]
+    fn insert_sorted() {
+        let mut list = List::new();
+        assert!(list.is_sorted());
+
+        for elem in [5, 1, 4, 1, 9, 2] {
+            list.insert_sorted(elem);
+            assert!(list.is_sorted());
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 1, 2, 4, 5, 9]);
+
+        list.push_front(10);
+        assert!(!list.is_sorted());
+    }
+
+    #[test]
+    fn insert_sorted_is_stable() {
+        // ordered by the number only; the tag tells us who came first
+        #[derive(Debug)]
+        struct Tagged(i32, &'static str);
+        impl PartialEq for Tagged {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Eq for Tagged {}
+        impl PartialOrd for Tagged {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Tagged {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let mut list = List::new();
+        list.insert_sorted(Tagged(1, "c"));
+        list.insert_sorted(Tagged(0, "b"));
+        list.insert_sorted(Tagged(1, "a"));
+
+        let order: Vec<_> = list.iter().map(|tagged| tagged.1).collect();
+        assert_eq!(order, ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn partition() {
+        let mut list = List::new();
+        for elem in (1..=6).rev() {
+            list.push_front(elem);
+        }
+        let first: *const i32 = list.peek().unwrap();
+
+        let (odds, evens) = list.partition(|elem| elem % 2 == 1);
+        assert_eq!(odds.iter().copied().collect::<Vec<_>>(), [1, 3, 5]);
+        assert_eq!(evens.iter().copied().collect::<Vec<_>>(), [2, 4, 6]);
+        // same node, same address: it was relinked, not copied
+        assert_eq!(odds.peek().unwrap() as *const i32, first);
+
+        let (all, none) = odds.partition(|_| true);
+        assert_eq!(all.iter().count(), 3);
+        assert!(none.peek().is_none());
+    }
+
+    #[test]
+    fn split_at() {
+        let mut list = List::new();
+        for elem in (1..=5).rev() {
+            list.push_front(elem);
+        }
+
+        let (front, back) = list.split_at(2);
+        assert_eq!(front.iter().copied().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(back.iter().copied().collect::<Vec<_>>(), [3, 4, 5]);
+
+        let (empty, back) = back.split_at(0);
+        assert!(empty.peek().is_none());
+        let (back, empty) = back.split_at(3);
+        assert_eq!(back.iter().count(), 3);
+        assert!(empty.peek().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "mid > len (2 > 1)")]
+    fn split_at_past_the_end() {
+        let mut list = List::new();
+        list.push_front(1);
+        let _ = list.split_at(2);
+    }
+
+    #[test]
+    fn interleave() {
+        for (ours, theirs, zipped) in [
+            (&[1, 3, 5][..], &[2, 4, 6][..], &[1, 2, 3, 4, 5, 6][..]),
+            (&[1, 3], &[2, 4, 5, 6], &[1, 2, 3, 4, 5, 6]),
+            (&[1, 3, 4, 5], &[2], &[1, 2, 3, 4, 5]),
+            (&[], &[1, 2], &[1, 2]),
+            (&[1, 2], &[], &[1, 2]),
+        ] {
+            let mut list = from(ours);
+            list.interleave(from(theirs));
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), zipped);
+        }
+    }
+
+    #[test]
+    fn concat() {
+        let lists = [&[1, 2][..], &[], &[3], &[4, 5, 6], &[]].map(|elems| {
+            let mut list = List::new();
+            for &elem in elems.iter().rev() {
+                list.push_front(elem);
+            }
+            list
+        });
+
+        let joined = List::concat(lists);
+        assert_eq!(
+            joined.iter().copied().collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5, 6]
+        );
+        assert!(List::<i32>::concat([]).peek().is_none());
+    }
+
+    #[test]
+    fn remove() {
+        let mut list = List::new();
+        for elem in [3, 1, 3, 2, 3, 3] {
+            list.push_front(elem);
+        }
+        // list: 3 3 2 3 1 3
+
+        assert!(list.remove_first(&2));
+        assert!(!list.remove_first(&2));
+        assert!(list.remove_first(&3));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [3, 3, 1, 3]);
+
+        assert_eq!(list.remove_all(&3), 3);
+        assert_eq!(list.remove_all(&3), 0);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1]);
+        assert_eq!(list.remove_all(&1), 1);
+        assert!(list.peek().is_none());
+    }
+
+    #[test]
+    fn from_end() {
+        let mut list = List::new();
+        assert_eq!(list.from_end(0), None);
+        for elem in [4, 3, 2, 1] {
+            list.push_front(elem);
+        }
+
+        assert_eq!(list.from_end(0), Some(&4));
+        assert_eq!(list.from_end(3), Some(&1));
+        assert_eq!(list.from_end(4), None);
+
+        *list.from_end_mut(1).unwrap() = 30;
+        // at 0, the lead and the trailer stand on the same node the whole way
+        *list.from_end_mut(0).unwrap() = 40;
+        *list.from_end_mut(3).unwrap() = 10;
+        assert_eq!(list.from_end_mut(4), None);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [10, 2, 30, 40]);
+    }
+
+    #[test]
+    fn chunks() {
+        let mut list = List::new();
+        for elem in (1..=7).rev() {
+            list.push_front(elem);
+        }
+
+        let chunks: Vec<Vec<i32>> = list
+            .chunks(3)
+            .map(|chunk| chunk.iter().copied().collect())
+            .collect();
+        assert_eq!(chunks, [vec![1, 2, 3], vec![4, 5, 6], vec![7]]);
+        assert_eq!(List::<i32>::new().chunks(3).count(), 0);
+    }
+
+    #[test]
+    fn exact_sizes() {
+        let mut list = List::new();
+        assert_eq!(list.back(), None);
+        for elem in (1..=5).rev() {
+            list.push_front(elem);
+        }
+        list.insert_sorted(6);
+        assert_eq!(list.back(), Some(&6));
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 6);
+        iter.next();
+        assert_eq!(iter.size_hint(), (5, Some(5)));
+        assert_eq!(list.iter_mut().skip(2).len(), 4);
+
+        // every relinking op has to keep the count honest
+        let (front, back) = list.split_at(2);
+        assert_eq!((front.len(), back.len()), (2, 4));
+        let (odds, mut evens) = List::concat([front, back]).partition(|elem| elem % 2 == 1);
+        assert_eq!((odds.len(), evens.len()), (3, 3));
+        evens.interleave(odds);
+        assert!(evens.remove_first(&1));
+        assert_eq!(evens.remove_all(&3), 1);
+        let chunks: Vec<_> = evens.chunks(3).map(|chunk| chunk.len()).collect();
+        assert_eq!(chunks, [3, 1]);
+
+        let mut list = List::new();
+        list.push_front(1);
+        list.push_front(2);
+        list.pop_front();
+        let mut iter = list.into_iter();
+        assert_eq!(iter.len(), 1);
+        iter.next();
+        assert_eq!(iter.len(), 0);
+    }
+
+    #[test]
+    fn pop_front_if() {
+        let mut list = List::new();
+        assert_eq!(list.pop_front_if(|_| true), None);
+        list.push_front(1);
+        list.push_front(2);
+        assert_eq!(list.pop_front_if(|&elem| elem == 1), None);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.pop_front_if(|&elem| elem == 2), Some(2));
+        assert_eq!(list.pop_front_if(|&elem| elem == 1), Some(1));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn cursor() {
+        let mut list = List::new();
+        for elem in (1..=3).rev() {
+            list.push_front(elem);
+        }
+
+        let mut cursor = list.cursor_front();
+        assert_eq!((cursor.index(), cursor.current()), (Some(0), Some(&1)));
+        assert_eq!(cursor.peek_next(), Some(&2));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!((cursor.index(), cursor.current()), (Some(2), Some(&3)));
+        assert_eq!(cursor.peek_next(), None);
+        cursor.move_next();
+        assert_eq!((cursor.index(), cursor.current()), (None, None));
+        // the ghost is as far as it goes
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+
+        assert_eq!(List::<i32>::new().cursor_front().current(), None);
+    }
+
+    #[test]
+    fn cursor_mut() {
+        let mut list = List::new();
+        for elem in (1..=4).rev() {
+            list.push_front(elem);
+        }
+
+        let mut cursor = list.cursor_front_mut();
+        *cursor.current().unwrap() *= 10;
+        cursor.insert_after(15);
+        assert_eq!(cursor.peek_next(), Some(&mut 15));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(
+            (cursor.index(), cursor.remove_current()),
+            (Some(2), Some(2))
+        );
+        assert_eq!(cursor.current(), Some(&mut 3));
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(4));
+        // removing the last element leaves us at the ghost
+        assert_eq!((cursor.index(), cursor.remove_current()), (None, None));
+        cursor.insert_after(5);
+        cursor.insert_after(6);
+        assert_eq!(cursor.current(), None);
+
+        assert_eq!(list.len(), 5);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [10, 15, 3, 5, 6]);
+
+        // removing everything from the front
+        let mut cursor = list.cursor_front_mut();
+        while cursor.remove_current().is_some() {}
+        assert!(list.is_empty());
+        list.cursor_front_mut().insert_after(7);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [7]);
+    }
+
+    #[test]
+    fn find_cursor() {
+        let mut list = List::new();
+        for elem in (1..=5).rev() {
+            list.push_front(elem);
+        }
+
+        let found = list.find_cursor(|&elem| elem > 2);
+        assert_eq!((found.index(), found.current()), (Some(2), Some(&3)));
+        assert_eq!(list.find_cursor(|&elem| elem > 5).index(), None);
+
+        // find, then edit right there
+        let mut cursor = list.find_cursor_mut(|&elem| elem % 2 == 0);
+        assert_eq!(cursor.remove_current(), Some(2));
+        cursor.insert_after(30);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 3, 30, 4, 5]);
+
+        // no match: the ghost, where inserting appends
+        list.find_cursor_mut(|&elem| elem > 100).insert_after(6);
+        assert_eq!(list.back(), Some(&6));
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let mut stack = List::new();
+        stack.push_front(1);
+        stack.push_front(2);
+        let checkpoint = stack.snapshot();
+        assert_eq!(checkpoint.len(), 2);
+
+        // a step that goes wrong halfway through
+        stack.pop_front();
+        stack.push_front(20);
+        stack.push_front(30);
+        *stack.get_mut(2).unwrap() = 10;
+
+        stack.restore(checkpoint);
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack.iter().copied().collect::<Vec<_>>(), [2, 1]);
+
+        let empty = List::<i32>::new().snapshot();
+        assert!(empty.is_empty());
+        stack.restore(empty);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn middle() {
+        let mut list = List::new();
+        assert_eq!((list.middle(), list.middle_index()), (None, None));
+        for (len, middle) in [(1, 0), (2, 0), (3, 1), (4, 1), (5, 2)] {
+            list = List::new();
+            for elem in (0..len).rev() {
+                list.push_front(elem);
+            }
+            assert_eq!(list.middle(), Some(&middle));
+            assert_eq!(list.middle_index(), Some(middle));
+        }
+    }
+
+    #[test]
+    fn is_palindrome() {
+        for (elems, palindrome) in [
+            (&[][..], true),
+            (&[1], true),
+            (&[1, 1], true),
+            (&[1, 2], false),
+            (&[1, 2, 1], true),
+            (&[1, 2, 2, 1], true),
+            (&[1, 2, 3, 1], false),
+            (&[1, 2, 3, 2, 1], true),
+            (&[1, 2, 3, 1, 1], false),
+        ] {
+            let mut list = from(elems);
+            assert_eq!(list.is_palindrome(), palindrome, "{elems:?}");
+            // and put back just how it was
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), elems);
+            assert_eq!(list.len(), elems.len());
+        }
+    }
+
+    #[test]
+    fn is_palindrome_panicking_eq() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        // panics when asked about 0
+        #[derive(Debug)]
+        struct Touchy(i32);
+
+        impl PartialEq for Touchy {
+            fn eq(&self, other: &Self) -> bool {
+                assert!(self.0 != 0 && other.0 != 0, "touched");
+                self.0 == other.0
+            }
+        }
+
+        let mut list = List::new();
+        for elem in [1, 0, 3, 0, 1] {
+            list.push_front(Touchy(elem));
+        }
+        assert!(catch_unwind(AssertUnwindSafe(|| list.is_palindrome())).is_err());
+        // all five are back, so len still holds and chunks doesn't run off the end
+        assert!(list.iter().map(|elem| elem.0).eq([1, 0, 3, 0, 1]));
+        assert_eq!(
+            list.chunks(2).map(|chunk| chunk.len()).collect::<Vec<_>>(),
+            [2, 2, 1]
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let merged = from(&[1, 4, 6]).merge(from(&[2, 3, 7, 8]));
+        assert_eq!(
+            merged.iter().copied().collect::<Vec<_>>(),
+            [1, 2, 3, 4, 6, 7, 8]
+        );
+        assert_eq!(merged.len(), 7);
+        assert!(List::<i32>::merge_k(vec![]).is_empty());
+        let merged = List::merge_k(vec![from(&[5]), from(&[]), from(&[1, 9]), from(&[2, 5])]);
+        assert_eq!(merged.iter().copied().collect::<Vec<_>>(), [1, 2, 5, 5, 9]);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn merge_k_sorts(mut runs in proptest::collection::vec(
+            proptest::collection::vec(-20i32..20, 0..16),
+            0..8,
+        )) {
+            let lists = runs
+                .iter_mut()
+                .map(|run| {
+                    run.sort_unstable();
+                    let mut list = List::new();
+                    for &elem in run.iter().rev() {
+                        list.push_front(elem);
+                    }
+                    list
+                })
+                .collect();
+            let merged = List::merge_k(lists);
+
+            let mut expected = runs.concat();
+            expected.sort_unstable();
+            proptest::prop_assert_eq!(merged.len(), expected.len());
+            proptest::prop_assert!(merged.iter().eq(&expected));
+        }
+    }
+
+    #[test]
+    fn reverse_in_groups() {
+        for (k, reverse_rest, reversed) in [
+            (1, false, &[1, 2, 3, 4, 5, 6, 7][..]),
+            (2, false, &[2, 1, 4, 3, 6, 5, 7]),
+            (3, false, &[3, 2, 1, 6, 5, 4, 7]),
+            (3, true, &[3, 2, 1, 6, 5, 4, 7]),
+            (4, false, &[4, 3, 2, 1, 5, 6, 7]),
+            (4, true, &[4, 3, 2, 1, 7, 6, 5]),
+            (7, false, &[7, 6, 5, 4, 3, 2, 1]),
+            (10, false, &[1, 2, 3, 4, 5, 6, 7]),
+            (10, true, &[7, 6, 5, 4, 3, 2, 1]),
+        ] {
+            let mut list = List::new();
+            for elem in (1..=7).rev() {
+                list.push_front(elem);
+            }
+            list.reverse_in_groups(k, reverse_rest);
+            assert_eq!(
+                list.iter().copied().collect::<Vec<_>>(),
+                reversed,
+                "k = {k}"
+            );
+            assert_eq!(list.len(), 7);
+        }
+
+        let mut empty = List::<i32>::new();
+        empty.reverse_in_groups(3, true);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "group size must be non-zero")]
+    fn reverse_in_groups_of_zero() {
+        List::<i32>::new().reverse_in_groups(0, false);
+    }
+
+    #[test]
+    fn partition3() {
+        let mut list = List::new();
+        for elem in [5, 1, 9, 5, 3, 7, 5, 0].into_iter().rev() {
+            list.push_front(elem);
+        }
+
+        let (less, equal, greater) = list.partition3(&5);
+        assert_eq!(less.iter().copied().collect::<Vec<_>>(), [1, 3, 0]);
+        assert_eq!(equal.iter().copied().collect::<Vec<_>>(), [5, 5, 5]);
+        assert_eq!(greater.iter().copied().collect::<Vec<_>>(), [9, 7]);
+        assert_eq!((less.len(), equal.len(), greater.len()), (3, 3, 2));
+
+        let (less, equal, greater) = greater.partition3(&0);
+        assert_eq!((less.len(), equal.len(), greater.len()), (0, 0, 2));
+    }
+
+    #[test]
+    fn long_clear() {
+        // one stack frame per node would blow the stack here
+        let mut list = List::new();
+        for i in 0..1_000_000 {
+            list.push_front(i);
+        }
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.pop_front(), None);
+
+        list.push_front(1);
+        let taken = std::mem::take(&mut list);
+        assert_eq!((taken.len(), list.len()), (1, 0));
+    }
 }
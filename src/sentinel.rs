@@ -0,0 +1,210 @@
+// The unsafe queue from fifth.rs, rebuilt around a sentinel: a dummy node that's
+// always at the front, so the queue is never "really" empty and tail always
+// points at a node.
+//
+// fifth.rs's push checks whether there's a tail to link onto, and its pop checks
+// whether it just took the last node so it can null the tail. Here neither
+// happens. push always links onto tail. pop moves the first real node's elem out
+// and then promotes that node to be the new sentinel, freeing the old one — so
+// if it was the last node, tail is already pointing at the sentinel. The one
+// check left is pop's "is there anything at all?", which no queue can skip.
+//
+// The catch: the sentinel has to live on the heap (a sentinel inside the struct
+// would move whenever the queue does, leaving tail dangling), so `new` allocates
+// and can't be a `const fn` like fifth's. That's why this is its own type rather
+// than fifth::List's new insides. `cargo bench --bench sentinel` compares them.
+//
+// On one core, the branches it saves are worth about nothing on their own.
+// Filling and draining 1024 took ~48µs here against fifth's ~58µs, but 16 took
+// a shade longer (~608ns to ~587ns), and one-in-one-out was twice as slow (~21ns
+// to ~10ns): every pop frees a node and every push allocates one, where fifth
+// hands popped nodes back to its block allocator for the next push to reuse.
+
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+pub struct Queue<T> {
+    // its elem is never initialized
+    sentinel: *mut Node<T>,
+    // the last node, or the sentinel when we're empty
+    tail: *mut Node<T>,
+    // we own Ts, even though we only hold raw pointers to them
+    _elems: PhantomData<T>,
+}
+
+struct Node<T> {
+    elem: MaybeUninit<T>,
+    next: *mut Node<T>,
+}
+
+impl<T> Node<T> {
+    fn alloc(elem: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            elem,
+            next: ptr::null_mut(),
+        }))
+    }
+}
+
+impl<T> Queue<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        let sentinel = Node::alloc(MaybeUninit::uninit());
+        Self {
+            sentinel,
+            tail: sentinel,
+            _elems: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let new_tail = Node::alloc(MaybeUninit::new(elem));
+        unsafe { (*self.tail).next = new_tail };
+        self.tail = new_tail;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        unsafe {
+            let first = (*self.sentinel).next;
+            if first.is_null() {
+                return None;
+            }
+            let elem = (*first).elem.assume_init_read();
+            // `first` is the sentinel now, elem logically uninitialized again.
+            // MaybeUninit never drops its contents, so freeing the old sentinel
+            // doesn't touch an elem either.
+            drop(Box::from_raw(self.sentinel));
+            self.sentinel = first;
+            Some(elem)
+        }
+    }
+
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        unsafe {
+            (*self.sentinel)
+                .next
+                .as_ref()
+                .map(|node| node.elem.assume_init_ref())
+        }
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        unsafe {
+            (*self.sentinel)
+                .next
+                .as_mut()
+                .map(|node| node.elem.assume_init_mut())
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tail == self.sentinel
+    }
+
+    /// Drops every element. The sentinel stays.
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: unsafe { (*self.sentinel).next },
+            _queue: PhantomData,
+        }
+    }
+}
+
+// Unlike every other list's, this isn't free: the sentinel is allocated up front,
+// that being the whole point. So `mem::take` on one of these allocates.
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { drop(Box::from_raw(self.sentinel)) };
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: *mut Node<T>,
+    _queue: PhantomData<&'a Queue<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            self.next.as_ref().map(|node| {
+                self.next = node.next;
+                node.elem.assume_init_ref()
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Queue;
+    use std::rc::Rc;
+
+    #[test]
+    fn basics() {
+        let mut queue = Queue::new();
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+
+        // drained down to the sentinel and back up again
+        queue.push(4);
+        queue.push(5);
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+
+        queue.push(6);
+        assert!(!queue.is_empty());
+        assert_eq!(queue.pop(), Some(6));
+    }
+
+    #[test]
+    fn peek_and_iter() {
+        let mut queue = Queue::new();
+        assert_eq!(queue.peek(), None);
+        for elem in 1..=3 {
+            queue.push(elem);
+        }
+        assert_eq!(queue.peek(), Some(&1));
+        *queue.peek_mut().unwrap() = 10;
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [10, 2, 3]);
+    }
+
+    #[test]
+    fn drops_what_it_holds() {
+        let canary = Rc::new(());
+        {
+            let mut queue = Queue::new();
+            for _ in 0..5 {
+                queue.push(Rc::clone(&canary));
+            }
+            drop(queue.pop());
+            assert_eq!(Rc::strong_count(&canary), 5);
+        }
+        assert_eq!(Rc::strong_count(&canary), 1);
+    }
+}
@@ -0,0 +1,179 @@
+// second.rs's stack behind an `Arc<Mutex<..>>`, so it can be shared between
+// threads without everyone writing the same wrapper and the same
+// `.lock().unwrap()` at every call. Handles are cheap to clone and all point at
+// the same stack.
+//
+// Poisoning is reported rather than unwrapped: if a thread panics inside `with`
+// while holding the lock, every call after that gets `Err(Poisoned)` until
+// someone looks at the stack and calls `clear_poison`. The other methods never
+// run anything but the list's own code under the lock, so `with` is the only way
+// in for a panic (besides T's Drop).
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::second::{IntoIter, List};
+
+pub struct SharedList<T>(Arc<Mutex<List<T>>>);
+
+/// A thread panicked while it held the list's lock, so the list may not be
+/// what that thread meant to leave behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Poisoned;
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a thread panicked while holding the list's lock")
+    }
+}
+
+impl std::error::Error for Poisoned {}
+
+impl<T> SharedList<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(List::new())))
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, List<T>>, Poisoned> {
+        self.0.lock().map_err(|_| Poisoned)
+    }
+
+    pub fn push(&self, value: T) -> Result<(), Poisoned> {
+        self.lock()?.push_front(value);
+        Ok(())
+    }
+
+    pub fn pop(&self) -> Result<Option<T>, Poisoned> {
+        Ok(self.lock()?.pop_front())
+    }
+
+    pub fn len(&self) -> Result<usize, Poisoned> {
+        Ok(self.lock()?.len())
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Poisoned> {
+        Ok(self.lock()?.is_empty())
+    }
+
+    /// Takes everything, leaving the shared stack empty. O(1) under the lock: the
+    /// whole chain is swapped out, and it's up to you to walk it afterwards.
+    pub fn drain_all(&self) -> Result<List<T>, Poisoned> {
+        Ok(core::mem::take(&mut *self.lock()?))
+    }
+
+    /// Copies the stack under the lock and iterates the copy, top first. The
+    /// lock is only held for the copy, so this never sees half of a `with`, and
+    /// nobody waits on the iteration.
+    pub fn snapshot_iter(&self) -> Result<IntoIter<T>, Poisoned>
+    where
+        T: Clone,
+    {
+        let snapshot = self.lock()?.snapshot();
+        let mut copy = List::new();
+        copy.restore(snapshot);
+        Ok(copy.into_iter())
+    }
+
+    /// Runs `f` on the stack with the lock held, for anything the methods above
+    /// don't cover, or several steps that have to happen together.
+    pub fn with<R>(&self, f: impl FnOnce(&mut List<T>) -> R) -> Result<R, Poisoned> {
+        Ok(f(&mut *self.lock()?))
+    }
+
+    /// Declares the stack fine again after a panic poisoned it.
+    pub fn clear_poison(&self) {
+        self.0.clear_poison();
+    }
+}
+
+impl<T> Default for SharedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Another handle on the same stack.
+impl<T> Clone for SharedList<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Poisoned, SharedList};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    #[test]
+    fn handles_share_one_stack() {
+        let list = SharedList::new();
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let list = list.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        list.push(worker * 100 + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(list.len(), Ok(400));
+        let mut all: Vec<_> = list.drain_all().unwrap().into_iter().collect();
+        all.sort_unstable();
+        assert!(all.into_iter().eq(0..400));
+        assert_eq!(list.is_empty(), Ok(true));
+        assert_eq!(list.pop(), Ok(None));
+    }
+
+    // A writer that moves the whole stack along one value at a time, inside
+    // `with`: from [i] to [i + 1]. Snapshots only ever see one or the other.
+    #[test]
+    fn snapshots_never_see_half_a_with() {
+        let list = SharedList::new();
+        list.push(0).unwrap();
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    assert_eq!(list.snapshot_iter().unwrap().len(), 1);
+                }
+            });
+            for i in 0..10_000 {
+                list.with(|list| {
+                    list.push_front(i + 1);
+                    list.remove_first(&i);
+                })
+                .unwrap();
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        assert!(list.snapshot_iter().unwrap().eq([10_000]));
+    }
+
+    #[test]
+    fn poisoning() {
+        let list = SharedList::new();
+        list.push(1).unwrap();
+
+        let panicked = catch_unwind(AssertUnwindSafe(|| {
+            list.with(|list| {
+                list.push_front(2);
+                panic!("halfway through");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(list.pop(), Err(Poisoned));
+        assert_eq!(list.clone().push(3), Err(Poisoned));
+
+        // 2 went in before the panic; decide that's fine and carry on
+        list.clear_poison();
+        assert_eq!(list.pop(), Ok(Some(2)));
+    }
+}
@@ -0,0 +1,235 @@
+// "The Double Single": a deque made out of two of second.rs's stacks, stood back to back.
+//
+//     front stack          back stack
+//     top ... bottom  |  bottom ... top
+//     [1]  [2]  [3]   |   [4]  [5]  [6]
+//
+// Pushing or popping at either end is just a push or pop on that end's stack. The only hard
+// part is popping from an end whose stack has run dry: then we steal half of the other stack,
+// which flips it around in the process. Stealing half (not all) is what keeps this cheap —
+// whichever end you hammer next, there's a good chunk of elements waiting on that side.
+
+use crate::second::List as Stack;
+
+pub struct List<T> {
+    front: Stack<T>,
+    back: Stack<T>,
+    front_len: usize,
+    back_len: usize,
+    // how many elements rebalancing has shuffled around, so tests can check the amortized cost
+    #[cfg(test)]
+    moved: usize,
+}
+
+impl<T> List<T> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            front: Stack::new(),
+            back: Stack::new(),
+            front_len: 0,
+            back_len: 0,
+            #[cfg(test)]
+            moved: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.front_len + self.back_len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.front.clear();
+        self.back.clear();
+        self.front_len = 0;
+        self.back_len = 0;
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.front.push_front(elem);
+        self.front_len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.back.push_front(elem);
+        self.back_len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.front_len == 0 {
+            // round up, so a lone element still makes it over
+            let steal = self.back_len.div_ceil(2);
+            Self::steal(&mut self.back, &mut self.front, self.back_len, steal);
+            self.back_len -= steal;
+            self.front_len += steal;
+            #[cfg(test)]
+            {
+                self.moved += self.len();
+            }
+        }
+
+        let elem = self.front.pop_front()?;
+        self.front_len -= 1;
+        Some(elem)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.back_len == 0 {
+            let steal = self.front_len.div_ceil(2);
+            Self::steal(&mut self.front, &mut self.back, self.front_len, steal);
+            self.front_len -= steal;
+            self.back_len += steal;
+            #[cfg(test)]
+            {
+                self.moved += self.len();
+            }
+        }
+
+        let elem = self.back.pop_front()?;
+        self.back_len -= 1;
+        Some(elem)
+    }
+
+    // The two ends' stacks are each other's mirror image, so only their tops are easy to reach.
+    // Moving the bottom `count` elements of `from` over to `to` means digging them out:
+    //   1. park the top (from_len - count) elements on a spare stack (reversing them),
+    //   2. pop the rest over onto `to` (reversing them, which is exactly what we want),
+    //   3. pop the parked ones back (un-reversing them).
+    fn steal(from: &mut Stack<T>, to: &mut Stack<T>, from_len: usize, count: usize) {
+        let mut parked = Stack::new();
+        for _ in count..from_len {
+            if let Some(elem) = from.pop_front() {
+                parked.push_front(elem);
+            }
+        }
+        while let Some(elem) = from.pop_front() {
+            to.push_front(elem);
+        }
+        while let Some(elem) = parked.pop_front() {
+            from.push_front(elem);
+        }
+    }
+
+    /// Peeks at the front. O(1), unless the front stack is empty — then the front of the deque
+    /// is at the bottom of the back stack, and we have to walk there.
+    #[must_use]
+    pub fn peek_front(&self) -> Option<&T> {
+        self.front.peek().or_else(|| self.back.iter().last())
+    }
+
+    /// Same as [`List::peek_front`], but for the back.
+    #[must_use]
+    pub fn peek_back(&self) -> Option<&T> {
+        self.back.peek().or_else(|| self.front.iter().last())
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::List;
+
+    #[test]
+    fn basics() {
+        let mut list = List::new();
+        assert!(list.is_empty());
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.push_front(2);
+        list.push_front(1);
+        list.push_back(3);
+        list.push_back(4);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.peek_front(), Some(&1));
+        assert_eq!(list.peek_back(), Some(&4));
+
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(4));
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.pop_front(), Some(3));
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn rebalancing() {
+        // everything goes in one end and out the other, so every pop crosses over
+        let mut list = List::new();
+        for i in 0..5 {
+            list.push_back(i);
+        }
+        assert_eq!(list.peek_front(), Some(&0));
+        for i in 0..5 {
+            assert_eq!(list.pop_front(), Some(i));
+        }
+        assert_eq!(list.pop_front(), None);
+
+        for i in 0..5 {
+            list.push_front(i);
+        }
+        assert_eq!(list.peek_back(), Some(&0));
+        for i in 0..5 {
+            assert_eq!(list.pop_back(), Some(i));
+        }
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn single_element_crosses_over() {
+        let mut list = List::new();
+        list.push_back(1);
+        assert_eq!(list.pop_front(), Some(1));
+        list.push_front(2);
+        assert_eq!(list.pop_back(), Some(2));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn amortized_cost_one_sided() {
+        // Filling one end and draining the other: rebalances happen when the front runs dry,
+        // each moving the whole (shrinking) list, and each time half of it crosses. That's
+        // n + n/2 + n/4 + ... < 2n elements moved in total.
+        let n = 1 << 12;
+        let mut list = List::new();
+        for i in 0..n {
+            list.push_back(i);
+        }
+        for i in 0..n {
+            assert_eq!(list.pop_front(), Some(i));
+        }
+        assert!(list.moved <= 2 * n, "moved {} elements", list.moved);
+    }
+
+    #[test]
+    fn amortized_cost_alternating() {
+        // The nasty case for "steal everything": popping alternately from both ends. Stealing
+        // half leaves the other side with half to spare, so it stays linear overall.
+        let n = 1 << 12;
+        let mut list = List::new();
+        for i in 0..n {
+            list.push_back(i);
+        }
+        let (mut lo, mut hi) = (0, n);
+        while !list.is_empty() {
+            assert_eq!(list.pop_front(), Some(lo));
+            lo += 1;
+            if lo < hi {
+                hi -= 1;
+                assert_eq!(list.pop_back(), Some(hi));
+            }
+        }
+        assert!(list.moved <= 4 * n, "moved {} elements", list.moved);
+    }
+}
@@ -0,0 +1,204 @@
+// A linked list that lives entirely on the stack — no Box, no Rc, no allocator at all.
+//
+// Each node sits in some function's stack frame and borrows the node from the frame above it.
+// That means a node can only exist while the frames above it are still running, so the only
+// way to add a node is to hand over a callback: push makes the new node in its own frame, calls
+// you with it, and the node disappears when you return. The borrow checker makes sure nobody
+// holds onto a node after its frame is gone.
+//
+// Handy for things like walking a tree recursively while keeping the path from the root around.
+
+pub struct List<'a, T> {
+    pub data: T,
+    // The node from the frame above us; None at the top.
+    //
+    // The obvious type here is Option<&'a mut List<'a, T>>, but &mut makes List invariant in
+    // 'a: a List<'long, T> can't be passed off as a List<'short, T>. Each frame's borrow of the
+    // node above is shorter than that node's own 'a, so pushing a second frame just doesn't
+    // typecheck. Hiding the node above behind a trait object erases its lifetime from our type
+    // (all that's left is "lives at least as long as 'a"), and the problem goes away.
+    prev: Option<&'a mut dyn Frame<T>>,
+}
+
+// All a node needs to know about the node above it: its data and the node above *that*.
+trait Frame<T> {
+    fn get(&self) -> (&T, Option<&dyn Frame<T>>);
+    fn get_mut(&mut self) -> (&mut T, Option<&mut dyn Frame<T>>);
+}
+
+impl<T> Frame<T> for List<'_, T> {
+    fn get(&self) -> (&T, Option<&dyn Frame<T>>) {
+        (&self.data, self.prev.as_deref())
+    }
+
+    fn get_mut(&mut self) -> (&mut T, Option<&mut dyn Frame<T>>) {
+        // The `as` shortens the trait object's lifetime to match our borrow of it. It has to be
+        // spelled out: Rust won't coerce through the Option on its own.
+        let prev = self
+            .prev
+            .as_deref_mut()
+            .map(|prev| prev as &mut dyn Frame<T>);
+        (&mut self.data, prev)
+    }
+}
+
+impl<'a, T> List<'a, T> {
+    pub fn push<U>(
+        prev: Option<&'a mut List<'_, T>>,
+        data: T,
+        callback: impl FnOnce(&mut List<T>) -> U,
+    ) -> U {
+        let mut list = List {
+            data,
+            prev: prev.map(|prev| prev as &mut dyn Frame<T>),
+        };
+        callback(&mut list)
+    }
+
+    // Iteration goes from the newest node (ours) back up through the frames.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: Some(self) }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { next: Some(self) }
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a dyn Frame<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            let (data, prev) = node.get();
+            self.next = prev;
+            data
+        })
+    }
+}
+
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut dyn Frame<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // take() for the same reason as second.rs's IterMut: &mut isn't Copy
+        self.next.take().map(|node| {
+            let (data, prev) = node.get_mut();
+            self.next = prev;
+            data
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::List;
+    use std::cell::Cell;
+
+    #[test]
+    fn elegance() {
+        List::push(None, 3, |list| {
+            assert_eq!(list.iter().copied().sum::<i32>(), 3);
+            List::push(Some(list), 5, |list| {
+                assert_eq!(list.iter().copied().sum::<i32>(), 5 + 3);
+                List::push(Some(list), 13, |list| {
+                    assert_eq!(list.iter().copied().sum::<i32>(), 5 + 3 + 13);
+                });
+            });
+        });
+    }
+
+    #[test]
+    fn iter_order() {
+        List::push(None, 'a', |list| {
+            List::push(Some(list), 'b', |list| {
+                assert_eq!(list.iter().collect::<String>(), "ba");
+            });
+        });
+    }
+
+    #[test]
+    fn cell() {
+        List::push(None, Cell::new(3), |list| {
+            List::push(Some(list), Cell::new(5), |list| {
+                List::push(Some(list), Cell::new(13), |list| {
+                    // shared iteration, but Cell lets us change values anyway
+                    for val in list.iter() {
+                        val.set(val.get() * 10);
+                    }
+
+                    let mut vals = list.iter();
+                    assert_eq!(vals.next().unwrap().get(), 130);
+                    assert_eq!(vals.next().unwrap().get(), 50);
+                    assert_eq!(vals.next().unwrap().get(), 30);
+                    assert_eq!(vals.next(), None);
+                });
+            });
+        });
+    }
+
+    #[test]
+    fn iter_mut() {
+        List::push(None, 3, |list| {
+            List::push(Some(list), 5, |list| {
+                for val in list.iter_mut() {
+                    *val *= 10;
+                }
+
+                let mut vals = list.iter();
+                assert_eq!(vals.next(), Some(&50));
+                assert_eq!(vals.next(), Some(&30));
+                assert_eq!(vals.next(), None);
+            });
+        });
+    }
+
+    #[test]
+    fn recursion() {
+        // Collects every root-to-leaf path of a little tree, keeping the current path in
+        // stack frames as we recurse. No allocation until we decide to keep a path.
+        struct Tree {
+            label: char,
+            children: Vec<Tree>,
+        }
+
+        fn paths(tree: &Tree, path: Option<&mut List<char>>, out: &mut Vec<String>) {
+            List::push(path, tree.label, |path| {
+                if tree.children.is_empty() {
+                    // the list runs leaf-first, so flip it around
+                    out.push(path.iter().collect::<Vec<_>>().into_iter().rev().collect());
+                }
+                for child in &tree.children {
+                    paths(child, Some(&mut *path), out);
+                }
+            });
+        }
+
+        let leaf = |label| Tree {
+            label,
+            children: vec![],
+        };
+        let tree = Tree {
+            label: 'a',
+            children: vec![
+                Tree {
+                    label: 'b',
+                    children: vec![leaf('c'), leaf('d')],
+                },
+                leaf('e'),
+            ],
+        };
+
+        let mut out = Vec::new();
+        paths(&tree, None, &mut out);
+        assert_eq!(out, ["abc", "abd", "ae"]);
+    }
+}
@@ -0,0 +1,130 @@
+// Most lists are short. SmallList keeps its first N elements in an inline::List
+// living right inside the struct, and only starts allocating nodes once that's
+// full. Short lists never touch the heap; long ones pay for a Box per element
+// beyond N, same as ever.
+//
+// It's a stack, so the inline part holds the *oldest* N elements and the spill
+// stack sits on top. Pops drain the spill first, which keeps the invariant simple:
+// if anything has spilled, the inline part is full.
+
+use crate::{inline, second};
+
+pub struct SmallList<T, const N: usize> {
+    inline: inline::List<T, N>,
+    spill: second::List<T>,
+    spilled: usize,
+}
+
+impl<T, const N: usize> SmallList<T, N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            inline: inline::List::new(),
+            spill: second::List::new(),
+            spilled: 0,
+        }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        if self.spilled == 0 {
+            match self.inline.try_push(elem) {
+                Ok(()) => return,
+                // full: this one goes to the heap
+                Err(elem) => self.spill.push_front(elem),
+            }
+        } else {
+            self.spill.push_front(elem);
+        }
+        self.spilled += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match self.spill.pop_front() {
+            Some(elem) => {
+                self.spilled -= 1;
+                Some(elem)
+            }
+            None => self.inline.pop(),
+        }
+    }
+
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.spill.peek().or_else(|| self.inline.peek())
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.inline.len() + self.spilled
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.spill.clear();
+        self.inline.clear();
+        self.spilled = 0;
+    }
+
+    /// Whether any elements live on the heap.
+    #[must_use]
+    pub const fn is_spilled(&self) -> bool {
+        self.spilled != 0
+    }
+
+    /// Front to back: through the spilled nodes, then on into the inline ones.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.spill.iter().chain(self.inline.iter())
+    }
+}
+
+impl<T, const N: usize> Default for SmallList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SmallList;
+
+    #[test]
+    fn basics() {
+        let mut list: SmallList<i32, 2> = SmallList::new();
+        assert_eq!(list.pop(), None);
+
+        list.push(1);
+        list.push(2);
+        assert!(!list.is_spilled());
+        list.push(3);
+        list.push(4);
+        assert!(list.is_spilled());
+        assert_eq!(list.len(), 4);
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [4, 3, 2, 1]);
+        assert_eq!(list.peek(), Some(&4));
+
+        assert_eq!(list.pop(), Some(4));
+        assert_eq!(list.pop(), Some(3));
+        assert!(!list.is_spilled());
+        assert_eq!(list.peek(), Some(&2));
+
+        // the inline slot 2 leaves behind gets reused
+        assert_eq!(list.pop(), Some(2));
+        list.push(5);
+        assert!(!list.is_spilled());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [5, 1]);
+    }
+
+    #[test]
+    fn zero_inline_capacity_is_just_a_stack() {
+        let mut list: SmallList<i32, 0> = SmallList::new();
+        list.push(1);
+        assert!(list.is_spilled());
+        assert_eq!(list.pop(), Some(1));
+        assert!(list.is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+// With the `stats` feature on, every list keeps a tally of what it's been up to,
+// so the implementations can be compared by numbers instead of vibes.
+//
+// The counters are atomics rather than Cells: iterators only get a shared borrow
+// of the list but still need to bump `steps`, and a Cell would quietly make the
+// Box-based stacks !Sync.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A snapshot of a list's counters, as returned by `list.stats()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub pushes: usize,
+    pub pops: usize,
+    /// Nodes allocated. None of our lists recycle nodes, so this tracks `pushes`.
+    pub allocations: usize,
+    /// Existing nodes shared with another list instead of allocated anew.
+    pub reuses: usize,
+    /// Links followed by iterators and by the walks that look for a position
+    /// (`split_at`, `from_end`, ...) — one pointer dereference per step. The O(1)
+    /// operations never add to it, which the latency tests lean on.
+    pub steps: usize,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    pushes: AtomicUsize,
+    pops: AtomicUsize,
+    allocations: AtomicUsize,
+    reuses: AtomicUsize,
+    steps: AtomicUsize,
+}
+
+impl Counters {
+    pub(crate) const fn new() -> Self {
+        Self::from_stats(Stats {
+            pushes: 0,
+            pops: 0,
+            allocations: 0,
+            reuses: 0,
+            steps: 0,
+        })
+    }
+
+    // The persistent list hands its history down to every list made from it.
+    pub(crate) const fn from_stats(stats: Stats) -> Self {
+        Self {
+            pushes: AtomicUsize::new(stats.pushes),
+            pops: AtomicUsize::new(stats.pops),
+            allocations: AtomicUsize::new(stats.allocations),
+            reuses: AtomicUsize::new(stats.reuses),
+            steps: AtomicUsize::new(stats.steps),
+        }
+    }
+
+    // Every push allocates exactly one node.
+    pub(crate) fn push(&self) {
+        self.pushes.fetch_add(1, Ordering::Relaxed);
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn pop(&self) {
+        self.pops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reuse(&self) {
+        self.reuses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn step(&self) {
+        self.steps.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // A list that takes over another's nodes takes over its history with them, so
+    // what the pieces did still shows on the whole.
+    pub(crate) fn absorb(&self, other: &Self) {
+        let other = other.snapshot();
+        self.pushes.fetch_add(other.pushes, Ordering::Relaxed);
+        self.pops.fetch_add(other.pops, Ordering::Relaxed);
+        self.allocations
+            .fetch_add(other.allocations, Ordering::Relaxed);
+        self.reuses.fetch_add(other.reuses, Ordering::Relaxed);
+        self.steps.fetch_add(other.steps, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> Stats {
+        Stats {
+            pushes: self.pushes.load(Ordering::Relaxed),
+            pops: self.pops.load(Ordering::Relaxed),
+            allocations: self.allocations.load(Ordering::Relaxed),
+            reuses: self.reuses.load(Ordering::Relaxed),
+            steps: self.steps.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,284 @@
+// proptest strategies for the lists, so code taking them can be property-tested
+// with the lists themselves as input, not a Vec turned into one in every test.
+//
+//     proptest! {
+//         #[test]
+//         fn sum_is_order_independent(list in list_of::<second::List<_>, _>(any::<i32>(), 0..1000)) {
+//             ...
+//         }
+//     }
+//
+// Shrinking works on the list's shape before its contents: a failing list first
+// loses nodes off the front, one at a time, for as long as it keeps failing,
+// then off the back, and only then are the remaining elements shrunk in turn.
+// Taking from the ends (rather than anywhere, as Vec's shrinker does) keeps the
+// remaining nodes in the order and relative positions they failed in.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+use proptest::collection::SizeRange;
+use proptest::strategy::{NewTree, Strategy, ValueTree};
+use proptest::test_runner::TestRunner;
+
+use crate::{fifth, first, fourth, second, third};
+
+/// The lists [`list_of`] can build, front to back from their elements.
+pub trait FromElems<T> {
+    fn from_elems(elems: Vec<T>) -> Self;
+}
+
+impl<T> FromElems<T> for first::List<T> {
+    fn from_elems(elems: Vec<T>) -> Self {
+        let mut list = Self::new();
+        for elem in elems.into_iter().rev() {
+            list.push_front(elem);
+        }
+        list
+    }
+}
+
+impl<T> FromElems<T> for second::List<T> {
+    fn from_elems(elems: Vec<T>) -> Self {
+        let mut list = Self::new();
+        for elem in elems.into_iter().rev() {
+            list.push_front(elem);
+        }
+        list
+    }
+}
+
+impl<T> FromElems<T> for third::List<T> {
+    fn from_elems(elems: Vec<T>) -> Self {
+        elems
+            .into_iter()
+            .rev()
+            .fold(Self::new(), |list, elem| list.prepend(elem))
+    }
+}
+
+impl<T> FromElems<T> for fourth::List<T> {
+    fn from_elems(elems: Vec<T>) -> Self {
+        let mut list = Self::new();
+        list.extend_back(elems);
+        list
+    }
+}
+
+impl<T> FromElems<T> for fifth::List<T> {
+    fn from_elems(elems: Vec<T>) -> Self {
+        let mut list = Self::new();
+        for elem in elems {
+            list.push(elem);
+        }
+        list
+    }
+}
+
+/// Lists of type `L` with elements from `element`, and a length in `size`.
+pub fn list_of<L, S>(element: S, size: impl Into<SizeRange>) -> ListStrategy<S, L>
+where
+    S: Strategy,
+    L: FromElems<S::Value> + fmt::Debug,
+{
+    ListStrategy {
+        element,
+        size: size.into(),
+        _list: PhantomData,
+    }
+}
+
+#[must_use = "strategies do nothing unless used"]
+pub struct ListStrategy<S, L> {
+    element: S,
+    size: SizeRange,
+    _list: PhantomData<fn() -> L>,
+}
+
+impl<S: fmt::Debug, L> fmt::Debug for ListStrategy<S, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ListStrategy")
+            .field("element", &self.element)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl<S, L> Strategy for ListStrategy<S, L>
+where
+    S: Strategy,
+    L: FromElems<S::Value> + fmt::Debug,
+{
+    type Tree = ListValueTree<S::Tree, L>;
+    type Value = L;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let len = (self.size.start()..self.size.end_excl())
+            .new_tree(runner)?
+            .current();
+        let elems = (0..len)
+            .map(|_| self.element.new_tree(runner))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ListValueTree {
+            end: elems.len(),
+            elems,
+            start: 0,
+            min_len: self.size.start(),
+            front_done: false,
+            back_done: false,
+            shrinking: 0,
+            last: None,
+            _list: PhantomData,
+        })
+    }
+}
+
+// What the last `simplify` did, so `complicate` knows what to put back.
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Front,
+    Back,
+    Elem(usize),
+}
+
+pub struct ListValueTree<V, L> {
+    elems: Vec<V>,
+    // the list is elems[start..end]
+    start: usize,
+    end: usize,
+    min_len: usize,
+    // set once taking another node off that end made the test pass
+    front_done: bool,
+    back_done: bool,
+    // the element being shrunk, once the ends are done
+    shrinking: usize,
+    last: Option<Step>,
+    _list: PhantomData<fn() -> L>,
+}
+
+impl<V: fmt::Debug, L> fmt::Debug for ListValueTree<V, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ListValueTree")
+            .field("elems", &&self.elems[self.start..self.end])
+            .finish_non_exhaustive()
+    }
+}
+
+impl<V, L> ValueTree for ListValueTree<V, L>
+where
+    V: ValueTree,
+    L: FromElems<V::Value> + fmt::Debug,
+{
+    type Value = L;
+
+    fn current(&self) -> L {
+        L::from_elems(
+            self.elems[self.start..self.end]
+                .iter()
+                .map(V::current)
+                .collect(),
+        )
+    }
+
+    fn simplify(&mut self) -> bool {
+        let shorter = self.end - self.start > self.min_len;
+        if shorter && !self.front_done {
+            self.start += 1;
+            self.last = Some(Step::Front);
+            return true;
+        }
+        if shorter && !self.back_done {
+            self.end -= 1;
+            self.last = Some(Step::Back);
+            return true;
+        }
+        self.shrinking = self.shrinking.max(self.start);
+        while self.shrinking < self.end {
+            if self.elems[self.shrinking].simplify() {
+                self.last = Some(Step::Elem(self.shrinking));
+                return true;
+            }
+            self.shrinking += 1;
+        }
+        self.last = None;
+        false
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self.last.take() {
+            Some(Step::Front) => {
+                self.start -= 1;
+                self.front_done = true;
+                true
+            }
+            Some(Step::Back) => {
+                self.end += 1;
+                self.back_done = true;
+                true
+            }
+            Some(Step::Elem(index)) if self.elems[index].complicate() => {
+                self.last = Some(Step::Elem(index));
+                true
+            }
+            Some(Step::Elem(_)) | None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::list_of;
+    use crate::{fifth, fourth, second, third};
+    use proptest::prelude::*;
+    use proptest::test_runner::{Config, TestError, TestRunner};
+
+    proptest! {
+        #[test]
+        fn lengths_in_range(
+            list in list_of::<second::List<_>, _>(any::<u8>(), 3..10),
+            queue in list_of::<fifth::List<_>, _>(any::<u8>(), 0..=4),
+        ) {
+            prop_assert!((3..10).contains(&list.len()));
+            prop_assert!(queue.iter().count() <= 4);
+        }
+    }
+
+    // Fails on any list with an element of 5 or more. Nodes come off the front
+    // up to the last such element, then off the back after it, and that one
+    // element shrinks down to 5.
+    #[test]
+    fn shrinks_from_both_ends() {
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let result = runner.run(&list_of::<fourth::List<_>, _>(0..10u8, 0..100), |list| {
+            prop_assert!(list.into_iter().all(|elem| elem < 5));
+            Ok(())
+        });
+        let Err(TestError::Fail(_, minimal)) = result else {
+            panic!("expected a failure, got {result:?}");
+        };
+        assert!(minimal.into_iter().eq([5]));
+    }
+
+    #[test]
+    fn respects_the_minimum_length() {
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let result = runner.run(&list_of::<third::List<_>, _>(any::<i32>(), 2..50), |_| {
+            prop_assert!(false);
+            Ok(())
+        });
+        let Err(TestError::Fail(_, minimal)) = result else {
+            panic!("expected a failure, got {result:?}");
+        };
+        // two zeroes: as short as allowed, and every element shrunk
+        assert_eq!(minimal.head(), Some(&0));
+        assert_eq!(minimal.tail().head(), Some(&0));
+        assert_eq!(minimal.tail().tail().head(), None);
+    }
+}
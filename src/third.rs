@@ -1,64 +1,317 @@
-use std::rc::Rc;
+use crate::generic::{self, GenericList, RcPtr};
+use alloc::rc::Rc;
+#[cfg(feature = "std")]
+use alloc::rc::Weak;
 
-pub struct List<T> {
-    head: Link<T>,
+// The persistent list is generic.rs's list with Rc links: prepend, head, tail,
+// the runners and Drop live there, shared with second.rs (and with the Arc
+// version, generic::SyncPersistent). What's here is Rc's alone.
+pub type List<T> = GenericList<T, RcPtr>;
+pub type Node<T> = generic::Node<T, RcPtr>;
+type Link<T> = generic::Link<T, RcPtr>;
+
+// Rc is a non-null pointer too, so None is free. (The counts live in the Rc's
+// allocation, not in the link.)
+const _: () = assert!(size_of::<Link<u64>>() == size_of::<usize>());
+const _: () = assert!(size_of::<Node<u64>>() == size_of::<(u64, usize)>());
+const _: () = assert!(align_of::<Node<u64>>() == align_of::<(u64, usize)>());
+
+// Just the elements, like a Vec. The structure (what's shared) is viz's job.
+impl<T: core::fmt::Debug> core::fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let nodes = core::iter::successors(self.head.as_deref(), |node| node.next.as_deref());
+        f.debug_list()
+            .entries(nodes.map(|node| &node.value))
+            .finish()
+    }
 }
 
-pub struct Node<T> {
-    value: T,
-    next: Link<T>,
+/// What a set of lists costs on the heap, from [`List::memory_usage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryUsage {
+    /// Distinct nodes across all the lists.
+    pub nodes: usize,
+    /// What those nodes take, Rc counts included. Anything the elements own
+    /// themselves (a String's buffer, say) isn't counted.
+    pub bytes: usize,
+    /// What the same lists would take if none of them shared a node.
+    pub unshared_bytes: usize,
 }
 
-type Link<T> = Option<Rc<Node<T>>>;
+// The allocation behind an Rc: std's is repr(C) with the two counts first, so
+// this has the same size.
+#[repr(C)]
+pub(crate) struct RcBox<T> {
+    strong: usize,
+    weak: usize,
+    value: T,
+}
 
 impl<T> List<T> {
+    /// How many distinct nodes the lists hold between them, counting each shared
+    /// node once.
     #[must_use]
-    pub const fn new() -> Self {
-        Self { head: Link::None }
+    pub fn reachable_nodes<'a>(lists: impl IntoIterator<Item = &'a Self>) -> usize
+    where
+        T: 'a,
+    {
+        Self::memory_usage(lists).nodes
     }
 
-    /// Prepends an element to the existing list.
-    /// I think this is synonymous with a `push_front`.
+    /// Tallies up the distinct nodes, by address, and what sharing them saves.
+    /// Whatever a list shares with one already counted is a suffix, so each
+    /// list is only checked against the others up to there.
     #[must_use]
-    pub fn prepend(&self, elem: T) -> Self {
+    pub fn memory_usage<'a>(lists: impl IntoIterator<Item = &'a Self>) -> MemoryUsage
+    where
+        T: 'a,
+    {
+        let node_bytes = size_of::<RcBox<Node<T>>>();
+        let mut seen = alloc::collections::BTreeSet::new();
+        let mut usage = MemoryUsage::default();
+        for list in lists {
+            let mut shared = false;
+            for node in core::iter::successors(list.head.as_ref(), |node| node.next.as_ref()) {
+                usage.unshared_bytes += node_bytes;
+                shared = shared || !seen.insert(Rc::as_ptr(node));
+                if !shared {
+                    usage.nodes += 1;
+                    usage.bytes += node_bytes;
+                }
+            }
+        }
+        usage
+    }
+}
+
+// Hash-consing. Two lists built from the same values share nothing unless one
+// was made from the other, so a program keeping many equal snapshots keeps many
+// copies of the same nodes. An Interner remembers every node it has made, by
+// value and tail, and `prepend_interned` hands back the node it already has
+// rather than making an identical one. Since the tail is part of the key, equal
+// lists built through the same interner end up as the very same nodes, and
+// comparing them is a pointer check.
+//
+// The interner only holds its nodes weakly, so it never keeps a list alive. The
+// entries for nodes that have gone do hang about (and so does the Rc allocation
+// behind a Weak), until `purge` clears them out.
+
+// the value's hash and the tail's address
+#[cfg(feature = "std")]
+type InternKey<T> = (u64, *const Node<T>);
+
+/// Remembers the nodes made by [`List::prepend_interned`], to reuse them.
+#[cfg(feature = "std")]
+pub struct Interner<T> {
+    // several values can share a hash, hence the Vec
+    buckets: std::collections::HashMap<InternKey<T>, alloc::vec::Vec<Weak<Node<T>>>>,
+    hasher: std::hash::RandomState,
+    hits: usize,
+    misses: usize,
+}
+
+/// How well an [`Interner`] is doing.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InternerStats {
+    /// Prepends that found their node already made.
+    pub hits: usize,
+    /// Prepends that had to make a new one.
+    pub misses: usize,
+    /// Nodes remembered, including any that have since gone and not been purged.
+    pub entries: usize,
+}
+
+#[cfg(feature = "std")]
+impl<T> Interner<T> {
+    #[must_use]
+    pub fn new() -> Self {
         Self {
-            head: Some(Rc::new(Node {
-                value: elem,
-                next: self.head.clone(),
-            })),
+            buckets: std::collections::HashMap::new(),
+            hasher: std::hash::RandomState::new(),
+            hits: 0,
+            misses: 0,
         }
     }
 
     #[must_use]
-    pub fn head(&self) -> Option<&T> {
-        self.head.as_ref().map(|n| &n.value)
+    pub fn stats(&self) -> InternerStats {
+        InternerStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.buckets.values().map(alloc::vec::Vec::len).sum(),
+        }
+    }
+
+    /// Forgets the nodes no list holds any more. Returns how many went.
+    pub fn purge(&mut self) -> usize {
+        let mut purged = 0;
+        self.buckets.retain(|_, bucket| {
+            let before = bucket.len();
+            bucket.retain(|node| node.strong_count() > 0);
+            purged += before - bucket.len();
+            !bucket.is_empty()
+        });
+        purged
+    }
+
+    /// Forgets everything. Lists already made keep their nodes; new ones just
+    /// won't share with them.
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::hash::Hash + Eq> Interner<T> {
+    fn node(&mut self, value: T, next: Link<T>) -> Rc<Node<T>> {
+        use std::hash::BuildHasher;
+
+        // A live entry with this tail address really does have this tail: the
+        // entry's node holds the tail, so it can't have been freed and reused.
+        let tail = next.as_ref().map_or(core::ptr::null(), Rc::as_ptr);
+        let bucket = self
+            .buckets
+            .entry((self.hasher.hash_one(&value), tail))
+            .or_default();
+        bucket.retain(|node| node.strong_count() > 0);
+        if let Some(node) = bucket
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|node| node.value == value)
+        {
+            self.hits += 1;
+            return node;
+        }
+        self.misses += 1;
+        let node = Rc::new(Node { value, next });
+        bucket.push(Rc::downgrade(&node));
+        node
     }
+}
 
+#[cfg(feature = "std")]
+impl<T> Default for Interner<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::hash::Hash + Eq> List<T> {
+    /// Like `prepend`, but if `interner` already made a node with this value in
+    /// front of this tail, the new list starts with that node.
     #[must_use]
-    pub fn tail(&self) -> Self {
+    pub fn prepend_interned(&self, elem: T, interner: &mut Interner<T>) -> Self {
+        let next = self.head.clone();
+        #[cfg(feature = "stats")]
+        let stats = crate::stats::Counters::from_stats(self.stats.snapshot());
+        #[cfg(feature = "stats")]
+        {
+            stats.push();
+            if next.is_some() {
+                stats.reuse();
+            }
+        }
         Self {
-            head: self.head.as_ref().and_then(|node| node.next.clone()),
+            head: Some(interner.node(elem, next)),
+            len: self.len + 1,
+            #[cfg(feature = "stats")]
+            stats,
+        }
+    }
+
+    /// Whether the two lists are the very same nodes, which for lists built
+    /// through one interner is the same as being equal. O(1).
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
         }
     }
 }
 
-// We're self-implementing Drop since we have lots of Box<Node>, which does NOT
-// drop using tail recursion; each drop will create a new stack frame.
-impl<T> Drop for List<T> {
-    fn drop(&mut self) {
-        let mut head = self.head.take();
-        while let Some(node) = head {
-            // drop nodes until there is one owned by another list
-            if let Ok(mut node) = Rc::try_unwrap(node) {
-                head = node.next.take();
-            } else {
-                break;
-            }
+// Same deal as the stacks: serialize front to back, deserialize by prepending back-to-front.
+// Deserializing always produces a fresh, unshared list.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let nodes = || std::iter::successors(self.head.as_deref(), |node| node.next.as_deref());
+
+        // Some formats (bincode) insist on knowing the length up front, so we walk twice.
+        let mut seq = serializer.serialize_seq(Some(nodes().count()))?;
+        for node in nodes() {
+            seq.serialize_element(&node.value)?;
         }
+        seq.end()
     }
 }
 
-fn main() {}
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let elems = Vec::<T>::deserialize(deserializer)?;
+        Ok(elems
+            .into_iter()
+            .rev()
+            .fold(Self::new(), |list, elem| list.prepend(elem)))
+    }
+}
+
+#[cfg(feature = "viz")]
+impl<T: std::fmt::Debug> List<T> {
+    fn graph(&self) -> crate::viz::Graph {
+        let mut graph = crate::viz::Graph::new();
+        for node in std::iter::successors(self.head.as_ref(), |node| node.next.as_ref()) {
+            graph.rc_node(Rc::as_ptr(node), &node.value, Rc::strong_count(node));
+            if let Some(next) = &node.next {
+                graph.edge(Rc::as_ptr(node), Rc::as_ptr(next), "next");
+            }
+        }
+        if let Some(head) = &self.head {
+            graph.marker("head", Rc::as_ptr(head));
+        }
+
+        // Everything after a node with other owners is reachable from those owners too, so the
+        // part of a persistent list that's shared is always a suffix.
+        let shared = std::iter::successors(self.head.as_ref(), |node| node.next.as_ref())
+            .skip_while(|node| Rc::strong_count(node) == 1)
+            .map(Rc::as_ptr);
+        graph.group("shared", shared);
+        graph
+    }
+
+    /// Dumps the list's structure as a Graphviz DOT graph. Each node is labelled with its
+    /// Rc strong count, and the suffix shared with other lists is boxed off.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        self.graph().to_dot()
+    }
+
+    /// Same as [`List::to_dot`], but as a Mermaid `graph LR` diagram, ready to paste into
+    /// markdown.
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        self.graph().to_mermaid()
+    }
+
+    /// Draws the list as ASCII art, e.g. `HEAD -> [2] -> [5] -> ∅`.
+    #[must_use]
+    pub fn render_ascii(&self) -> String {
+        self.graph().to_ascii()
+    }
+
+    /// Same as [`List::to_dot`], but as JSON, for drawing somewhere other than a
+    /// terminal (examples/wasm-viz draws it in a browser).
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        self.graph().to_json()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -82,4 +335,195 @@ mod tests {
         let list = list.tail();
         assert_eq!(list.head(), None);
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn interning() {
+        use super::{Interner, InternerStats};
+
+        let mut interner = Interner::new();
+        let build = |interner: &mut Interner<i32>, elems: &[i32]| {
+            elems.iter().rev().fold(List::new(), |list, &elem| {
+                list.prepend_interned(elem, interner)
+            })
+        };
+
+        let a = build(&mut interner, &[1, 2, 3]);
+        let b = build(&mut interner, &[1, 2, 3]);
+        assert!(a.ptr_eq(&b));
+        // same tail, different value: only the tail is shared
+        let c = build(&mut interner, &[7, 2, 3]);
+        assert!(!a.ptr_eq(&c));
+        assert!(a.tail().ptr_eq(&c.tail()));
+        // same value, different tail
+        let d = build(&mut interner, &[1, 2]);
+        assert!(!a.ptr_eq(&d));
+        assert_eq!(
+            interner.stats(),
+            InternerStats {
+                hits: 5,
+                misses: 6,
+                entries: 6
+            }
+        );
+
+        // the interner doesn't keep anything alive
+        drop((a, b, d));
+        assert_eq!(interner.purge(), 3);
+        assert_eq!(interner.stats().entries, 3);
+        assert_eq!(c.head(), Some(&7));
+
+        interner.clear();
+        let e = build(&mut interner, &[7, 2, 3]);
+        assert!(!c.ptr_eq(&e));
+        assert_eq!(interner.stats().entries, 3);
+    }
+
+    #[test]
+    fn memory_usage() {
+        use super::{MemoryUsage, Node, RcBox};
+
+        let a = List::new().prepend(3).prepend(2).prepend(1);
+        let b = a.prepend(0);
+        let c = a.tail().prepend(9);
+        let node = size_of::<RcBox<Node<i32>>>();
+        // a's three nodes, b's 0 and c's 9
+        assert_eq!(
+            List::memory_usage([&a, &b, &c]),
+            MemoryUsage {
+                nodes: 5,
+                bytes: 5 * node,
+                unshared_bytes: 10 * node,
+            }
+        );
+        assert_eq!(List::reachable_nodes([&c]), 3);
+        assert_eq!(List::<i32>::reachable_nodes([]), 0);
+        // on 64-bit: two counts, the i32 (padded) and the link
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(node, 32);
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn to_dot() {
+        use crate::viz::dot_id;
+
+        let list = List::new().prepend(5);
+        let dot = list.to_dot();
+        assert!(dot.contains("[label=\"5\\nstrong=1\"]"));
+
+        // sharing the tail bumps its strong count
+        let shared = list.prepend(2);
+        let dot = shared.to_dot();
+        let (two, five) = (dot_id(&dot, "2"), dot_id(&dot, "5"));
+        assert!(dot.contains("[label=\"5\\nstrong=2\"]"));
+        assert!(dot.contains(&format!("head -> {two};")));
+        assert!(dot.contains(&format!("{two} -> {five} [label=\"next\"];")));
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn to_mermaid_shared_suffix() {
+        use crate::viz::mermaid_id;
+
+        let suffix = List::new().prepend(3).prepend(2);
+        assert!(!suffix.to_mermaid().contains("subgraph"));
+
+        // a = 1 -> 2 -> 3 and b = 0 -> 2 -> 3 share the 2 -> 3 suffix
+        let a = suffix.prepend(1);
+        let b = suffix.prepend(0);
+        drop(suffix);
+
+        let mermaid = a.to_mermaid();
+        let (one, two, three) = (
+            mermaid_id(&mermaid, "1"),
+            mermaid_id(&mermaid, "2"),
+            mermaid_id(&mermaid, "3"),
+        );
+        assert!(mermaid.contains(&format!("{one}[\"1<br>strong=1\"]")));
+        assert!(mermaid.contains(&format!("{two}[\"2<br>strong=2\"]")));
+        assert!(mermaid.contains(&format!("head([head]) --> {one}")));
+        assert!(mermaid.contains(&format!(
+            "    subgraph shared\n        {two}\n        {three}\n    end\n"
+        )));
+
+        // b sees the very same nodes as shared
+        let mermaid = b.to_mermaid();
+        assert_eq!(mermaid_id(&mermaid, "2"), two);
+        assert!(mermaid.contains(&format!(
+            "    subgraph shared\n        {two}\n        {three}\n    end\n"
+        )));
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn trace() {
+        let log = crate::trace::capture(|| {
+            let list = List::new().prepend(1).prepend(2);
+            let _tail = list.tail();
+        });
+        assert_eq!(
+            log,
+            [
+                "clone head as next",
+                "clone head as next",
+                "clone next as head"
+            ]
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats() {
+        let list = List::new().prepend(1).prepend(2);
+        let stats = list.stats();
+        assert_eq!((stats.pushes, stats.allocations), (2, 2));
+        // the second prepend shares the first node
+        assert_eq!(stats.reuses, 1);
+
+        let tail = list.tail();
+        let stats = tail.stats();
+        assert_eq!((stats.pushes, stats.pops, stats.reuses), (2, 1, 2));
+
+        // the original doesn't hear about what its descendants did
+        assert_eq!(list.stats().pops, 0);
+    }
+
+    #[test]
+    fn from_end() {
+        let list = List::new();
+        assert_eq!(list.from_end(0), None);
+
+        let list = list.prepend(3).prepend(2).prepend(1);
+        assert_eq!(list.from_end(0), Some(&3));
+        assert_eq!(list.from_end(2), Some(&1));
+        assert_eq!(list.from_end(3), None);
+        // the tail shares the same end
+        assert_eq!(list.tail().from_end(0), Some(&3));
+    }
+
+    #[test]
+    fn middle() {
+        let mut list = List::new();
+        assert_eq!((list.middle(), list.middle_index()), (None, None));
+        for (elem, middle) in [(1, 1), (2, 2), (3, 2), (4, 3), (5, 3)] {
+            list = list.prepend(elem);
+            // the list reads elem, elem - 1, ..., 1
+            assert_eq!(list.middle(), Some(&middle));
+            assert_eq!(list.middle_index(), Some(elem - middle));
+        }
+    }
+
+    #[test]
+    fn long_clear() {
+        let mut list = List::new();
+        for i in 0..1_000_000 {
+            list = list.prepend(i);
+        }
+        let shared = list.tail();
+        list.clear();
+        assert_eq!(list.head(), None);
+        // the nodes `shared` still points at weren't ours to free
+        assert_eq!(shared.head(), Some(&999_998));
+    }
 }
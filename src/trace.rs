@@ -0,0 +1,67 @@
+// Narrates every pointer shuffle as a `tracing` event, so you can watch the
+// book's `take()`s and relinks happen at runtime. Each event's target is the
+// module it came from (`too_many_linked_lists::fourth`, ...), so a subscriber
+// filter can zoom in on a single chapter.
+//
+// Without the `trace` feature the macro expands to nothing: the lists pay zero
+// cost for being chatty.
+
+#[cfg(feature = "trace")]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        ::tracing::trace!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {};
+}
+
+pub(crate) use trace;
+
+// A tiny subscriber that writes down every event's message, for asserting on
+// the narration in tests.
+#[cfg(all(test, feature = "trace"))]
+pub(crate) fn capture(f: impl FnOnce()) -> Vec<String> {
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    struct Message<'a>(&'a mut String);
+
+    impl Visit for Message<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if field.name() == "message" {
+                *self.0 = format!("{value:?}");
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut message = String::new();
+            event.record(&mut Message(&mut message));
+            self.0.lock().unwrap().push(message);
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    tracing::subscriber::with_default(Recorder(Arc::clone(&log)), f);
+    let log = log.lock().unwrap();
+    log.clone()
+}
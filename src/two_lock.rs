@@ -0,0 +1,332 @@
+// Michael and Scott's two-lock queue: a linked queue shared between threads with
+// one lock per end, so a thread pushing at the back never waits on one popping
+// at the front. Wrapping a whole list in a single Mutex makes every push and
+// every pop take turns; here they only take turns with their own kind.
+//
+// The trick is a dummy node at the front, as in sentinel.rs. The head lock owns
+// `head` (always the dummy), the tail lock owns `tail`, and the two ends never
+// share a pointer they both write. Even a queue of one element is fine: the
+// pusher writes the last node's `next`, the popper only reads the dummy's, and
+// with one element those are the same node, so that link is an atomic. Popping
+// moves the first real node's value out and makes that node the new dummy.
+//
+// It's a queue, not a full deque: pushing at the front or popping at the back
+// would have both ends writing the same links again, and popping at the back
+// would need prev pointers on top.
+//
+// `snapshot_iter` is the one place that takes both locks (head first, always),
+// which stops the queue dead long enough to copy it. Under loom the locks are
+// loom's, so the model checker sees every way a snapshot can interleave with a
+// push or a pop:
+//
+//     RUSTFLAGS="--cfg loom" cargo test --release --features parking_lot two_lock
+
+use alloc::boxed::Box;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+#[cfg(not(loom))]
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+#[cfg(not(loom))]
+use parking_lot::Mutex;
+
+use crate::second;
+
+// loom's Mutex, shaped like parking_lot's: no poisoning to unwrap
+#[cfg(loom)]
+struct Mutex<T>(loom::sync::Mutex<T>);
+
+#[cfg(loom)]
+impl<T> Mutex<T> {
+    fn new(value: T) -> Self {
+        Self(loom::sync::Mutex::new(value))
+    }
+
+    fn lock(&self) -> loom::sync::MutexGuard<'_, T> {
+        self.0.lock().unwrap()
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut().unwrap()
+    }
+}
+
+struct Node<T> {
+    // uninit in the dummy
+    value: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn alloc(value: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            value,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+pub struct SharedDeque<T> {
+    // the dummy
+    head: Mutex<*mut Node<T>>,
+    // the last node: the dummy, when empty
+    tail: Mutex<*mut Node<T>>,
+    // updated after the fact, so only a snapshot
+    len: AtomicUsize,
+}
+
+// The Ts move in on one thread and out on another, and the raw pointers are
+// only followed under the lock for their end.
+unsafe impl<T: Send> Send for SharedDeque<T> {}
+unsafe impl<T: Send> Sync for SharedDeque<T> {}
+
+impl<T> SharedDeque<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        let dummy = Node::alloc(MaybeUninit::uninit());
+        Self {
+            head: Mutex::new(dummy),
+            tail: Mutex::new(dummy),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Takes the tail lock only.
+    pub fn push_back(&self, value: T) {
+        // allocate before locking, to keep the critical section to two stores
+        let node = Node::alloc(MaybeUninit::new(value));
+        let mut tail = self.tail.lock();
+        // Release: whoever pops this sees the value written
+        unsafe { (**tail).next.store(node, Ordering::Release) };
+        *tail = node;
+        drop(tail);
+        self.len.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Takes the head lock only.
+    pub fn pop_front(&self) -> Option<T> {
+        let mut head = self.head.lock();
+        let dummy = *head;
+        let first = unsafe { (*dummy).next.load(Ordering::Acquire) };
+        if first.is_null() {
+            return None;
+        }
+        // `first` becomes the dummy, so its value is ours to move out
+        let value = unsafe { (*first).value.assume_init_read() };
+        *head = first;
+        drop(head);
+        // nobody can reach the old dummy now: not the tail (that's at `first` or
+        // beyond) and not another popper (they start from the new dummy)
+        unsafe { drop(Box::from_raw(dummy)) };
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        Some(value)
+    }
+
+    /// How many elements there are, give or take the pushes and pops in
+    /// progress on other threads.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Clone> SharedDeque<T> {
+    /// Copies the queue, front first, with both ends locked: no push or pop
+    /// lands halfway through. The iteration itself happens after they're let go.
+    #[must_use]
+    pub fn snapshot_iter(&self) -> second::IntoIter<T> {
+        let head = self.head.lock();
+        let _tail = self.tail.lock();
+        let mut copy = second::List::new();
+        let mut end = copy.cursor_front_mut();
+        // with the tail locked, the last node's `next` stays null
+        let mut node = unsafe { (**head).next.load(Ordering::Acquire) };
+        while !node.is_null() {
+            unsafe {
+                end.insert_after((*node).value.assume_init_ref().clone());
+                node = (*node).next.load(Ordering::Acquire);
+            }
+        }
+        copy.into_iter()
+    }
+}
+
+impl<T> Default for SharedDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for SharedDeque<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+        unsafe { drop(Box::from_raw(*self.head.get_mut())) };
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::SharedDeque;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn basics() {
+        let deque = SharedDeque::new();
+        assert_eq!(deque.pop_front(), None);
+        deque.push_back(1);
+        deque.push_back(2);
+        assert_eq!(deque.len(), 2);
+        assert_eq!(deque.pop_front(), Some(1));
+        deque.push_back(3);
+        assert_eq!(deque.pop_front(), Some(2));
+        assert_eq!(deque.pop_front(), Some(3));
+        assert_eq!(deque.pop_front(), None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn drops_what_is_left() {
+        let value = Arc::new(());
+        let deque = SharedDeque::new();
+        deque.push_back(Arc::clone(&value));
+        deque.push_back(Arc::clone(&value));
+        drop(deque.pop_front());
+        drop(deque);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+
+    // With the head lock held (a popper stuck midway, say), a push still goes
+    // through. A single-lock wrapper would hang here.
+    #[test]
+    fn ends_do_not_contend() {
+        let deque = Arc::new(SharedDeque::new());
+        let head = deque.head.lock();
+
+        let (done, pushed) = mpsc::channel();
+        let pusher = thread::spawn({
+            let deque = Arc::clone(&deque);
+            move || {
+                deque.push_back(1);
+                done.send(()).unwrap();
+            }
+        });
+        assert!(pushed.recv_timeout(Duration::from_secs(10)).is_ok());
+        drop(head);
+        pusher.join().unwrap();
+        assert_eq!(deque.pop_front(), Some(1));
+    }
+
+    // Every push and pop moves the queue along by one: [i] becomes [i, i + 1]
+    // becomes [i + 1]. A snapshot only ever sees one of those.
+    #[test]
+    fn snapshots_see_whole_queues() {
+        let deque = SharedDeque::new();
+        deque.push_back(0);
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    let seen: Vec<_> = deque.snapshot_iter().collect();
+                    match seen[..] {
+                        [_] => {}
+                        [i, j] => assert_eq!(i + 1, j),
+                        _ => panic!("{seen:?}"),
+                    }
+                }
+            });
+            for i in 0..10_000 {
+                deque.push_back(i + 1);
+                assert_eq!(deque.pop_front(), Some(i));
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        assert!(deque.snapshot_iter().eq([10_000]));
+    }
+
+    #[test]
+    fn stress() {
+        const PRODUCERS: usize = 4;
+        const PER_PRODUCER: usize = 10_000;
+        let deque = SharedDeque::new();
+
+        thread::scope(|scope| {
+            for producer in 0..PRODUCERS {
+                let deque = &deque;
+                scope.spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        deque.push_back((producer, i));
+                    }
+                });
+            }
+
+            // two consumers; each producer's values still come out in order,
+            // as seen by any one consumer
+            let consumers: Vec<_> = (0..2)
+                .map(|_| {
+                    let deque = &deque;
+                    scope.spawn(move || {
+                        let mut last = [None; PRODUCERS];
+                        let mut popped = 0;
+                        while popped < PRODUCERS * PER_PRODUCER / 2 {
+                            let Some((producer, i)) = deque.pop_front() else {
+                                thread::yield_now();
+                                continue;
+                            };
+                            assert!(last[producer] < Some(i));
+                            last[producer] = Some(i);
+                            popped += 1;
+                        }
+                    })
+                })
+                .collect();
+            for consumer in consumers {
+                consumer.join().unwrap();
+            }
+        });
+        assert!(deque.is_empty());
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::SharedDeque;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    // A push and a pop race a snapshot of [0]. Whatever order they land in, the
+    // snapshot is a queue that really existed: [0] or [0, 1] or [1], if the push
+    // went first, and [0] or [] or [1], if the pop did.
+    #[test]
+    fn snapshot_sees_whole_queues() {
+        loom::model(|| {
+            let deque = Arc::new(SharedDeque::new());
+            deque.push_back(0);
+
+            let pusher = thread::spawn({
+                let deque = Arc::clone(&deque);
+                move || deque.push_back(1)
+            });
+            let popper = thread::spawn({
+                let deque = Arc::clone(&deque);
+                move || deque.pop_front()
+            });
+
+            let seen: Vec<_> = deque.snapshot_iter().collect();
+            assert!(matches!(seen[..], [0] | [0, 1] | [1] | []), "{seen:?}");
+            pusher.join().unwrap();
+            let popped = popper.join().unwrap();
+            assert_eq!(popped, Some(0));
+            assert!(deque.snapshot_iter().eq([1]));
+        });
+    }
+}
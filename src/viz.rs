@@ -0,0 +1,357 @@
+// Shared plumbing for dumping a list's structure.
+//
+// Every list knows how to walk its own nodes, and describes what it finds as a Graph: one entry
+// per node (keyed by address, so a node reachable twice still shows up once), the links between
+// them, and which nodes the list's head/tail point at. Rendering is then the same for all lists.
+
+use std::fmt::{Debug, Write};
+
+pub(crate) struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    markers: Vec<(&'static str, usize)>,
+    // named sets of nodes worth highlighting, e.g. the shared suffix of a persistent list
+    groups: Vec<(&'static str, Vec<usize>)>,
+    // whether the nodes have prev links at all, even if none are set right now
+    doubly_linked: bool,
+}
+
+struct Node {
+    id: usize,
+    label: String,
+    // only the Rc-based lists have one of these
+    strong_count: Option<usize>,
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    label: &'static str,
+}
+
+impl Graph {
+    pub(crate) const fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            markers: Vec::new(),
+            groups: Vec::new(),
+            doubly_linked: false,
+        }
+    }
+
+    pub(crate) fn node<N, T: Debug>(&mut self, node: *const N, value: &T) {
+        self.push_node(node as usize, format!("{value:?}"), None);
+    }
+
+    pub(crate) fn rc_node<N, T: Debug>(&mut self, node: *const N, value: &T, strong_count: usize) {
+        self.push_node(node as usize, format!("{value:?}"), Some(strong_count));
+    }
+
+    fn push_node(&mut self, id: usize, label: String, strong_count: Option<usize>) {
+        if self.nodes.iter().all(|node| node.id != id) {
+            self.nodes.push(Node {
+                id,
+                label,
+                strong_count,
+            });
+        }
+    }
+
+    pub(crate) fn edge<N>(&mut self, from: *const N, to: *const N, label: &'static str) {
+        self.edges.push(Edge {
+            from: from as usize,
+            to: to as usize,
+            label,
+        });
+    }
+
+    // For lists whose nodes have prev links: the ASCII art gets a prev row, even
+    // for a single node (whose prev is null, so there's no edge to go by).
+    pub(crate) fn doubly_linked(&mut self) {
+        self.doubly_linked = true;
+    }
+
+    // e.g. marker("head", self.head)
+    pub(crate) fn marker<N>(&mut self, name: &'static str, node: *const N) {
+        self.markers.push((name, node as usize));
+    }
+
+    pub(crate) fn group<N>(
+        &mut self,
+        name: &'static str,
+        nodes: impl IntoIterator<Item = *const N>,
+    ) {
+        let ids: Vec<usize> = nodes.into_iter().map(|node| node as usize).collect();
+        if !ids.is_empty() {
+            self.groups.push((name, ids));
+        }
+    }
+
+    pub(crate) fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph List {\n    rankdir=LR;\n    node [shape=box];\n");
+
+        for node in &self.nodes {
+            // escape first, so the \n we add afterwards stays a DOT newline
+            let label = with_strong_count(escape_dot(&node.label), node.strong_count, "\\n");
+            let _ = writeln!(dot, "    n{:x} [label=\"{label}\"];", node.id);
+        }
+
+        for edge in &self.edges {
+            let _ = writeln!(
+                dot,
+                "    n{:x} -> n{:x} [label=\"{}\"];",
+                edge.from, edge.to, edge.label
+            );
+        }
+
+        for (name, id) in &self.markers {
+            let _ = writeln!(dot, "    {name} [shape=plaintext];");
+            let _ = writeln!(dot, "    {name} -> n{id:x};");
+        }
+
+        // DOT only draws a box around subgraphs whose name starts with "cluster"
+        for (i, (name, ids)) in self.groups.iter().enumerate() {
+            let _ = writeln!(dot, "    subgraph cluster_{i} {{");
+            let _ = writeln!(dot, "        label=\"{name}\";");
+            for id in ids {
+                let _ = writeln!(dot, "        n{id:x};");
+            }
+            dot.push_str("    }\n");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub(crate) fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("graph LR\n");
+
+        for node in &self.nodes {
+            let label = with_strong_count(escape_mermaid(&node.label), node.strong_count, "<br>");
+            let _ = writeln!(mermaid, "    n{:x}[\"{label}\"]", node.id);
+        }
+
+        for edge in &self.edges {
+            let _ = writeln!(
+                mermaid,
+                "    n{:x} -->|{}| n{:x}",
+                edge.from, edge.label, edge.to
+            );
+        }
+
+        for (name, id) in &self.markers {
+            let _ = writeln!(mermaid, "    {name}([{name}]) --> n{id:x}");
+        }
+
+        // Mentioning an already-declared node inside a subgraph moves it in there.
+        for (name, ids) in &self.groups {
+            let _ = writeln!(mermaid, "    subgraph {name}");
+            for id in ids {
+                let _ = writeln!(mermaid, "        n{id:x}");
+            }
+            mermaid.push_str("    end\n");
+        }
+
+        mermaid
+    }
+
+    // For drawing somewhere other than a terminal: the same graph as data, ids as
+    // in the DOT and Mermaid dumps.
+    //
+    // {
+    //   "nodes": [{ "id": "n5581a0", "label": "2", "strong_count": null }, ...],
+    //   "links": [{ "from": "n5581a0", "to": "n5581c0", "kind": "next" }, ...],
+    //   "markers": { "head": "n5581a0", "tail": "n5581c0" },
+    //   "groups": [{ "name": "shared", "nodes": ["n5581c0"] }]
+    // }
+    pub(crate) fn to_json(&self) -> String {
+        use serde_json::{json, Map, Value};
+
+        let id = |id: usize| Value::from(format!("n{id:x}"));
+        let nodes: Vec<Value> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                json!({
+                    "id": id(node.id),
+                    "label": node.label,
+                    "strong_count": node.strong_count,
+                })
+            })
+            .collect();
+        let links: Vec<Value> = self
+            .edges
+            .iter()
+            .map(|edge| json!({ "from": id(edge.from), "to": id(edge.to), "kind": edge.label }))
+            .collect();
+        let markers: Map<String, Value> = self
+            .markers
+            .iter()
+            .map(|&(name, node)| (name.to_owned(), id(node)))
+            .collect();
+        let groups: Vec<Value> = self
+            .groups
+            .iter()
+            .map(|(name, ids)| json!({ "name": name, "nodes": ids.iter().map(|&node| id(node)).collect::<Vec<_>>() }))
+            .collect();
+
+        json!({ "nodes": nodes, "links": links, "markers": markers, "groups": groups }).to_string()
+    }
+
+    // HEAD -> [2] -> [5] -> ∅
+    //
+    // Doubly linked lists get a second row underneath, lined up with the first and
+    // drawn from the prev links the nodes really have:
+    //
+    // HEAD -> [1] -> [2] -> ∅
+    //    ∅ <- [1] <- [2] <- TAIL
+    //
+    // A prev that doesn't point at the node to its left shows up as `?-` instead
+    // of `<-`, and a first node with a prev at all as `?` instead of `∅`.
+    pub(crate) fn to_ascii(&self) -> String {
+        let cells: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|node| format!("[{}]", node.label))
+            .collect();
+
+        let mut ascii = String::from("HEAD -> ");
+        for cell in &cells {
+            let _ = write!(ascii, "{cell} -> ");
+        }
+        ascii.push_str("∅\n");
+
+        let tail = self
+            .markers
+            .iter()
+            .find_map(|&(name, id)| (name == "tail").then_some(id));
+        if self.doubly_linked {
+            let prev = |id: usize| {
+                self.edges
+                    .iter()
+                    .find(|edge| edge.from == id && edge.label == "prev")
+                    .map(|edge| edge.to)
+            };
+            // the node to the left, which is where each prev ought to point
+            let mut left = None;
+            ascii.push_str("   ");
+            for (node, cell) in self.nodes.iter().zip(&cells) {
+                let ok = prev(node.id) == left;
+                if left.is_none() {
+                    let _ = write!(ascii, "{} <- {cell}", if ok { "∅" } else { "?" });
+                } else {
+                    let _ = write!(ascii, " {} {cell}", if ok { "<-" } else { "?-" });
+                }
+                left = Some(node.id);
+            }
+            if left.is_none() {
+                ascii.push('∅');
+            }
+            let _ = writeln!(ascii, " {} TAIL", if tail == left { "<-" } else { "?-" });
+        } else if let Some(tail) = tail {
+            // wherever it really points, which should be the last node
+            let cell = self
+                .nodes
+                .iter()
+                .zip(&cells)
+                .find_map(|(node, cell)| (node.id == tail).then_some(cell.as_str()));
+            let _ = writeln!(ascii, "TAIL -> {}", cell.unwrap_or("?"));
+        }
+
+        ascii
+    }
+}
+
+fn with_strong_count(label: String, strong_count: Option<usize>, newline: &str) -> String {
+    match strong_count {
+        Some(strong_count) => format!("{label}{newline}strong={strong_count}"),
+        None => label,
+    }
+}
+
+// Debug output of strings/chars comes with quotes, which would end a DOT label early.
+fn escape_dot(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Mermaid has no backslash escapes, only entity codes, and the characters that
+// break a label are a long list that depends on where they land: `"` ends it, `#`
+// starts a code, `<` and `>` read as HTML, and `;`, backticks and newlines trip up
+// the parser. So everything but letters and digits goes in as a code: `"` is
+// #34;, `#` is #35;, and so on.
+fn escape_mermaid(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        if c.is_alphanumeric() {
+            escaped.push(c);
+        } else {
+            let _ = write!(escaped, "#{};", u32::from(c));
+        }
+    }
+    escaped
+}
+
+// Digs the DOT id of the node labelled `label` out of a dump, since ids are addresses.
+#[cfg(test)]
+pub(crate) fn dot_id(dot: &str, label: &str) -> String {
+    let plain = format!("[label=\"{label}\"]");
+    let with_count = format!("[label=\"{label}\\n");
+    dot.lines()
+        .find(|line| line.contains(&plain) || line.contains(&with_count))
+        .and_then(|line| line.split_whitespace().next())
+        .unwrap()
+        .to_owned()
+}
+
+// Same as dot_id, for Mermaid dumps.
+#[cfg(test)]
+pub(crate) fn mermaid_id(mermaid: &str, label: &str) -> String {
+    let plain = format!("[\"{label}\"]");
+    let with_count = format!("[\"{label}<br>");
+    mermaid
+        .lines()
+        .find_map(|line| {
+            let line = line.trim();
+            let end = line.find(&plain).or_else(|| line.find(&with_count))?;
+            Some(line[..end].to_owned())
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_mermaid, Graph};
+
+    // A node reached a second time (through a shared tail, or a cycle) keeps its
+    // first entry, and only gets the extra edge.
+    #[test]
+    fn nodes_are_keyed_by_address() {
+        let (a, b) = (1, 2);
+        let mut graph = Graph::new();
+        graph.node(&a, &a);
+        graph.node(&b, &b);
+        graph.edge(&a, &b, "next");
+        graph.node(&a, &"again");
+        graph.edge(&b, &a, "next");
+
+        let dot = graph.to_dot();
+        assert_eq!(dot.matches("[label=\"1\"]").count(), 1);
+        assert!(!dot.contains("again"));
+        assert_eq!(dot.matches("[label=\"next\"]").count(), 2);
+    }
+
+    #[test]
+    fn mermaid_labels_are_all_codes() {
+        assert_eq!(escape_mermaid("a1"), "a1");
+        assert_eq!(
+            escape_mermaid("\"<b>\"; #x`\n"),
+            "#34;#60;b#62;#34;#59;#32;#35;x#96;#10;"
+        );
+        // nothing left that Mermaid could read as syntax
+        let label = escape_mermaid("<script>\"]) --> evil[\"");
+        assert!(label
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '#' || c == ';'));
+    }
+}
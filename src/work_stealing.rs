@@ -0,0 +1,437 @@
+// The Chase-Lev work-stealing deque, in the C11 formulation of Lê, Pop, Cohen
+// and Zappa Nardelli. It's what task schedulers keep per worker thread: the
+// worker pushes and pops its own tasks at the bottom, like a stack, with no
+// compare-and-swap at all in the common case; idle threads steal from the top.
+// The only fight is over the last task, when the worker's pop and a steal both
+// go for it, and a CAS on `top` settles it.
+//
+// Not a linked list, for once: the tasks live in a circular buffer, indexed by
+// ever-increasing `top` and `bottom`. When it fills up the worker copies into
+// one twice the size. A stealer that read the old buffer's pointer a moment ago
+// may still be reading from it, though, and there's no telling when it's done.
+// So old buffers aren't freed until the whole deque is; each new buffer links
+// back to the one it replaced, and Drop walks that chain.
+//
+// A stealer reads its task out of the buffer *before* its CAS decides whether it
+// gets to keep it, so the read can race with the worker reusing the slot after a
+// wrap-around. The CAS fails whenever it raced, so the bytes are never used, but
+// a plain read racing a plain write is a data race all the same, and the
+// compiler is allowed to assume there are none. So every slot access is
+// volatile, as in crossbeam-deque: exactly the loads and stores written, each
+// done once, none merged, split or invented. Atomic loads per word would settle
+// it properly, but a T's padding is uninitialized and an atomic load has to come
+// back an integer; that needs the atomic memcpy Rust doesn't have yet. A volatile
+// read of a MaybeUninit leaves the padding uninitialized. Loom can't see
+// volatile accesses, so under loom the slots go unchecked.
+
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+#[cfg(not(loom))]
+use alloc::sync::Arc;
+#[cfg(not(loom))]
+use core::sync::atomic::{fence, AtomicIsize, AtomicPtr, Ordering};
+#[cfg(loom)]
+use loom::sync::atomic::{fence, AtomicIsize, AtomicPtr, Ordering};
+#[cfg(loom)]
+use loom::sync::Arc;
+
+struct Buffer<T> {
+    // a power of two, so an index wraps with a mask
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // the buffer this one replaced, kept for stealers that might still be in it
+    prev: *mut Buffer<T>,
+}
+
+impl<T> Buffer<T> {
+    fn alloc(capacity: usize, prev: *mut Self) -> *mut Self {
+        let slots = (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect();
+        Box::into_raw(Box::new(Self { slots, prev }))
+    }
+
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    #[allow(clippy::cast_sign_loss)] // indices start at 0 and only go up
+    fn slot(&self, index: isize) -> *mut MaybeUninit<T> {
+        self.slots[index as usize & (self.capacity() - 1)].get()
+    }
+
+    unsafe fn write(&self, index: isize, value: MaybeUninit<T>) {
+        self.slot(index).write_volatile(value);
+    }
+
+    // A copy of whatever's in the slot; see the top of the file for why it's
+    // volatile, and why that's fine even when it's mid-overwrite.
+    unsafe fn read(&self, index: isize) -> MaybeUninit<T> {
+        self.slot(index).read_volatile()
+    }
+}
+
+struct Inner<T> {
+    // stealers take from here
+    top: AtomicIsize,
+    // the worker pushes and pops here
+    bottom: AtomicIsize,
+    buffer: AtomicPtr<Buffer<T>>,
+}
+
+/// The worker's end. There's exactly one, and it stays on one thread at a time
+/// (it's Send, not Sync).
+pub struct WorkStealingDeque<T> {
+    inner: Arc<Inner<T>>,
+    // !Sync: push and pop assume nobody else is at the bottom
+    _not_sync: PhantomData<*mut ()>,
+}
+
+/// A thief. Clone as many as you like and hand them to other threads.
+pub struct Stealer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+unsafe impl<T: Send> Send for WorkStealingDeque<T> {}
+unsafe impl<T: Send> Send for Stealer<T> {}
+unsafe impl<T: Send> Sync for Stealer<T> {}
+
+/// What a steal came back with.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Steal<T> {
+    Success(T),
+    Empty,
+    /// Lost a race for the top task to another stealer or the worker. There may
+    /// well be more; try again.
+    Retry,
+}
+
+impl<T> WorkStealingDeque<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_capacity(32)
+    }
+
+    /// Starts with room for `capacity` tasks (rounded up to a power of two)
+    /// before the first grow.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let buffer = Buffer::alloc(capacity.max(1).next_power_of_two(), ptr::null_mut());
+        Self {
+            inner: Arc::new(Inner {
+                top: AtomicIsize::new(0),
+                bottom: AtomicIsize::new(0),
+                buffer: AtomicPtr::new(buffer),
+            }),
+            _not_sync: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        let inner = &*self.inner;
+        let bottom = inner.bottom.load(Ordering::Relaxed);
+        let top = inner.top.load(Ordering::Acquire);
+        let mut buffer = inner.buffer.load(Ordering::Relaxed);
+        unsafe {
+            #[allow(clippy::cast_sign_loss)] // bottom never falls behind top here
+            if (bottom - top) as usize >= (*buffer).capacity() {
+                buffer = self.grow(buffer, top, bottom);
+            }
+            (*buffer).write(bottom, MaybeUninit::new(value));
+        }
+        // the task has to be in the slot before a stealer can see bottom past it
+        fence(Ordering::Release);
+        inner.bottom.store(bottom + 1, Ordering::Relaxed);
+    }
+
+    // Only the worker ever writes `buffer`, so there's no racing another grow.
+    unsafe fn grow(&self, old: *mut Buffer<T>, top: isize, bottom: isize) -> *mut Buffer<T> {
+        let new = Buffer::alloc((*old).capacity() * 2, old);
+        for index in top..bottom {
+            (*new).write(index, (*old).read(index));
+        }
+        self.inner.buffer.store(new, Ordering::Release);
+        new
+    }
+
+    /// Pops the newest task: the bottom is a stack, as far as the worker's
+    /// concerned, which keeps what it works on next warm in its cache.
+    pub fn pop(&mut self) -> Option<T> {
+        let inner = &*self.inner;
+        let bottom = inner.bottom.load(Ordering::Relaxed) - 1;
+        let buffer = inner.buffer.load(Ordering::Relaxed);
+        // claim the bottom slot first, then look at top: stealers do it the
+        // other way round, and the SeqCst fences stop both missing each other
+        inner.bottom.store(bottom, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let top = inner.top.load(Ordering::Relaxed);
+
+        if top > bottom {
+            // it was empty
+            inner.bottom.store(bottom + 1, Ordering::Relaxed);
+            return None;
+        }
+        let value = unsafe { (*buffer).read(bottom) };
+        if top < bottom {
+            // more than one left, so no stealer can be after this one
+            return Some(unsafe { value.assume_init() });
+        }
+        // The last one: stealers may be going for it too. Whoever moves top
+        // past it gets it.
+        let won = inner
+            .top
+            .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok();
+        inner.bottom.store(bottom + 1, Ordering::Relaxed);
+        won.then(|| unsafe { value.assume_init() })
+    }
+
+    /// How many tasks there are. Stealers can make it stale right away.
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn len(&self) -> usize {
+        let bottom = self.inner.bottom.load(Ordering::Relaxed);
+        let top = self.inner.top.load(Ordering::Relaxed);
+        (bottom - top).max(0) as usize
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Default for WorkStealingDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Stealer<T> {
+    /// Takes the oldest task.
+    pub fn steal(&self) -> Steal<T> {
+        let inner = &*self.inner;
+        let top = inner.top.load(Ordering::Acquire);
+        fence(Ordering::SeqCst);
+        let bottom = inner.bottom.load(Ordering::Acquire);
+        if top >= bottom {
+            return Steal::Empty;
+        }
+
+        let buffer = inner.buffer.load(Ordering::Acquire);
+        let value = unsafe { (*buffer).read(top) };
+        // If top moved since we read it, someone else got this task (and maybe
+        // the slot's been reused since): what we read is garbage, and MaybeUninit
+        // drops nothing.
+        match inner
+            .top
+            .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+        {
+            Ok(_) => Steal::Success(unsafe { value.assume_init() }),
+            Err(_) => Steal::Retry,
+        }
+    }
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+// Runs once the worker and every stealer are gone.
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        // (loom's atomics have no get_mut, so plain loads; there's nobody else)
+        let top = self.top.load(Ordering::Relaxed);
+        let bottom = self.bottom.load(Ordering::Relaxed);
+        let mut buffer = self.buffer.load(Ordering::Relaxed);
+        unsafe {
+            for index in top..bottom {
+                (*buffer).read(index).assume_init_drop();
+            }
+            // the current buffer, then everything it replaced
+            while !buffer.is_null() {
+                let prev = (*buffer).prev;
+                drop(Box::from_raw(buffer));
+                buffer = prev;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::{Steal, WorkStealingDeque};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let mut deque = WorkStealingDeque::with_capacity(2);
+        let stealer = deque.stealer();
+        assert_eq!(deque.pop(), None);
+        assert_eq!(stealer.steal(), Steal::Empty);
+
+        // past the initial capacity, so it grows
+        for i in 0..5 {
+            deque.push(i);
+        }
+        assert_eq!(deque.len(), 5);
+        // the worker takes the newest, thieves the oldest
+        assert_eq!(deque.pop(), Some(4));
+        assert_eq!(stealer.steal(), Steal::Success(0));
+        assert_eq!(stealer.clone().steal(), Steal::Success(1));
+        assert_eq!(deque.pop(), Some(3));
+        assert_eq!(deque.pop(), Some(2));
+        assert_eq!(deque.pop(), None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn drops_what_is_left() {
+        let task = Arc::new(());
+        let mut deque = WorkStealingDeque::with_capacity(1);
+        for _ in 0..10 {
+            deque.push(Arc::clone(&task));
+        }
+        let stealer = deque.stealer();
+        drop(deque);
+        assert!(matches!(stealer.steal(), Steal::Success(_)));
+        drop(stealer);
+        assert_eq!(Arc::strong_count(&task), 1);
+    }
+
+    #[test]
+    fn every_task_runs_once() {
+        const TASKS: usize = 50_000;
+        let mut deque = WorkStealingDeque::<usize>::with_capacity(4);
+        let runs: Arc<Vec<AtomicUsize>> =
+            Arc::new((0..TASKS).map(|_| AtomicUsize::new(0)).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..3 {
+                let stealer = deque.stealer();
+                let runs = Arc::clone(&runs);
+                scope.spawn(move || {
+                    let mut empty_streak = 0;
+                    while empty_streak < 1000 {
+                        match stealer.steal() {
+                            Steal::Success(task) => {
+                                runs[task].fetch_add(1, Ordering::Relaxed);
+                                empty_streak = 0;
+                            }
+                            Steal::Retry => {}
+                            Steal::Empty => {
+                                empty_streak += 1;
+                                thread::yield_now();
+                            }
+                        }
+                    }
+                });
+            }
+
+            // the worker pushes everything, popping now and then
+            for task in 0..TASKS {
+                deque.push(task);
+                if task % 3 == 0 {
+                    if let Some(task) = deque.pop() {
+                        runs[task].fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+            while let Some(task) = deque.pop() {
+                runs[task].fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        assert!(runs.iter().all(|runs| runs.load(Ordering::Relaxed) == 1));
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::{Steal, WorkStealingDeque};
+    use loom::thread;
+
+    fn steal_until_settled(stealer: &super::Stealer<i32>) -> Option<i32> {
+        loop {
+            match stealer.steal() {
+                Steal::Success(value) => return Some(value),
+                Steal::Empty => return None,
+                Steal::Retry => thread::yield_now(),
+            }
+        }
+    }
+
+    // The one task, and the worker and a thief both after it: exactly one of
+    // them gets it.
+    #[test]
+    fn pop_races_steal_for_the_last_task() {
+        loom::model(|| {
+            let mut deque = WorkStealingDeque::with_capacity(2);
+            deque.push(1);
+            let stealer = deque.stealer();
+            let thief = thread::spawn(move || steal_until_settled(&stealer));
+
+            let popped = deque.pop();
+            let stolen = thief.join().unwrap();
+            assert_eq!(popped.into_iter().chain(stolen).collect::<Vec<_>>(), [1]);
+        });
+    }
+
+    // Two thieves after two tasks, while the worker pops: nobody gets one twice.
+    #[test]
+    fn two_thieves() {
+        loom::model(|| {
+            let mut deque = WorkStealingDeque::with_capacity(2);
+            deque.push(1);
+            deque.push(2);
+            let thieves: Vec<_> = (0..2)
+                .map(|_| {
+                    let stealer = deque.stealer();
+                    thread::spawn(move || steal_until_settled(&stealer))
+                })
+                .collect();
+
+            let mut got: Vec<i32> = deque.pop().into_iter().collect();
+            for thief in thieves {
+                got.extend(thief.join().unwrap());
+            }
+            got.extend(deque.pop());
+            got.sort_unstable();
+            assert_eq!(got, [1, 2]);
+        });
+    }
+
+    // The worker grows the buffer while a thief reads from the old one.
+    #[test]
+    fn steal_during_grow() {
+        loom::model(|| {
+            let mut deque = WorkStealingDeque::with_capacity(1);
+            deque.push(1);
+            let stealer = deque.stealer();
+            let thief = thread::spawn(move || steal_until_settled(&stealer));
+
+            deque.push(2);
+            let mut got: Vec<i32> = thief.join().unwrap().into_iter().collect();
+            while let Some(value) = deque.pop() {
+                got.push(value);
+            }
+            got.sort_unstable();
+            assert_eq!(got, [1, 2]);
+        });
+    }
+}
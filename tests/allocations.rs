@@ -0,0 +1,101 @@
+// Counts allocator calls, to hold the queue to what its docs promise about
+// allocating. Tests run on several threads at once, so each thread counts its
+// own.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use too_many_linked_lists::fifth::{List, WhenFull, MAX_BOUNDED_BLOCK};
+
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // (try_with: not while the thread's locals are being torn down)
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn clear_keeps_the_block() {
+    let mut buffer = List::with_capacity(100, WhenFull::Panic);
+    assert_eq!(allocations(|| (0..100).for_each(|i| buffer.push(i))), 1);
+    buffer.clear();
+    assert_eq!(allocations(|| (0..100).for_each(|i| buffer.push(i))), 0);
+
+    // too big for one block: the last one comes back, the rest don't
+    let n = 1_000_000;
+    let mut queue = List::with_capacity(n, WhenFull::Panic);
+    (0..n).for_each(|i| queue.push(i));
+    queue.clear();
+    assert_eq!(
+        allocations(|| (0..n).for_each(|i| queue.push(i))),
+        (n - MAX_BOUNDED_BLOCK).div_ceil(MAX_BOUNDED_BLOCK)
+    );
+}
+
+#[cfg(feature = "embedded")]
+#[test]
+fn embedded_queue_allocates_once() {
+    use too_many_linked_lists::embedded::Queue;
+
+    let rx = Queue::with_capacity(16);
+    assert_eq!(allocations(|| rx.push(0u8)), 1);
+    // what an ISR and the main loop do all day: never allocates again
+    let traffic = allocations(|| {
+        for byte in 0..=255 {
+            while rx.try_push(byte).is_err() {
+                rx.pop();
+            }
+            if byte % 3 == 0 {
+                rx.pop();
+            }
+        }
+    });
+    assert_eq!(traffic, 0);
+}
+
+// Splices relink: joining, splitting and draining never copy a node, however
+// long the pieces. The stats tests count links followed, which a splice that
+// copied instead would get past.
+#[test]
+fn splices_never_allocate() {
+    for n in [16, 4096] {
+        let filled = || {
+            let mut queue = List::new();
+            (0..n).for_each(|i| queue.push(i));
+            queue
+        };
+        let (pieces, other) = ([filled(), filled(), filled()], filled());
+
+        let mut joined = List::new();
+        assert_eq!(allocations(|| joined = List::concat(pieces)), 0);
+        assert_eq!(allocations(|| joined += other), 0);
+        let mut halves = None;
+        assert_eq!(allocations(|| halves = Some(joined.split_at(n))), 0);
+        let (mut front, back) = halves.unwrap();
+        assert_eq!(allocations(|| front.interleave(back)), 0);
+        assert_eq!(
+            allocations(|| assert_eq!(front.take_all().count(), 4 * n)),
+            0
+        );
+    }
+}
@@ -0,0 +1,10 @@
+// What the borrow checker has to turn away for the iterators and peeks to be
+// sound. Each case under tests/ui/ must fail to build, with the error in its
+// .stderr file. After a compiler upgrade changes the wording, regenerate them:
+//
+//     TRYBUILD=overwrite cargo test --test compile_fail
+
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
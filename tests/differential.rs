@@ -0,0 +1,279 @@
+// The same random script of operations, run against the Box stack (second.rs),
+// the deque (fourth.rs), the unsafe queue (fifth.rs) and the segmented queue
+// (segmented.rs, the crate's unrolled list: 32 elements to a node). Each list
+// runs the operations it has and skips the rest, and everything it hands back
+// (and everything in it, after every step) has to match what a VecDeque does
+// with the same operations. A list that's drifted from the others, say a pop_if
+// that checks the wrong end, fails here even if its own tests agree with it.
+//
+// proptest shrinks a failure down to the shortest script that still shows it.
+
+use std::collections::VecDeque;
+use std::mem;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+use too_many_linked_lists::{fifth, fourth, second, segmented};
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    PushFront(i32),
+    PushBack(i32),
+    PopFront,
+    PopBack,
+    // pops the front if it's less than this
+    PopFrontIf(i32),
+    PeekFront,
+    PeekBack,
+    // through peek_mut
+    AddToFront(i32),
+    Len,
+    IsEmpty,
+    Clear,
+    Reverse,
+    ReverseInGroups(usize, bool),
+}
+
+// What an operation handed back.
+#[derive(Debug, PartialEq)]
+enum Seen {
+    Nothing,
+    Elem(Option<i32>),
+    Len(usize),
+    IsEmpty(bool),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let elem = 0..50;
+    prop_oneof![
+        16 => elem.clone().prop_map(Op::PushFront),
+        16 => elem.clone().prop_map(Op::PushBack),
+        8 => Just(Op::PopFront),
+        8 => Just(Op::PopBack),
+        4 => elem.clone().prop_map(Op::PopFrontIf),
+        4 => Just(Op::PeekFront),
+        4 => Just(Op::PeekBack),
+        4 => elem.prop_map(Op::AddToFront),
+        4 => Just(Op::Len),
+        4 => Just(Op::IsEmpty),
+        4 => Just(Op::Reverse),
+        4 => (1..5usize, any::<bool>()).prop_map(|(k, rest)| Op::ReverseInGroups(k, rest)),
+        // rarely, or nothing would ever get long enough to span two segments
+        1 => Just(Op::Clear),
+    ]
+}
+
+// What every list is held to.
+fn model(deque: &mut VecDeque<i32>, op: Op) -> Seen {
+    match op {
+        Op::PushFront(elem) => deque.push_front(elem),
+        Op::PushBack(elem) => deque.push_back(elem),
+        Op::PopFront => return Seen::Elem(deque.pop_front()),
+        Op::PopBack => return Seen::Elem(deque.pop_back()),
+        Op::PopFrontIf(below) => {
+            return Seen::Elem(deque.pop_front_if(|&mut front| front < below));
+        }
+        Op::PeekFront => return Seen::Elem(deque.front().copied()),
+        Op::PeekBack => return Seen::Elem(deque.back().copied()),
+        Op::AddToFront(n) => {
+            return Seen::Elem(deque.front_mut().map(|front| {
+                *front += n;
+                *front
+            }));
+        }
+        Op::Len => return Seen::Len(deque.len()),
+        Op::IsEmpty => return Seen::IsEmpty(deque.is_empty()),
+        Op::Clear => deque.clear(),
+        Op::Reverse => deque.make_contiguous().reverse(),
+        Op::ReverseInGroups(k, reverse_rest) => {
+            let mut elems = Vec::from(mem::take(deque));
+            for group in elems.chunks_mut(k) {
+                if group.len() == k || reverse_rest {
+                    group.reverse();
+                }
+            }
+            *deque = elems.into();
+        }
+    }
+    Seen::Nothing
+}
+
+trait Subject: Default {
+    const NAME: &'static str;
+
+    /// None if this list doesn't do `op`.
+    fn apply(&mut self, op: Op) -> Option<Seen>;
+
+    fn contents(&self) -> Vec<i32>;
+}
+
+impl Subject for second::List<i32> {
+    const NAME: &'static str = "second";
+
+    fn apply(&mut self, op: Op) -> Option<Seen> {
+        Some(match op {
+            Op::PushFront(elem) => {
+                self.push_front(elem);
+                Seen::Nothing
+            }
+            Op::PopFront => Seen::Elem(self.pop_front()),
+            Op::PopFrontIf(below) => Seen::Elem(self.pop_front_if(|&front| front < below)),
+            Op::PeekFront => Seen::Elem(self.peek().copied()),
+            Op::Len => Seen::Len(self.len()),
+            Op::IsEmpty => Seen::IsEmpty(self.is_empty()),
+            Op::Clear => {
+                self.clear();
+                Seen::Nothing
+            }
+            Op::ReverseInGroups(k, reverse_rest) => {
+                self.reverse_in_groups(k, reverse_rest);
+                Seen::Nothing
+            }
+            _ => return None,
+        })
+    }
+
+    fn contents(&self) -> Vec<i32> {
+        self.iter().copied().collect()
+    }
+}
+
+impl Subject for fourth::List<i32> {
+    const NAME: &'static str = "fourth";
+
+    fn apply(&mut self, op: Op) -> Option<Seen> {
+        Some(match op {
+            Op::PushFront(elem) => {
+                self.push_front(elem);
+                Seen::Nothing
+            }
+            Op::PushBack(elem) => {
+                self.push_back(elem);
+                Seen::Nothing
+            }
+            Op::PopFront => Seen::Elem(self.pop_front()),
+            Op::PopBack => Seen::Elem(self.pop_back()),
+            Op::PopFrontIf(below) => Seen::Elem(self.pop_front_if(|&front| front < below)),
+            Op::PeekFront => Seen::Elem(self.peek_front().map(|front| *front)),
+            Op::PeekBack => Seen::Elem(self.peek_back().map(|back| *back)),
+            Op::AddToFront(n) => Seen::Elem(self.peek_front_mut().map(|mut front| {
+                *front += n;
+                *front
+            })),
+            // the deque doesn't count itself, but its std-style face will
+            Op::Len => Seen::Len(self.as_std_compat().len()),
+            Op::IsEmpty => Seen::IsEmpty(self.as_std_compat().is_empty()),
+            Op::Clear => {
+                self.clear();
+                Seen::Nothing
+            }
+            Op::Reverse => {
+                self.reverse();
+                Seen::Nothing
+            }
+            Op::ReverseInGroups(..) => return None,
+        })
+    }
+
+    fn contents(&self) -> Vec<i32> {
+        self.clone().into()
+    }
+}
+
+impl Subject for fifth::List<i32> {
+    const NAME: &'static str = "fifth";
+
+    fn apply(&mut self, op: Op) -> Option<Seen> {
+        Some(match op {
+            Op::PushBack(elem) => {
+                self.push(elem);
+                Seen::Nothing
+            }
+            Op::PopFront => Seen::Elem(self.pop()),
+            Op::PopFrontIf(below) => Seen::Elem(self.pop_if(|&front| front < below)),
+            Op::PeekFront => Seen::Elem(self.peek().copied()),
+            Op::AddToFront(n) => Seen::Elem(self.peek_mut().map(|front| {
+                *front += n;
+                *front
+            })),
+            Op::Len => Seen::Len(self.len()),
+            Op::IsEmpty => Seen::IsEmpty(self.is_empty()),
+            Op::Clear => {
+                self.clear();
+                Seen::Nothing
+            }
+            Op::ReverseInGroups(k, reverse_rest) => {
+                self.reverse_in_groups(k, reverse_rest);
+                Seen::Nothing
+            }
+            _ => return None,
+        })
+    }
+
+    fn contents(&self) -> Vec<i32> {
+        self.iter().copied().collect()
+    }
+}
+
+impl Subject for segmented::Queue<i32> {
+    const NAME: &'static str = "segmented";
+
+    fn apply(&mut self, op: Op) -> Option<Seen> {
+        Some(match op {
+            Op::PushBack(elem) => {
+                self.push(elem);
+                Seen::Nothing
+            }
+            Op::PopFront => Seen::Elem(self.pop()),
+            Op::PeekFront => Seen::Elem(self.peek().copied()),
+            Op::AddToFront(n) => Seen::Elem(self.peek_mut().map(|front| {
+                *front += n;
+                *front
+            })),
+            Op::Len => Seen::Len(self.len()),
+            Op::IsEmpty => Seen::IsEmpty(self.is_empty()),
+            Op::Clear => {
+                self.clear();
+                Seen::Nothing
+            }
+            _ => return None,
+        })
+    }
+
+    fn contents(&self) -> Vec<i32> {
+        self.iter().copied().collect()
+    }
+}
+
+fn run<L: Subject>(script: &[Op]) -> Result<(), TestCaseError> {
+    let mut list = L::default();
+    let mut deque = VecDeque::new();
+    for (step, &op) in script.iter().enumerate() {
+        let Some(seen) = list.apply(op) else {
+            continue;
+        };
+        let expected = model(&mut deque, op);
+        prop_assert_eq!(seen, expected, "{} at step {}, {:?}", L::NAME, step, op);
+        prop_assert!(
+            list.contents().iter().eq(&deque),
+            "{} at step {}, {:?}: holds {:?}, should hold {:?}",
+            L::NAME,
+            step,
+            op,
+            list.contents(),
+            deque
+        );
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn same_script_same_behavior(script in vec(op(), 0..300)) {
+        run::<second::List<i32>>(&script)?;
+        run::<fourth::List<i32>>(&script)?;
+        run::<fifth::List<i32>>(&script)?;
+        run::<segmented::Queue<i32>>(&script)?;
+    }
+}
@@ -0,0 +1,254 @@
+// The unsafe lists' worst days, at full size, for AddressSanitizer and
+// LeakSanitizer. Miri checks these paths more strictly, but it's far too slow for
+// a hundred thousand nodes or a block allocator that's been split and spliced a
+// few times over. ASan catches use-after-free, double frees and overruns as they
+// happen, and LSan (on by default with ASan on Linux) fails the run if anything's
+// still allocated at exit. Needs nightly:
+//
+//     RUSTFLAGS="-Zsanitizer=address --cfg sanitize_tests" \
+//         cargo +nightly test --test sanitize --target x86_64-unknown-linux-gnu
+//
+// The explicit --target keeps build scripts and proc macros out of it. Without
+// --cfg sanitize_tests this file is empty, so normal test runs skip it.
+
+#![cfg(sanitize_tests)]
+
+use std::cell::Cell;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+use too_many_linked_lists::{fifth, fourth, second};
+
+const N: usize = 100_000;
+
+// An element that knows how many of its kind are alive, and can be told to
+// panic on a given clone.
+struct Tracked {
+    value: usize,
+    pool: Rc<Pool>,
+}
+
+#[derive(Default)]
+struct Pool {
+    live: Cell<usize>,
+    // clones left before one panics
+    clones_left: Cell<Option<usize>>,
+}
+
+impl Pool {
+    fn make(self: &Rc<Self>, value: usize) -> Tracked {
+        self.live.set(self.live.get() + 1);
+        Tracked {
+            value,
+            pool: Rc::clone(self),
+        }
+    }
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Self {
+        if let Some(left) = self.pool.clones_left.get() {
+            assert!(left > 0, "clone budget used up");
+            self.pool.clones_left.set(Some(left - 1));
+        }
+        self.pool.make(self.value)
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.pool.live.set(self.pool.live.get() - 1);
+    }
+}
+
+fn queue(block_size: usize, n: usize) -> fifth::List<usize> {
+    let mut queue = fifth::List::with_block_size(block_size);
+    for i in 0..n {
+        queue.push(i);
+    }
+    queue
+}
+
+#[test]
+fn double_split() {
+    for block_size in [1, 16] {
+        let (front, back) = queue(block_size, N).split_at(N / 2);
+        // split the halves again, at the ends as well as the middle
+        let (a, b) = front.split_at(0);
+        let (c, d) = back.split_at(N / 2);
+        let (b, e) = b.split_at(N / 4);
+        // put the pieces back in order, the empty ones included...
+        let mut joined = fifth::List::concat([a, b, e, c, d]);
+        assert_eq!(joined.len(), N);
+        // ...and make sure the tail's right by pushing past it
+        joined.push(N);
+        assert!(joined.iter().copied().eq(0..=N));
+
+        // pieces dropped out of order, each freeing nodes that share blocks
+        // with the others
+        let (front, back) = joined.split_at(N / 3);
+        let (middle, back) = back.split_at(N / 3);
+        drop(middle);
+        drop(front);
+        drop(back);
+    }
+
+    let mut list = second::List::new();
+    for i in (0..N).rev() {
+        list.push_front(i);
+    }
+    let (front, back) = list.split_at(N / 2);
+    let (b, c) = back.split_at(N / 2);
+    let (a, empty) = front.split_at(N / 2);
+    let joined = second::List::concat([empty, a, b, c]);
+    assert!(joined.iter().copied().eq(0..N));
+
+    let mut deque = fourth::List::new();
+    deque.extend_back(0..N);
+    let mut back = deque.split_off(N / 2);
+    let mut end = back.split_off(N / 4);
+    // at the very end: nothing to hand over
+    assert!(end.split_off(N / 4).pop_front().is_none());
+    deque += back;
+    deque += end;
+    assert!(deque.into_iter().eq(0..N));
+}
+
+// Appending a list to itself doesn't compile (it's moved into the call and
+// borrowed by it at once), so these are the closest safe code can get: taking
+// the list out and appending it back, and appending it to a copy of itself.
+#[test]
+fn append_to_self() {
+    for block_size in [1, 16] {
+        let mut queue = queue(block_size, N);
+        let taken = mem::take(&mut queue);
+        queue += taken;
+        queue.push(N);
+        assert_eq!(queue.len(), N + 1);
+
+        let copy = queue.clone();
+        queue += copy;
+        assert_eq!(queue.len(), 2 * (N + 1));
+        assert!(queue.iter().copied().eq((0..=N).chain(0..=N)));
+
+        let taken = mem::take(&mut queue);
+        queue.interleave(taken);
+        queue.push(0);
+        assert_eq!(queue.len(), 2 * (N + 1) + 1);
+    }
+
+    let mut list = second::List::new();
+    for i in 0..N {
+        list.push_front(i);
+    }
+    let taken = mem::take(&mut list);
+    list.interleave(taken);
+    let copy = list.snapshot();
+    let mut other = second::List::new();
+    other.restore(copy);
+    list.interleave(other);
+    assert_eq!(list.len(), 2 * N);
+
+    let mut deque = fourth::List::new();
+    deque.extend_back(0..N);
+    let taken = mem::take(&mut deque);
+    deque += taken;
+    let copy = deque.clone();
+    deque += copy;
+    assert!(deque.into_iter().eq((0..N).chain(0..N)));
+}
+
+// Draining iterators dropped partway must free what they didn't hand out, and
+// leave the list they came from whole.
+#[test]
+fn drop_during_drain() {
+    let pool = Rc::new(Pool::default());
+    for block_size in [1, 16] {
+        let mut queue = fifth::List::with_block_size(block_size);
+        for i in 0..N {
+            queue.push(pool.make(i));
+        }
+        let mut drain = queue.take_all();
+        assert_eq!(drain.nth(N / 2).map(|elem| elem.value), Some(N / 2));
+        drop(drain);
+        assert_eq!(pool.live.get(), 0);
+        // the queue it was taken from is empty, and still works
+        queue.push(pool.make(0));
+        assert_eq!(queue.len(), 1);
+
+        for i in 1..N {
+            queue.push(pool.make(i));
+        }
+        let mut into_iter = queue.into_iter();
+        into_iter.next();
+        drop(into_iter);
+        assert_eq!(pool.live.get(), 0);
+
+        // retain drops as it goes, halfway through its own walk
+        let mut queue = fifth::List::with_block_size(block_size);
+        for i in 0..N {
+            queue.push(pool.make(i));
+        }
+        queue.retain(|elem| elem.value % 3 == 0);
+        assert_eq!(pool.live.get(), N.div_ceil(3));
+        queue.push(pool.make(N));
+        drop(queue);
+        assert_eq!(pool.live.get(), 0);
+    }
+
+    let mut deque = fourth::List::new();
+    deque.extend_back((0..N).map(|i| pool.make(i)));
+    let mut odd = deque.extract_if(|elem| elem.value % 2 == 1);
+    assert_eq!(odd.nth(10).map(|elem| elem.value), Some(21));
+    drop(odd);
+    // extract_if stops where it was dropped, like std's
+    assert_eq!(pool.live.get(), N - 11);
+    deque.push_back(pool.make(N));
+    drop(deque);
+    assert_eq!(pool.live.get(), 0);
+}
+
+// A panic halfway through a clone must drop the half-built copy, and leave the
+// original alone.
+#[test]
+fn panic_during_clone() {
+    let pool = Rc::new(Pool::default());
+    for block_size in [1, 16] {
+        let mut queue = fifth::List::with_block_size(block_size);
+        for i in 0..N {
+            queue.push(pool.make(i));
+        }
+        pool.clones_left.set(Some(N / 2));
+        let cloned = panic::catch_unwind(AssertUnwindSafe(|| queue.clone()));
+        assert!(cloned.is_err());
+        assert_eq!(pool.live.get(), N);
+        pool.clones_left.set(None);
+        assert!(queue.iter().map(|elem| elem.value).eq(0..N));
+        queue.push(pool.make(N));
+
+        // and a predicate panicking halfway through retain leaves a queue that
+        // can still be used and freed
+        let retained = panic::catch_unwind(AssertUnwindSafe(|| {
+            queue.retain(|elem| {
+                assert!(elem.value < N / 2, "halfway");
+                elem.value % 2 == 0
+            });
+        }));
+        assert!(retained.is_err());
+        assert_eq!(queue.iter().count(), queue.len());
+        queue.push(pool.make(N + 1));
+        drop(queue);
+        assert_eq!(pool.live.get(), 0);
+    }
+
+    let mut deque = fourth::List::new();
+    deque.extend_back((0..N).map(|i| pool.make(i)));
+    pool.clones_left.set(Some(N - 1));
+    let cloned = panic::catch_unwind(AssertUnwindSafe(|| deque.clone()));
+    assert!(cloned.is_err());
+    assert_eq!(pool.live.get(), N);
+    pool.clones_left.set(None);
+    drop(deque);
+    assert_eq!(pool.live.get(), 0);
+}
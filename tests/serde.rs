@@ -0,0 +1,101 @@
+// Every list serializes as a plain sequence, front to back, so each one has to
+// come out exactly as a Vec of the same elements would, and read back into the
+// same order: through JSON, and through bincode, which wants the length up front.
+
+#![cfg(feature = "serde")]
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use too_many_linked_lists::{fifth, first, fourth, second, third};
+
+// ascending, descending, jumbled, single and empty
+const CASES: [&[i32]; 5] = [&[1, 2, 3], &[3, 2, 1], &[2, 7, 1, 8], &[42], &[]];
+
+// Builds each case with `build`, round-trips it, and reads it back with `contents`.
+fn round_trip<L: Serialize + DeserializeOwned>(
+    build: impl Fn(&[i32]) -> L,
+    contents: impl Fn(L) -> Vec<i32>,
+) {
+    for elems in CASES {
+        let list = build(elems);
+
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(json, serde_json::to_string(elems).unwrap());
+        assert_eq!(contents(serde_json::from_str(&json).unwrap()), elems);
+
+        let bytes = bincode::serialize(&list).unwrap();
+        assert_eq!(bytes, bincode::serialize(elems).unwrap());
+        assert_eq!(contents(bincode::deserialize(&bytes).unwrap()), elems);
+    }
+}
+
+#[test]
+fn first() {
+    round_trip(
+        |elems| {
+            let mut list = first::List::new();
+            for &elem in elems.iter().rev() {
+                list.push_front(elem);
+            }
+            list
+        },
+        |mut list| std::iter::from_fn(|| list.pop_front()).collect(),
+    );
+}
+
+#[test]
+fn second() {
+    round_trip(
+        |elems| {
+            let mut list = second::List::new();
+            for &elem in elems.iter().rev() {
+                list.push_front(elem);
+            }
+            list
+        },
+        |list| list.into_iter().collect(),
+    );
+}
+
+#[test]
+fn third() {
+    round_trip(
+        |elems| {
+            elems
+                .iter()
+                .rev()
+                .fold(third::List::new(), |list, &elem| list.prepend(elem))
+        },
+        |list| {
+            std::iter::successors(Some(list), |list| Some(list.tail()))
+                .map_while(|list| list.head().copied())
+                .collect()
+        },
+    );
+}
+
+#[test]
+fn fourth() {
+    round_trip(
+        |elems| {
+            let mut list = fourth::List::new();
+            for &elem in elems {
+                list.push_back(elem);
+            }
+            list
+        },
+        |list| list.into_iter().collect(),
+    );
+}
+
+#[test]
+fn fifth() {
+    round_trip(
+        |elems| {
+            let mut list = fifth::List::new();
+            list.push_slice(elems);
+            list
+        },
+        |list| list.into_iter().collect(),
+    );
+}
@@ -0,0 +1,11 @@
+// A live Iter keeps the queue from being pushed to (which rewrites the tail
+// node's next pointer) under it.
+use too_many_linked_lists::fifth::List;
+
+fn main() {
+    let mut queue = List::new();
+    queue.push(1);
+    let mut iter = queue.iter();
+    queue.push(2);
+    iter.next();
+}
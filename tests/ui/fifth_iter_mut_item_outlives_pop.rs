@@ -0,0 +1,11 @@
+// An element borrowed through IterMut can't be held while it's popped (and its
+// node freed).
+use too_many_linked_lists::fifth::List;
+
+fn main() {
+    let mut queue = List::new();
+    queue.push(1);
+    let first = queue.iter_mut().next().unwrap();
+    queue.pop();
+    *first += 1;
+}
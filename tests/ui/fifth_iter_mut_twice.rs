@@ -0,0 +1,11 @@
+// Two IterMuts at once would hand out two &mut to the same node.
+use too_many_linked_lists::fifth::List;
+
+fn main() {
+    let mut queue = List::new();
+    queue.push(1);
+    let mut a = queue.iter_mut();
+    let mut b = queue.iter_mut();
+    *a.next().unwrap() += 1;
+    *b.next().unwrap() += 1;
+}
@@ -0,0 +1,11 @@
+// No reading through Iter while IterMut might be writing.
+use too_many_linked_lists::fifth::List;
+
+fn main() {
+    let mut queue = List::new();
+    queue.push(1);
+    let mut writer = queue.iter_mut();
+    let reader = queue.iter();
+    *writer.next().unwrap() += 1;
+    reader.count();
+}
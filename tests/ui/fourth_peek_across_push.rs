@@ -0,0 +1,12 @@
+// A Ref from peek_front can't be held across push_front, which would rewire the
+// node it points into. (Across peek_front_mut, which only takes &self, it
+// compiles and the RefCell panics instead: see fourth.rs's tests.)
+use too_many_linked_lists::fourth::List;
+
+fn main() {
+    let mut list = List::new();
+    list.push_front(1);
+    let front = list.peek_front().unwrap();
+    list.push_front(2);
+    assert_eq!(*front, 1);
+}
@@ -0,0 +1,10 @@
+// A live Iter keeps the stack from being changed under it.
+use too_many_linked_lists::second::List;
+
+fn main() {
+    let mut list = List::new();
+    list.push_front(1);
+    let mut iter = list.iter();
+    list.push_front(2);
+    iter.next();
+}
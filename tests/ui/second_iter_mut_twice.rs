@@ -0,0 +1,11 @@
+// Two IterMuts at once would hand out two &mut to the same node.
+use too_many_linked_lists::second::List;
+
+fn main() {
+    let mut list = List::new();
+    list.push_front(1);
+    let mut a = list.iter_mut();
+    let mut b = list.iter_mut();
+    *a.next().unwrap() += 1;
+    *b.next().unwrap() += 1;
+}
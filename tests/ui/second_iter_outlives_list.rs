@@ -0,0 +1,12 @@
+// Elements borrowed through Iter can't outlive the stack.
+use too_many_linked_lists::second::List;
+
+fn main() {
+    let first;
+    {
+        let mut list = List::new();
+        list.push_front(1);
+        first = list.iter().next();
+    }
+    println!("{first:?}");
+}
@@ -0,0 +1,10 @@
+// A live Iter keeps the queue from popping (and freeing a segment) under it.
+use too_many_linked_lists::segmented::Queue;
+
+fn main() {
+    let mut queue = Queue::new();
+    queue.push(1);
+    let mut iter = queue.iter();
+    queue.pop();
+    iter.next();
+}